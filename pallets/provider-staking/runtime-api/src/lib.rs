@@ -0,0 +1,35 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Runtime API for querying compute providers.
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_provider_staking::{HardwareRequirements, ProviderStatus, ReachableProvider, TaskEarnings};
+
+sp_api::decl_runtime_apis! {
+    pub trait ProviderStakingApi<AccountId>
+    where
+        AccountId: Codec,
+    {
+        /// Providers with at least one endpoint whose status and hardware match the filters
+        fn reachable_providers(
+            status: Option<ProviderStatus>,
+            requirements: HardwareRequirements,
+        ) -> Vec<ReachableProvider<AccountId>>;
+
+        /// Best matching active or idle providers, highest GPU tier and reputation first
+        fn top_providers(requirements: HardwareRequirements, count: u32) -> Vec<AccountId>;
+    }
+
+    pub trait ProviderEarningsApi<AccountId, Hash, Balance, BlockNumber>
+    where
+        AccountId: Codec,
+        Hash: Codec,
+        Balance: Codec,
+        BlockNumber: Codec,
+    {
+        /// Every task a provider earned from, with its work and native earnings on each
+        fn earnings_history(provider: AccountId) -> Vec<(Hash, TaskEarnings<Balance, BlockNumber>)>;
+    }
+}
@@ -4,36 +4,174 @@ use codec::DecodeWithMemTracking;
 use frame_support::{
     dispatch::DispatchResult,
     pallet_prelude::*,
-    traits::{Currency, ReservableCurrency, Imbalance},
-    BoundedVec,
+    traits::{
+        fungible::{self, Balanced, Inspect, Mutate, MutateHold},
+        tokens::{Fortitude, Precision, Preservation, Restriction},
+        Imbalance, OnUnbalanced, Randomness,
+    },
+    storage::StoragePrefixedMap,
+    PalletId, BoundedVec,
 };
 use frame_system::pallet_prelude::*;
+use scale_info::prelude::vec::Vec;
 use sp_runtime::{
-    traits::{Saturating, Zero},
-    Percent,
+    transaction_validity::{
+        InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity, ValidTransaction,
+    },
+    KeyTypeId, RuntimeAppPublic,
+    traits::{AccountIdConversion, Bounded, Hash as HashT, One, SaturatedConversion, Saturating, Zero},
+    Perbill, Percent,
 };
 
 pub use pallet::*;
 
-type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+pub mod migrations;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+/// Key type of provider session keys
+pub const PROVIDER_KEY_TYPE: KeyTypeId = KeyTypeId(*b"gprv");
+
+/// Custom validity error: the gradient commitment's round is above `MaxGradientRounds`
+pub const UNSIGNED_ROUND_OUT_OF_RANGE: u8 = 1;
+
+/// Custom validity error: the provider has not joined the task or the task is not running
+pub const UNSIGNED_NOT_ACTIVE_PROVIDER: u8 = 2;
+
+/// Custom validity error: the challenge does not exist or was issued to another provider
+pub const UNSIGNED_NOT_CHALLENGED_PROVIDER: u8 = 3;
+
+/// Provider session key crypto; the key signs heartbeats, gradient commitments and results
+pub mod crypto {
+    use sp_runtime::app_crypto::{app_crypto, sr25519};
+    app_crypto!(sr25519, super::PROVIDER_KEY_TYPE);
+}
+
+type BalanceOf<T> = <<T as Config>::Currency as fungible::Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+type CreditOf<T> = fungible::Credit<<T as frame_system::Config>::AccountId, <T as Config>::Currency>;
+
+/// Read access to tasks, implemented by the task registry
+pub trait TaskInspect<AccountId, Hash, Balance> {
+    /// Creator of a task and its bounty in the native currency, if the task exists
+    fn creator_and_bounty(task_id: &Hash) -> Option<(AccountId, Balance)>;
+
+    /// Whether a provider has joined a task that is currently running
+    fn is_active_provider(task_id: &Hash, who: &AccountId) -> bool;
+}
+
+impl<AccountId, Hash, Balance> TaskInspect<AccountId, Hash, Balance> for () {
+    fn creator_and_bounty(_task_id: &Hash) -> Option<(AccountId, Balance)> {
+        None
+    }
+
+    fn is_active_provider(_task_id: &Hash, _who: &AccountId) -> bool {
+        false
+    }
+}
+
+/// Read access to providers, implemented by the provider staking pallet
+pub trait ProviderInspect<AccountId> {
+    /// Declared hardware of a registered provider
+    fn hardware(who: &AccountId) -> Option<HardwareInfo>;
+
+    /// Available providers that meet the requirements and their tier's minimum stake, best first
+    fn candidates(requirements: &HardwareRequirements, count: u32) -> Vec<AccountId>;
+
+    /// Worst-case weight of a `candidates` query for `count` providers
+    fn candidates_weight(count: u32) -> Weight;
+}
+
+/// Payout preferences of providers, consumed when settling rewards
+pub trait RewardSettings<AccountId, Balance> {
+    /// Where a provider's rewards are paid
+    fn payee(who: &AccountId) -> RewardDestination<AccountId>;
+
+    /// Share of a provider's rewards it keeps before the delegator share is split off
+    fn commission(who: &AccountId) -> Perbill;
+
+    /// Move rewards already paid to a provider into its stake
+    fn restake(who: &AccountId, amount: Balance) -> DispatchResult;
+
+    /// Whether a provider has its rewards added to its stake as they settle
+    fn auto_compound(who: &AccountId) -> bool;
+
+    /// Move settled native rewards from `source` straight into a provider's stake
+    fn compound(who: &AccountId, source: &AccountId, amount: Balance) -> DispatchResult;
+}
+
+/// Gradient contributions and slashes of providers, consumed by per-round payment streams
+pub trait ContributionInspect<AccountId, Hash> {
+    /// Whether `who` committed a gradient for `round` of a task
+    fn contributed(task_id: &Hash, who: &AccountId, round: u32) -> bool;
+
+    /// Number of slashes applied to `who` so far; cancelled slashes are not counted
+    fn applied_slash_count(who: &AccountId) -> SlashIndex;
+}
+
+/// Work and earnings counters of providers, updated by reward distribution as rewards settle
+pub trait ProviderStats<AccountId, Hash, Balance> {
+    /// Record a provider's settled reward for a completed task, with the gradients it computed
+    /// and its native earnings
+    fn on_reward_settled(who: &AccountId, task_id: &Hash, gradients: u64, earned: Balance);
+
+    /// Record native earnings paid to a provider while a task is still running
+    fn on_stream_paid(who: &AccountId, task_id: &Hash, earned: Balance);
+}
+
+/// Handler notified after a provider's stake was slashed, implemented by reward distribution
+pub trait OnProviderSlashed<AccountId> {
+    /// Called once the slash has been applied and recorded
+    fn on_provider_slashed(who: &AccountId);
+}
+
+impl<AccountId> OnProviderSlashed<AccountId> for () {
+    fn on_provider_slashed(_who: &AccountId) {}
+}
+
+/// Slash handler that deposits slashed funds into the provider insurance pool
+pub struct DepositToInsurancePool<T>(core::marker::PhantomData<T>);
+
+impl<T: Config> OnUnbalanced<CreditOf<T>> for DepositToInsurancePool<T> {
+    fn on_nonzero_unbalanced(amount: CreditOf<T>) {
+        let pool = Pallet::<T>::insurance_pool_account();
+        // The pool refuses a deposit below the existential deposit while it is empty
+        if let Err(credit) = T::Currency::resolve(&pool, amount) {
+            log::warn!(
+                target: "provider-staking",
+                "insurance pool could not take {:?} of slashed funds, burning them",
+                credit.peek(),
+            );
+            drop(credit);
+        }
+    }
+}
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
 
+    /// The current storage version.
+    pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(9);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
-        /// Currency type for staking
-        type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+        /// Overarching hold reason
+        type RuntimeHoldReason: From<HoldReason>;
 
-        /// Minimum stake required to become a provider
-        #[pallet::constant]
-        type MinimumStake: Get<BalanceOf<Self>>;
+        /// Currency type for staking
+        type Currency: fungible::Inspect<Self::AccountId>
+            + fungible::Mutate<Self::AccountId>
+            + fungible::MutateHold<Self::AccountId, Reason = Self::RuntimeHoldReason>
+            + fungible::BalancedHold<Self::AccountId>;
 
         /// Maximum number of providers
         #[pallet::constant]
@@ -46,6 +184,111 @@ pub mod pallet {
         /// Cooldown period for unstaking (in blocks)
         #[pallet::constant]
         type UnstakingPeriod: Get<BlockNumberFor<Self>>;
+
+        /// Number of blocks a slash stays pending before it is applied
+        #[pallet::constant]
+        type SlashDeferDuration: Get<BlockNumberFor<Self>>;
+
+        /// Maximum number of slashes that can fall due in a single block
+        #[pallet::constant]
+        type MaxSlashesPerBlock: Get<u32>;
+
+        /// Maximum number of records kept in a provider's slash log; older records move to `SlashArchive`
+        #[pallet::constant]
+        type MaxSlashRecords: Get<u32>;
+
+        /// Origin allowed to cancel or confirm pending slashes and to set the GPU tier table
+        type GovernanceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// GPU tier table written at genesis and by the v3 migration
+        type InitialTierTable: Get<Vec<(GpuTier, TierParams<BalanceOf<Self>>)>>;
+
+        /// Handler for slashed funds
+        type OnSlash: OnUnbalanced<CreditOf<Self>>;
+
+        /// Handler notified of every applied slash
+        type SlashHooks: OnProviderSlashed<Self::AccountId>;
+
+        /// The insurance pool's ID, used for deriving the account that compensates task creators
+        #[pallet::constant]
+        type InsurancePalletId: Get<PalletId>;
+
+        /// Task lookup used to validate compensation claims
+        type Tasks: TaskInspect<Self::AccountId, Self::Hash, BalanceOf<Self>>;
+
+        /// Source of randomness for challenge seeds and random provider draws
+        type Randomness: Randomness<Self::Hash, BlockNumberFor<Self>>;
+
+        /// Origin allowed to verify hardware challenge results
+        type VerifierOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Blocks a provider has to post the result of a hardware challenge
+        #[pallet::constant]
+        type ChallengeResponsePeriod: Get<BlockNumberFor<Self>>;
+
+        /// Blocks verifiers have to check a posted challenge result
+        #[pallet::constant]
+        type ChallengeVerificationPeriod: Get<BlockNumberFor<Self>>;
+
+        /// Interval between random on-chain challenge draws (zero disables them)
+        #[pallet::constant]
+        type RandomChallengeInterval: Get<BlockNumberFor<Self>>;
+
+        /// Bond held from an account issuing a challenge
+        #[pallet::constant]
+        type ChallengeBond: Get<BalanceOf<Self>>;
+
+        /// Maximum number of challenge deadlines falling in a single block
+        #[pallet::constant]
+        type MaxChallengesPerBlock: Get<u32>;
+
+        /// Maximum number of service endpoints per provider
+        #[pallet::constant]
+        type MaxEndpoints: Get<u32>;
+
+        /// Session key type providers sign hot-path operations with
+        type ProviderKey: Member + Parameter + RuntimeAppPublic + Ord + MaxEncodedLen;
+
+        /// Priority of unsigned transactions signed with provider keys
+        #[pallet::constant]
+        type UnsignedPriority: Get<TransactionPriority>;
+
+        /// Minimum number of blocks between two heartbeats of a provider
+        #[pallet::constant]
+        type HeartbeatInterval: Get<BlockNumberFor<Self>>;
+
+        /// Highest round a gradient commitment may be made for
+        #[pallet::constant]
+        type MaxGradientRounds: Get<u32>;
+
+        /// Maximum number of index entries a single matchmaking query looks at
+        #[pallet::constant]
+        type MaxIndexScan: Get<u32>;
+
+        /// Jail length of a first suspension; it doubles with every further jailing
+        #[pallet::constant]
+        type BaseJailDuration: Get<BlockNumberFor<Self>>;
+
+        /// Upper bound on the jail length
+        #[pallet::constant]
+        type MaxJailDuration: Get<BlockNumberFor<Self>>;
+
+        /// Number of jailings after which a provider is banned for good
+        #[pallet::constant]
+        type MaxJailings: Get<u32>;
+
+        /// Reputation a provider restarts with after leaving jail
+        #[pallet::constant]
+        type ProbationReputation: Get<u32>;
+    }
+
+    /// Reasons this pallet places holds on funds
+    #[pallet::composite_enum]
+    pub enum HoldReason {
+        /// Stake bonded by a registered provider
+        ProviderStake,
+        /// Bond posted by an account issuing a hardware challenge
+        ChallengeBond,
     }
 
     #[derive(Encode, Decode, DecodeWithMemTracking, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
@@ -56,9 +299,10 @@ pub mod pallet {
         Offline,
         Suspended,
         Unbonding,
+        Banned,
     }
 
-    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug, TypeInfo, MaxEncodedLen)]
     pub enum GpuTier {
         Consumer,  // RTX 3070, 3080, etc.
         Prosumer,  // RTX 4080, 4090
@@ -78,6 +322,76 @@ pub mod pallet {
         pub registered_at: BlockNumberFor<T>,
         pub last_active: BlockNumberFor<T>,
         pub unbonding_at: Option<BlockNumberFor<T>>,
+        pub endpoints: BoundedVec<Endpoint, T::MaxEndpoints>,
+        pub peer_id: Option<PeerId>,
+        pub encryption_key: Option<[u8; 32]>, // Public key for encrypting gradient channels
+    }
+
+    /// libp2p peer ID, in its binary multihash form
+    pub type PeerId = BoundedVec<u8, ConstU32<64>>;
+
+    /// Where a provider can be reached by coordinators
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, Eq, MaxEncodedLen)]
+    pub enum Endpoint {
+        /// libp2p multiaddr in its textual form, e.g. `/ip4/1.2.3.4/tcp/30333`
+        Multiaddr(BoundedVec<u8, ConstU32<256>>),
+        /// HTTPS URL
+        Https(BoundedVec<u8, ConstU32<256>>),
+    }
+
+    impl Endpoint {
+        fn is_well_formed(&self) -> bool {
+            match self {
+                Endpoint::Multiaddr(addr) => addr.len() > 1 && addr.starts_with(b"/"),
+                Endpoint::Https(url) => url.len() > 8 && url.starts_with(b"https://"),
+            }
+        }
+    }
+
+    /// Liveness signal signed with the provider's session key
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, Eq)]
+    pub struct Heartbeat<AccountId, BlockNumber> {
+        pub provider: AccountId,
+        pub block_number: BlockNumber,
+    }
+
+    /// Commitment to the gradients a provider computed in a training round
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, Eq)]
+    pub struct GradientCommitment<AccountId, Hash> {
+        pub provider: AccountId,
+        pub task_id: Hash,
+        pub round: u32,
+        pub commitment: Hash,
+    }
+
+    /// Result of a hardware challenge, signed by the challenged provider's session key
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, Eq)]
+    pub struct ChallengeResult<AccountId, Hash> {
+        pub provider: AccountId,
+        pub challenge_id: ChallengeId,
+        pub result_hash: Hash,
+    }
+
+    pub type ProviderSignatureOf<T> = <<T as Config>::ProviderKey as RuntimeAppPublic>::Signature;
+
+    /// Work done and native rewards earned by a provider on one task
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, Default, PartialEq, MaxEncodedLen)]
+    pub struct TaskEarnings<Balance, BlockNumber> {
+        pub gradients: u64,
+        pub earned: Balance,
+        pub last_paid_at: BlockNumber,
+    }
+
+    /// A provider's contact details as returned by the runtime API
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, PartialEq)]
+    pub struct ReachableProvider<AccountId> {
+        pub account: AccountId,
+        pub status: ProviderStatus,
+        pub hardware_info: HardwareInfo,
+        pub reputation_score: u32,
+        pub endpoints: Vec<Endpoint>,
+        pub peer_id: Option<PeerId>,
+        pub encryption_key: Option<[u8; 32]>,
     }
 
     #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, Default, PartialEq, MaxEncodedLen)]
@@ -97,6 +411,65 @@ pub mod pallet {
         }
     }
 
+    impl GpuTier {
+        /// All tiers, highest first
+        pub const DESCENDING: [GpuTier; 3] = [GpuTier::Professional, GpuTier::Prosumer, GpuTier::Consumer];
+    }
+
+    /// Hardware thresholds and minimum stake for a GPU tier
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, Default, PartialEq, MaxEncodedLen)]
+    pub struct TierParams<Balance> {
+        pub min_vram_gb: u32,
+        pub min_compute_capability: u32, // Stored as u32, divide by 10 for float
+        pub min_stake: Balance,
+    }
+
+    /// Where a provider's rewards are paid
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, Eq, MaxEncodedLen)]
+    pub enum RewardDestination<AccountId> {
+        /// The provider account itself
+        Stash,
+        /// A separate account
+        Account(AccountId),
+        /// Added to the provider's stake
+        Staked,
+    }
+
+    /// Reward payout preferences of a provider
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, Eq, MaxEncodedLen)]
+    pub struct ProviderSettings<AccountId> {
+        pub payee: RewardDestination<AccountId>,
+        /// Share of rewards kept by the provider; the rest is owed to its delegators
+        pub commission: Perbill,
+        /// Add native rewards to the stake as they settle instead of making them claimable
+        pub auto_compound: bool,
+    }
+
+    impl<AccountId> Default for ProviderSettings<AccountId> {
+        fn default() -> Self {
+            // Without delegators the provider keeps its whole reward
+            Self { payee: RewardDestination::Stash, commission: Perbill::one(), auto_compound: false }
+        }
+    }
+
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, Default, PartialEq, MaxEncodedLen)]
+    pub struct HardwareRequirements {
+        pub min_vram_gb: u32,
+        pub min_compute_capability: u32, // Stored as u32, divide by 10 for float
+        pub min_bandwidth_mbps: u32,
+        pub min_gpu_tier: GpuTier,
+    }
+
+    impl HardwareRequirements {
+        /// Whether the given hardware satisfies these requirements
+        pub fn is_met_by(&self, hardware: &HardwareInfo) -> bool {
+            hardware.vram_gb >= self.min_vram_gb &&
+                hardware.compute_capability >= self.min_compute_capability &&
+                hardware.bandwidth_mbps >= self.min_bandwidth_mbps &&
+                hardware.gpu_tier >= self.min_gpu_tier
+        }
+    }
+
     #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
     pub enum SlashReason {
         MaliciousGradient,
@@ -105,6 +478,71 @@ pub mod pallet {
         ValidationFailure,
     }
 
+    /// Identifier of a queued slash
+    pub type SlashId = u32;
+
+    /// A slash waiting for its cancellation window to pass
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct PendingSlash<T: Config> {
+        pub provider: T::AccountId,
+        pub reason: SlashReason,
+        pub amount: BalanceOf<T>,
+        pub created_at: BlockNumberFor<T>,
+        pub apply_at: BlockNumberFor<T>,
+        /// Task the misbehaviour happened on, if any
+        pub task_id: Option<T::Hash>,
+        /// Hash of the evidence backing the slash
+        pub evidence_hash: Option<T::Hash>,
+        /// Evidence CID filed by the provider; appealed slashes wait for governance
+        pub appeal: Option<BoundedVec<u8, ConstU32<64>>>,
+    }
+
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    pub enum SlashOutcome {
+        Applied,
+        Cancelled,
+    }
+
+    /// Index of an entry in a provider's slash log
+    pub type SlashIndex = u32;
+
+    /// Final outcome of a slash, kept in `SlashLog` and later in `SlashArchive`
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct SlashRecord<T: Config> {
+        pub reason: SlashReason,
+        pub amount: BalanceOf<T>,
+        pub outcome: SlashOutcome,
+        pub task_id: Option<T::Hash>,
+        pub evidence_hash: Option<T::Hash>,
+        pub resolved_at: BlockNumberFor<T>,
+    }
+
+    /// Identifier of a hardware challenge
+    pub type ChallengeId = u32;
+
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub enum ChallengeStatus {
+        AwaitingResult,
+        AwaitingVerification,
+    }
+
+    /// A benchmark challenge issued against a provider's declared hardware
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct HardwareChallenge<T: Config> {
+        pub provider: T::AccountId,
+        /// `None` for challenges drawn on-chain
+        pub challenger: Option<T::AccountId>,
+        pub seed: T::Hash,
+        pub issued_at: BlockNumberFor<T>,
+        /// Block by which the current step must be completed
+        pub deadline: BlockNumberFor<T>,
+        pub result_hash: Option<T::Hash>,
+        pub status: ChallengeStatus,
+    }
+
     #[pallet::storage]
     #[pallet::getter(fn providers)]
     pub type Providers<T: Config> = StorageMap<
@@ -118,121 +556,684 @@ pub mod pallet {
     #[pallet::getter(fn provider_count)]
     pub type ProviderCount<T: Config> = StorageValue<_, u32, ValueQuery>;
 
+    /// Slash log per provider, keyed by a monotonic slash index, keeping the latest `MaxSlashRecords`
     #[pallet::storage]
-    #[pallet::getter(fn slash_history)]
-    pub type SlashHistory<T: Config> = StorageDoubleMap<
+    #[pallet::getter(fn slash_log)]
+    pub type SlashLog<T: Config> = StorageDoubleMap<
         _,
         Blake2_128Concat,
         T::AccountId,
+        Twox64Concat,
+        SlashIndex,
+        SlashRecord<T>,
+    >;
+
+    /// Slash records that no longer fit in the slash log, keyed like `SlashLog`
+    #[pallet::storage]
+    #[pallet::getter(fn slash_archive)]
+    pub type SlashArchive<T: Config> = StorageDoubleMap<
+        _,
         Blake2_128Concat,
-        BlockNumberFor<T>,
-        (SlashReason, BalanceOf<T>),
+        T::AccountId,
+        Twox64Concat,
+        SlashIndex,
+        SlashRecord<T>,
     >;
 
-    #[pallet::event]
-    #[pallet::generate_deposit(pub(super) fn deposit_event)]
-    pub enum Event<T: Config> {
-        /// Provider registered [provider, stake]
-        ProviderRegistered {
-            provider: T::AccountId,
-            stake: BalanceOf<T>,
-        },
+    #[pallet::storage]
+    #[pallet::getter(fn challenges)]
+    pub type Challenges<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        ChallengeId,
+        HardwareChallenge<T>,
+    >;
 
-        /// Provider updated hardware info [provider]
-        HardwareUpdated {
-            provider: T::AccountId,
-        },
+    #[pallet::storage]
+    #[pallet::getter(fn next_challenge_id)]
+    pub type NextChallengeId<T: Config> = StorageValue<_, ChallengeId, ValueQuery>;
 
-        /// Provider slashed [provider, amount, reason]
-        ProviderSlashed {
-            provider: T::AccountId,
-            amount: BalanceOf<T>,
-            reason: SlashReason,
-        },
+    /// Open challenge per provider; a provider has at most one at a time
+    #[pallet::storage]
+    #[pallet::getter(fn active_challenge)]
+    pub type ActiveChallenge<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        ChallengeId,
+    >;
 
-        /// Provider started unbonding [provider, unbonding_at]
-        UnbondingStarted {
-            provider: T::AccountId,
-            unbonding_at: BlockNumberFor<T>,
-        },
+    /// Challenge IDs indexed by the block their current deadline falls in
+    #[pallet::storage]
+    pub type ChallengeDeadlines<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        BoundedVec<ChallengeId, T::MaxChallengesPerBlock>,
+        ValueQuery,
+    >;
 
-        /// Provider withdrew stake [provider, amount]
-        StakeWithdrawn {
-            provider: T::AccountId,
-            amount: BalanceOf<T>,
-        },
+    /// Block at which a provider's declared hardware last passed a challenge
+    #[pallet::storage]
+    #[pallet::getter(fn hardware_verified_at)]
+    pub type VerifiedHardware<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        BlockNumberFor<T>,
+    >;
 
-        /// Provider reputation updated [provider, new_score]
-        ReputationUpdated {
-            provider: T::AccountId,
-            new_score: u32,
-        },
+    /// Session key registered by each provider
+    #[pallet::storage]
+    #[pallet::getter(fn provider_keys)]
+    pub type ProviderKeys<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        T::ProviderKey,
+    >;
 
-        /// Provider status changed [provider, new_status]
-        StatusChanged {
-            provider: T::AccountId,
-            new_status: ProviderStatus,
-        },
-    }
+    /// Reverse lookup from session key to provider
+    #[pallet::storage]
+    #[pallet::getter(fn key_owner)]
+    pub type KeyOwner<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::ProviderKey,
+        T::AccountId,
+    >;
 
-    #[pallet::error]
-    #[derive(PartialEq)]
-    pub enum Error<T> {
-        /// Provider not found
-        ProviderNotFound,
-        /// Provider already registered
-        ProviderAlreadyRegistered,
-        /// Stake below minimum
-        StakeBelowMinimum,
-        /// Too many providers
-        TooManyProviders,
-        /// Invalid hardware specification
-        InvalidHardwareSpec,
-        /// Provider not active
-        ProviderNotActive,
-        /// Still in unbonding period
-        StillUnbonding,
-        /// Nothing to withdraw
-        NothingToWithdraw,
-        /// Invalid GPU model
-        InvalidGpuModel,
-        /// Insufficient stake for operation
-        InsufficientStake,
+    #[pallet::storage]
+    #[pallet::getter(fn last_heartbeat)]
+    pub type LastHeartbeat<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        BlockNumberFor<T>,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn gradient_commitments)]
+    pub type GradientCommitments<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::Hash>, // Task ID
+            NMapKey<Blake2_128Concat, T::AccountId>, // Provider
+            NMapKey<Twox64Concat, u32>, // Round
+        ),
+        T::Hash,
+    >;
+
+    /// Jail record of a provider
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, Default, PartialEq, Eq, MaxEncodedLen)]
+    pub struct JailInfo<BlockNumber> {
+        /// End of the current jail period, if the provider is suspended
+        pub jailed_until: Option<BlockNumber>,
+        /// Number of times the provider has been jailed
+        pub times_jailed: u32,
     }
 
-    #[pallet::call]
-    impl<T: Config> Pallet<T> {
-        /// Register as a compute provider with stake
-        #[pallet::call_index(0)]
-        #[pallet::weight(Weight::from_parts(10_000, 0))]
-        pub fn register_provider(
-            origin: OriginFor<T>,
-            stake_amount: BalanceOf<T>,
-            hardware_info: HardwareInfo,
-        ) -> DispatchResult {
-            let provider = ensure_signed(origin)?;
+    /// Jail records; kept after withdrawal so bans outlive the registration
+    #[pallet::storage]
+    #[pallet::getter(fn jails)]
+    pub type Jails<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        JailInfo<BlockNumberFor<T>>,
+        ValueQuery,
+    >;
 
-            // Validate inputs
-            ensure!(stake_amount >= T::MinimumStake::get(), Error::<T>::StakeBelowMinimum);
-            ensure!(!Providers::<T>::contains_key(&provider), Error::<T>::ProviderAlreadyRegistered);
-            ensure!(ProviderCount::<T>::get() < T::MaxProviders::get(), Error::<T>::TooManyProviders);
-            ensure!(hardware_info.vram_gb > 0, Error::<T>::InvalidHardwareSpec);
-            // GPU model validation is handled by BoundedVec
+    /// Reputation bucket of an indexed provider; each bucket spans 100 reputation points
+    pub type ReputationBucket = u8;
 
-            // Reserve the stake
-            T::Currency::reserve(&provider, stake_amount)?;
+    /// Matchable providers sorted into buckets by GPU tier and reputation
+    #[pallet::storage]
+    pub type ProviderIndex<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Twox64Concat, GpuTier>,
+            NMapKey<Twox64Concat, ReputationBucket>,
+            NMapKey<Blake2_128Concat, T::AccountId>,
+        ),
+        (),
+    >;
 
-            // Validate GPU model length
-            let gpu_model_bounded = hardware_info.gpu_model.try_into().map_err(|_| Error::<T>::InvalidGpuModel)?;
-            let hardware_info_bounded = HardwareInfo {
-                gpu_model: gpu_model_bounded,
-                gpu_tier: hardware_info.gpu_tier,
-                vram_gb: hardware_info.vram_gb,
-                compute_capability: hardware_info.compute_capability,
-                bandwidth_mbps: hardware_info.bandwidth_mbps,
-                cpu_cores: hardware_info.cpu_cores,
-                ram_gb: hardware_info.ram_gb,
-            };
+    /// Bucket each indexed provider currently sits in
+    #[pallet::storage]
+    #[pallet::getter(fn index_position)]
+    pub type IndexPosition<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        (GpuTier, ReputationBucket),
+    >;
+
+    /// Governance-set hardware thresholds and minimum stake per GPU tier
+    #[pallet::storage]
+    #[pallet::getter(fn tier_params)]
+    pub type TierTable<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        GpuTier,
+        TierParams<BalanceOf<T>>,
+    >;
+
+    /// Compensation paid out for a slash log entry
+    #[pallet::storage]
+    #[pallet::getter(fn compensation_paid)]
+    pub type CompensationPaid<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId, // Slashed provider
+        Twox64Concat,
+        SlashIndex,
+        BalanceOf<T>,
+    >;
+
+    /// Reward payout preferences per provider
+    #[pallet::storage]
+    #[pallet::getter(fn settings)]
+    pub type Settings<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        ProviderSettings<T::AccountId>,
+        ValueQuery,
+    >;
+
+    /// Earnings history of each provider by task
+    #[pallet::storage]
+    #[pallet::getter(fn earnings)]
+    pub type Earnings<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId, // Provider
+        Blake2_128Concat,
+        T::Hash, // Task ID
+        TaskEarnings<BalanceOf<T>, BlockNumberFor<T>>,
+    >;
+
+    /// Total compensation paid out per task
+    #[pallet::storage]
+    #[pallet::getter(fn task_compensation)]
+    pub type TaskCompensation<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Number of entries in each provider's slash log (the next slash index)
+    #[pallet::storage]
+    #[pallet::getter(fn slash_count)]
+    pub type SlashCount<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        SlashIndex,
+        ValueQuery,
+    >;
+
+    /// Number of slashes applied to each provider; cancelled slashes are not counted
+    #[pallet::storage]
+    #[pallet::getter(fn applied_slash_count)]
+    pub type AppliedSlashCount<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        SlashIndex,
+        ValueQuery,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn pending_slashes)]
+    pub type PendingSlashes<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        SlashId,
+        PendingSlash<T>,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn next_slash_id)]
+    pub type NextSlashId<T: Config> = StorageValue<_, SlashId, ValueQuery>;
+
+    /// Pending slash IDs indexed by the block they fall due
+    #[pallet::storage]
+    pub type SlashSchedule<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        BoundedVec<SlashId, T::MaxSlashesPerBlock>,
+        ValueQuery,
+    >;
+
+    /// Pending slash IDs indexed by provider
+    #[pallet::storage]
+    pub type ProviderPendingSlashes<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Twox64Concat,
+        SlashId,
+        (),
+    >;
+
+    /// Number of pending slashes per provider
+    #[pallet::storage]
+    #[pallet::getter(fn pending_slash_count)]
+    pub type PendingSlashCount<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        u32,
+        ValueQuery,
+    >;
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        #[serde(skip)]
+        pub _config: core::marker::PhantomData<T>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            for (tier, params) in T::InitialTierTable::get() {
+                TierTable::<T>::insert(tier, params);
+            }
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Provider registered [provider, stake]
+        ProviderRegistered {
+            provider: T::AccountId,
+            stake: BalanceOf<T>,
+        },
+
+        /// Provider updated hardware info [provider, gpu_tier]
+        HardwareUpdated {
+            provider: T::AccountId,
+            gpu_tier: GpuTier,
+        },
+
+        /// Provider updated its endpoints, peer ID or encryption key [provider]
+        NetworkInfoUpdated {
+            provider: T::AccountId,
+        },
+
+        /// Provider registered a new session key [provider]
+        ProviderKeysSet {
+            provider: T::AccountId,
+        },
+
+        /// Heartbeat received [provider, block_number]
+        HeartbeatReceived {
+            provider: T::AccountId,
+            block_number: BlockNumberFor<T>,
+        },
+
+        /// Gradient commitment recorded [provider, task_id, round, commitment]
+        GradientCommitted {
+            provider: T::AccountId,
+            task_id: T::Hash,
+            round: u32,
+            commitment: T::Hash,
+        },
+
+        /// Reward destination changed [provider, payee]
+        PayeeSet {
+            provider: T::AccountId,
+            payee: RewardDestination<T::AccountId>,
+        },
+
+        /// Commission rate changed [provider, commission]
+        CommissionSet {
+            provider: T::AccountId,
+            commission: Perbill,
+        },
+
+        /// Provider turned auto-compounding of rewards on or off [provider, enabled]
+        AutoCompoundSet {
+            provider: T::AccountId,
+            enabled: bool,
+        },
+
+        /// Provider suspended for a jail period [provider, jailed_until, times_jailed]
+        ProviderJailed {
+            provider: T::AccountId,
+            jailed_until: BlockNumberFor<T>,
+            times_jailed: u32,
+        },
+
+        /// Provider banned after too many jailings [provider]
+        ProviderBanned {
+            provider: T::AccountId,
+        },
+
+        /// Provider left jail on probation [provider, new_status]
+        ProviderUnjailed {
+            provider: T::AccountId,
+            new_status: ProviderStatus,
+        },
+
+        /// Provider added to its stake [provider, amount]
+        StakeBonded {
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Rewards added to a provider's stake [provider, amount]
+        RewardRestaked {
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Settled rewards compounded into a provider's stake [provider, amount]
+        RewardCompounded {
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// GPU tier parameters set by governance [tier]
+        TierParamsUpdated {
+            tier: GpuTier,
+        },
+
+        /// Slash queued for the cancellation window [slash_id, provider, amount, reason, apply_at]
+        SlashDeferred {
+            slash_id: SlashId,
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+            reason: SlashReason,
+            apply_at: BlockNumberFor<T>,
+        },
+
+        /// Provider appealed a pending slash [slash_id, evidence]
+        SlashAppealed {
+            slash_id: SlashId,
+            evidence: BoundedVec<u8, ConstU32<64>>,
+        },
+
+        /// Pending slash cancelled by governance [slash_id, provider, slash_index]
+        SlashCancelled {
+            slash_id: SlashId,
+            provider: T::AccountId,
+            slash_index: SlashIndex,
+        },
+
+        /// Provider slashed [provider, amount, reason, slash_index]
+        ProviderSlashed {
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+            reason: SlashReason,
+            slash_index: SlashIndex,
+        },
+
+        /// Task creator compensated from the insurance pool [creator, provider, slash_index, amount]
+        CompensationPaid {
+            creator: T::AccountId,
+            provider: T::AccountId,
+            slash_index: SlashIndex,
+            amount: BalanceOf<T>,
+        },
+
+        /// Hardware challenge issued [challenge_id, provider, seed, deadline]
+        ChallengeIssued {
+            challenge_id: ChallengeId,
+            provider: T::AccountId,
+            seed: T::Hash,
+            deadline: BlockNumberFor<T>,
+        },
+
+        /// Provider posted a challenge result [challenge_id, result_hash]
+        ChallengeResultSubmitted {
+            challenge_id: ChallengeId,
+            result_hash: T::Hash,
+        },
+
+        /// Challenge passed, hardware marked as verified [challenge_id, provider]
+        ChallengePassed {
+            challenge_id: ChallengeId,
+            provider: T::AccountId,
+        },
+
+        /// Challenge failed and a `FalseHardwareClaim` slash was queued [challenge_id, provider]
+        ChallengeFailed {
+            challenge_id: ChallengeId,
+            provider: T::AccountId,
+        },
+
+        /// Posted result was not verified in time [challenge_id, provider]
+        ChallengeExpired {
+            challenge_id: ChallengeId,
+            provider: T::AccountId,
+        },
+
+        /// Provider started unbonding [provider, unbonding_at]
+        UnbondingStarted {
+            provider: T::AccountId,
+            unbonding_at: BlockNumberFor<T>,
+        },
+
+        /// Provider withdrew stake [provider, amount]
+        StakeWithdrawn {
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Provider reputation updated [provider, new_score]
+        ReputationUpdated {
+            provider: T::AccountId,
+            new_score: u32,
+        },
+
+        /// Provider status changed [provider, new_status]
+        StatusChanged {
+            provider: T::AccountId,
+            new_status: ProviderStatus,
+        },
+    }
+
+    #[pallet::error]
+    #[derive(PartialEq)]
+    pub enum Error<T> {
+        /// Provider not found
+        ProviderNotFound,
+        /// Provider already registered
+        ProviderAlreadyRegistered,
+        /// Stake below minimum
+        StakeBelowMinimum,
+        /// Too many providers
+        TooManyProviders,
+        /// Invalid hardware specification
+        InvalidHardwareSpec,
+        /// Provider not active
+        ProviderNotActive,
+        /// Still in unbonding period
+        StillUnbonding,
+        /// Nothing to withdraw
+        NothingToWithdraw,
+        /// Invalid GPU model
+        InvalidGpuModel,
+        /// Insufficient stake for operation
+        InsufficientStake,
+        /// Pending slash not found
+        SlashNotFound,
+        /// Too many slashes already scheduled for the same block
+        TooManySlashesScheduled,
+        /// Slash already appealed
+        AlreadyAppealed,
+        /// Cancellation window has passed
+        AppealWindowClosed,
+        /// Evidence CID too long
+        EvidenceTooLong,
+        /// Provider has pending slashes
+        PendingSlashExists,
+        /// Not the slashed provider
+        NotSlashedProvider,
+        /// Slash record not found
+        SlashRecordNotFound,
+        /// Slash was not applied or is not tied to a task
+        NotCompensable,
+        /// Not the creator of the slashed task
+        NotTaskCreator,
+        /// Compensation already claimed for this slash
+        AlreadyCompensated,
+        /// Nothing left to compensate
+        NothingToCompensate,
+        /// Challenge not found
+        ChallengeNotFound,
+        /// Provider already has an open challenge
+        ChallengeAlreadyActive,
+        /// Challenge is not in the expected state
+        InvalidChallengeStatus,
+        /// Challenge deadline has passed
+        ChallengeDeadlinePassed,
+        /// Too many challenge deadlines already fall in the same block
+        TooManyChallengesScheduled,
+        /// Not the challenged provider
+        NotChallengedProvider,
+        /// Declared hardware does not reach the lowest GPU tier
+        HardwareBelowMinimumTier,
+        /// Too many endpoints
+        TooManyEndpoints,
+        /// Endpoint is not a multiaddr or HTTPS URL
+        InvalidEndpoint,
+        /// Session key is registered to another provider
+        KeyAlreadyInUse,
+        /// Proof of key ownership is invalid
+        InvalidKeyProof,
+        /// Jail period has not ended yet
+        StillJailed,
+        /// Provider is banned
+        ProviderBanned,
+        /// Provider's current status does not allow this
+        InvalidStatus,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            Self::process_due_slashes(now)
+                .saturating_add(Self::process_challenge_deadlines(now))
+                .saturating_add(Self::draw_random_challenge(now))
+        }
+    }
+
+    #[pallet::validate_unsigned]
+    impl<T: Config> ValidateUnsigned for Pallet<T> {
+        type Call = Call<T>;
+
+        fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+            match call {
+                Call::heartbeat { heartbeat, signature } => {
+                    let now = frame_system::Pallet::<T>::block_number();
+                    if heartbeat.block_number > now {
+                        return InvalidTransaction::Future.into();
+                    }
+                    if LastHeartbeat::<T>::get(&heartbeat.provider)
+                        .map_or(false, |last| heartbeat.block_number < last.saturating_add(T::HeartbeatInterval::get()))
+                    {
+                        return InvalidTransaction::Stale.into();
+                    }
+                    if !Self::verify_provider_signature(&heartbeat.provider, &heartbeat.encode(), signature) {
+                        return InvalidTransaction::BadProof.into();
+                    }
+
+                    ValidTransaction::with_tag_prefix("ProviderHeartbeat")
+                        .priority(T::UnsignedPriority::get())
+                        .and_provides(&heartbeat.provider)
+                        .longevity(5)
+                        .propagate(true)
+                        .build()
+                },
+                Call::commit_gradient { commitment, signature } => {
+                    if commitment.round > T::MaxGradientRounds::get() {
+                        return InvalidTransaction::Custom(UNSIGNED_ROUND_OUT_OF_RANGE).into();
+                    }
+                    if !T::Tasks::is_active_provider(&commitment.task_id, &commitment.provider) {
+                        return InvalidTransaction::Custom(UNSIGNED_NOT_ACTIVE_PROVIDER).into();
+                    }
+                    if GradientCommitments::<T>::contains_key((&commitment.task_id, &commitment.provider, commitment.round)) {
+                        return InvalidTransaction::Stale.into();
+                    }
+                    if !Self::verify_provider_signature(&commitment.provider, &commitment.encode(), signature) {
+                        return InvalidTransaction::BadProof.into();
+                    }
+
+                    ValidTransaction::with_tag_prefix("ProviderGradientCommitment")
+                        .priority(T::UnsignedPriority::get())
+                        .and_provides((&commitment.provider, commitment.task_id, commitment.round))
+                        .longevity(64)
+                        .propagate(true)
+                        .build()
+                },
+                Call::submit_challenge_result { result, signature } => {
+                    let Some(challenge) = Challenges::<T>::get(result.challenge_id)
+                        .filter(|challenge| challenge.provider == result.provider)
+                    else {
+                        return InvalidTransaction::Custom(UNSIGNED_NOT_CHALLENGED_PROVIDER).into();
+                    };
+                    let now = frame_system::Pallet::<T>::block_number();
+                    if challenge.status != ChallengeStatus::AwaitingResult || now > challenge.deadline {
+                        return InvalidTransaction::Stale.into();
+                    }
+                    if !Self::verify_provider_signature(&result.provider, &result.encode(), signature) {
+                        return InvalidTransaction::BadProof.into();
+                    }
+
+                    ValidTransaction::with_tag_prefix("ProviderChallengeResult")
+                        .priority(T::UnsignedPriority::get())
+                        .and_provides(result.challenge_id)
+                        .longevity(challenge.deadline.saturating_sub(now).saturated_into::<u64>().max(1))
+                        .propagate(true)
+                        .build()
+                },
+                _ => InvalidTransaction::Call.into(),
+            }
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Register as a compute provider with stake
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn register_provider(
+            origin: OriginFor<T>,
+            stake_amount: BalanceOf<T>,
+            hardware_info: HardwareInfo,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            // Validate inputs
+            ensure!(!Providers::<T>::contains_key(&provider), Error::<T>::ProviderAlreadyRegistered);
+            ensure!(!Self::is_banned(&provider), Error::<T>::ProviderBanned);
+            ensure!(
+                Jails::<T>::get(&provider)
+                    .jailed_until
+                    .map_or(true, |until| frame_system::Pallet::<T>::block_number() >= until),
+                Error::<T>::StillJailed
+            );
+            ensure!(ProviderCount::<T>::get() < T::MaxProviders::get(), Error::<T>::TooManyProviders);
+            ensure!(hardware_info.vram_gb > 0, Error::<T>::InvalidHardwareSpec);
+            // GPU model validation is handled by BoundedVec
+
+            let hardware_info_bounded = Self::validate_hardware(hardware_info)?;
+            ensure!(
+                stake_amount >= Self::minimum_stake_for(hardware_info_bounded.gpu_tier),
+                Error::<T>::StakeBelowMinimum
+            );
+
+            // Hold the stake
+            T::Currency::hold(&HoldReason::ProviderStake.into(), &provider, stake_amount)?;
 
             // Create provider entry
             let provider_info = Provider {
@@ -246,11 +1247,15 @@ pub mod pallet {
                 registered_at: frame_system::Pallet::<T>::block_number(),
                 last_active: frame_system::Pallet::<T>::block_number(),
                 unbonding_at: None,
+                endpoints: BoundedVec::default(),
+                peer_id: None,
+                encryption_key: None,
             };
 
             // Store provider
             Providers::<T>::insert(&provider, &provider_info);
             ProviderCount::<T>::mutate(|count| *count = count.saturating_add(1));
+            Self::reindex(&provider);
 
             // Emit event
             Self::deposit_event(Event::ProviderRegistered {
@@ -270,34 +1275,39 @@ pub mod pallet {
         ) -> DispatchResult {
             let provider = ensure_signed(origin)?;
 
-            Providers::<T>::try_mutate(&provider, |maybe_provider| {
+            let gpu_tier = Providers::<T>::try_mutate(&provider, |maybe_provider| {
                 let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
 
                 ensure!(hardware_info.vram_gb > 0, Error::<T>::InvalidHardwareSpec);
 
-                // Validate GPU model length
-                let gpu_model_bounded = hardware_info.gpu_model.try_into().map_err(|_| Error::<T>::InvalidGpuModel)?;
-                let hardware_info_bounded = HardwareInfo {
-                    gpu_model: gpu_model_bounded,
-                    gpu_tier: hardware_info.gpu_tier,
-                    vram_gb: hardware_info.vram_gb,
-                    compute_capability: hardware_info.compute_capability,
-                    bandwidth_mbps: hardware_info.bandwidth_mbps,
-                    cpu_cores: hardware_info.cpu_cores,
-                    ram_gb: hardware_info.ram_gb,
-                };
+                let hardware_info_bounded = Self::validate_hardware(hardware_info)?;
+
+                // A tier change must still be covered by the provider's stake
+                if hardware_info_bounded.gpu_tier != provider_info.hardware_info.gpu_tier {
+                    ensure!(
+                        provider_info.stake >= Self::minimum_stake_for(hardware_info_bounded.gpu_tier),
+                        Error::<T>::InsufficientStake
+                    );
+                }
 
+                let gpu_tier = hardware_info_bounded.gpu_tier;
                 provider_info.hardware_info = hardware_info_bounded;
 
-                Ok::<(), DispatchError>(())
+                Ok::<_, DispatchError>(gpu_tier)
             })?;
 
-            Self::deposit_event(Event::HardwareUpdated { provider });
+            // New hardware claims have to be verified again
+            VerifiedHardware::<T>::remove(&provider);
+            Self::reindex(&provider);
+
+            Self::deposit_event(Event::HardwareUpdated { provider, gpu_tier });
 
             Ok(())
         }
 
         /// Start unbonding process
+        ///
+        /// A jailed provider has to serve its jail first and a banned provider cannot unbond.
         #[pallet::call_index(2)]
         #[pallet::weight(Weight::from_parts(6_000, 0))]
         pub fn start_unbonding(
@@ -309,15 +1319,22 @@ pub mod pallet {
                 let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
 
                 ensure!(provider_info.status != ProviderStatus::Unbonding, Error::<T>::StillUnbonding);
+                ensure!(provider_info.status != ProviderStatus::Banned, Error::<T>::ProviderBanned);
+
+                let now = frame_system::Pallet::<T>::block_number();
+                if provider_info.status == ProviderStatus::Suspended {
+                    let jail = Jails::<T>::get(&provider);
+                    ensure!(jail.jailed_until.map_or(true, |until| now >= until), Error::<T>::StillJailed);
+                }
 
-                let unbonding_at = frame_system::Pallet::<T>::block_number()
-                    .saturating_add(T::UnstakingPeriod::get());
+                let unbonding_at = now.saturating_add(T::UnstakingPeriod::get());
 
                 provider_info.status = ProviderStatus::Unbonding;
                 provider_info.unbonding_at = Some(unbonding_at);
 
                 Ok::<_, DispatchError>(unbonding_at)
             }).map(|unbonding_at| {
+                Self::reindex(&provider);
                 Self::deposit_event(Event::UnbondingStarted {
                     provider,
                     unbonding_at,
@@ -342,13 +1359,26 @@ pub mod pallet {
             let unbonding_at = provider_info.unbonding_at.ok_or(Error::<T>::NothingToWithdraw)?;
             let current_block = frame_system::Pallet::<T>::block_number();
             ensure!(current_block >= unbonding_at, Error::<T>::StillUnbonding);
+            ensure!(PendingSlashCount::<T>::get(&provider) == 0, Error::<T>::PendingSlashExists);
+            ensure!(!ActiveChallenge::<T>::contains_key(&provider), Error::<T>::ChallengeAlreadyActive);
 
-            // Unreserve stake
-            T::Currency::unreserve(&provider, provider_info.stake);
+            // Release stake
+            T::Currency::release(
+                &HoldReason::ProviderStake.into(),
+                &provider,
+                provider_info.stake,
+                Precision::BestEffort,
+            )?;
 
             // Remove provider
             Providers::<T>::remove(&provider);
+            if let Some(key) = ProviderKeys::<T>::take(&provider) {
+                KeyOwner::<T>::remove(key);
+            }
+            LastHeartbeat::<T>::remove(&provider);
+            Settings::<T>::remove(&provider);
             ProviderCount::<T>::mutate(|count| *count = count.saturating_sub(1));
+            Self::reindex(&provider);
 
             Self::deposit_event(Event::StakeWithdrawn {
                 provider,
@@ -358,50 +1388,22 @@ pub mod pallet {
             Ok(())
         }
 
-        /// Slash a provider for malicious behavior (governance/sudo only)
+        /// Queue a slash for malicious behavior (governance only)
+        ///
+        /// The slash is applied after `SlashDeferDuration` blocks unless it is
+        /// cancelled or appealed in the meantime.
         #[pallet::call_index(4)]
         #[pallet::weight(Weight::from_parts(10_000, 0))]
         pub fn slash_provider(
             origin: OriginFor<T>,
             provider: T::AccountId,
             reason: SlashReason,
+            task_id: Option<T::Hash>,
+            evidence_hash: Option<T::Hash>,
         ) -> DispatchResult {
-            ensure_root(origin)?;
+            T::GovernanceOrigin::ensure_origin(origin)?;
 
-            Providers::<T>::try_mutate(&provider, |maybe_provider| {
-                let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
-
-                let slash_amount = T::SlashPercentage::get() * provider_info.stake;
-
-                // Reduce stake
-                let remaining = provider_info.stake.saturating_sub(slash_amount);
-
-                // Slash from reserved balance
-                let (actual_slash, _) = T::Currency::slash_reserved(&provider, slash_amount);
-                let actual_slash_amount = actual_slash.peek();
-
-                provider_info.stake = remaining;
-
-                // Update reputation
-                provider_info.reputation_score = provider_info.reputation_score.saturating_sub(100);
-
-                // Suspend if reputation too low or stake below minimum
-                if provider_info.reputation_score < 200 || remaining < T::MinimumStake::get() {
-                    provider_info.status = ProviderStatus::Suspended;
-                }
-
-                // Record slash
-                let block = frame_system::Pallet::<T>::block_number();
-                SlashHistory::<T>::insert(&provider, block, (reason.clone(), actual_slash_amount));
-
-                Ok::<_, DispatchError>(actual_slash_amount)
-            }).map(|slash_amount| {
-                Self::deposit_event(Event::ProviderSlashed {
-                    provider,
-                    amount: slash_amount,
-                    reason,
-                });
-            })?;
+            Self::queue_slash(&provider, reason, task_id, evidence_hash)?;
 
             Ok(())
         }
@@ -425,11 +1427,12 @@ pub mod pallet {
 
                 // Auto-suspend if reputation too low
                 if new_score < 200 {
-                    provider_info.status = ProviderStatus::Suspended;
+                    Self::jail(&provider, provider_info);
                 }
 
                 Ok::<(), DispatchError>(())
             })?;
+            Self::reindex(&provider);
 
             Self::deposit_event(Event::ReputationUpdated {
                 provider,
@@ -452,11 +1455,17 @@ pub mod pallet {
             Providers::<T>::try_mutate(&provider, |maybe_provider| {
                 let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
 
-                provider_info.status = new_status.clone();
+                ensure!(provider_info.status != ProviderStatus::Banned, Error::<T>::ProviderBanned);
+                if new_status == ProviderStatus::Suspended {
+                    Self::jail(&provider, provider_info);
+                } else {
+                    provider_info.status = new_status.clone();
+                }
                 provider_info.last_active = frame_system::Pallet::<T>::block_number();
 
                 Ok::<(), DispatchError>(())
             })?;
+            Self::reindex(&provider);
 
             Self::deposit_event(Event::StatusChanged {
                 provider,
@@ -465,6 +1474,538 @@ pub mod pallet {
 
             Ok(())
         }
+
+        /// Appeal a pending slash with an evidence CID
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(6_000, 0))]
+        pub fn appeal_slash(
+            origin: OriginFor<T>,
+            slash_id: SlashId,
+            evidence: Vec<u8>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let evidence: BoundedVec<u8, ConstU32<64>> = evidence.try_into()
+                .map_err(|_| Error::<T>::EvidenceTooLong)?;
+
+            PendingSlashes::<T>::try_mutate(slash_id, |maybe_slash| {
+                let pending = maybe_slash.as_mut().ok_or(Error::<T>::SlashNotFound)?;
+
+                ensure!(pending.provider == who, Error::<T>::NotSlashedProvider);
+                ensure!(pending.appeal.is_none(), Error::<T>::AlreadyAppealed);
+                ensure!(
+                    frame_system::Pallet::<T>::block_number() < pending.apply_at,
+                    Error::<T>::AppealWindowClosed
+                );
+
+                pending.appeal = Some(evidence.clone());
+
+                Ok::<(), DispatchError>(())
+            })?;
+
+            Self::deposit_event(Event::SlashAppealed { slash_id, evidence });
+
+            Ok(())
+        }
+
+        /// Cancel a pending slash (governance only)
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(8_000, 0))]
+        pub fn cancel_slash(
+            origin: OriginFor<T>,
+            slash_id: SlashId,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            let pending = PendingSlashes::<T>::take(slash_id).ok_or(Error::<T>::SlashNotFound)?;
+            Self::unschedule_slash(slash_id, pending.apply_at);
+            ProviderPendingSlashes::<T>::remove(&pending.provider, slash_id);
+            PendingSlashCount::<T>::mutate_exists(&pending.provider, |count| {
+                *count = count.map(|c| c.saturating_sub(1)).filter(|c| *c > 0);
+            });
+
+            let slash_index = Self::record_slash(&pending, pending.amount, SlashOutcome::Cancelled);
+
+            Self::deposit_event(Event::SlashCancelled {
+                slash_id,
+                provider: pending.provider,
+                slash_index,
+            });
+
+            Ok(())
+        }
+
+        /// Confirm a pending slash and apply it immediately (governance only)
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn confirm_slash(
+            origin: OriginFor<T>,
+            slash_id: SlashId,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            let pending = PendingSlashes::<T>::get(slash_id).ok_or(Error::<T>::SlashNotFound)?;
+            let apply_at = pending.apply_at;
+
+            Self::apply_slash(slash_id, pending)?;
+            Self::unschedule_slash(slash_id, apply_at);
+
+            Ok(())
+        }
+
+        /// Compensate the creator of a task from the insurance pool for a slash on that task
+        ///
+        /// The payout is capped by the slashed amount and by what is left of the task's bounty
+        /// after earlier compensations.
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn claim_compensation(
+            origin: OriginFor<T>,
+            provider: T::AccountId,
+            slash_index: SlashIndex,
+        ) -> DispatchResult {
+            let creator = ensure_signed(origin)?;
+
+            let record = Self::slash_record(&provider, slash_index).ok_or(Error::<T>::SlashRecordNotFound)?;
+            ensure!(record.outcome == SlashOutcome::Applied, Error::<T>::NotCompensable);
+            let task_id = record.task_id.ok_or(Error::<T>::NotCompensable)?;
+            ensure!(
+                !CompensationPaid::<T>::contains_key(&provider, slash_index),
+                Error::<T>::AlreadyCompensated
+            );
+
+            let (task_creator, bounty) = T::Tasks::creator_and_bounty(&task_id)
+                .ok_or(Error::<T>::NotCompensable)?;
+            ensure!(task_creator == creator, Error::<T>::NotTaskCreator);
+
+            let pool = Self::insurance_pool_account();
+            let pool_available = T::Currency::reducible_balance(&pool, Preservation::Preserve, Fortitude::Polite);
+            let bounty_left = bounty.saturating_sub(TaskCompensation::<T>::get(&task_id));
+
+            let amount = record.amount.min(bounty_left).min(pool_available);
+            ensure!(!amount.is_zero(), Error::<T>::NothingToCompensate);
+
+            T::Currency::transfer(&pool, &creator, amount, Preservation::Preserve)?;
+
+            CompensationPaid::<T>::insert(&provider, slash_index, amount);
+            TaskCompensation::<T>::mutate(&task_id, |paid| *paid = paid.saturating_add(amount));
+
+            Self::deposit_event(Event::CompensationPaid {
+                creator,
+                provider,
+                slash_index,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Set the hardware thresholds and minimum stake of a GPU tier (governance only)
+        #[pallet::call_index(14)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn set_tier_params(
+            origin: OriginFor<T>,
+            tier: GpuTier,
+            params: TierParams<BalanceOf<T>>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            TierTable::<T>::insert(tier, params);
+
+            Self::deposit_event(Event::TierParamsUpdated { tier });
+
+            Ok(())
+        }
+
+        /// Set the endpoints, peer ID and encryption key coordinators use to reach the provider
+        #[pallet::call_index(15)]
+        #[pallet::weight(Weight::from_parts(6_000, 0))]
+        pub fn set_network_info(
+            origin: OriginFor<T>,
+            endpoints: Vec<Endpoint>,
+            peer_id: Option<PeerId>,
+            encryption_key: Option<[u8; 32]>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            ensure!(endpoints.iter().all(Endpoint::is_well_formed), Error::<T>::InvalidEndpoint);
+            let endpoints: BoundedVec<Endpoint, T::MaxEndpoints> = endpoints.try_into()
+                .map_err(|_| Error::<T>::TooManyEndpoints)?;
+
+            Providers::<T>::try_mutate(&provider, |maybe_provider| {
+                let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
+
+                provider_info.endpoints = endpoints;
+                provider_info.peer_id = peer_id;
+                provider_info.encryption_key = encryption_key;
+
+                Ok::<(), DispatchError>(())
+            })?;
+
+            Self::deposit_event(Event::NetworkInfoUpdated { provider });
+
+            Ok(())
+        }
+
+        /// Register the session key used for heartbeats, gradient commitments and result signatures
+        ///
+        /// `proof` is the key's signature over `(b"glin/provider-key", provider)`, so the stash
+        /// account can only register keys it controls.
+        #[pallet::call_index(16)]
+        #[pallet::weight(Weight::from_parts(8_000, 0))]
+        pub fn set_provider_keys(
+            origin: OriginFor<T>,
+            key: T::ProviderKey,
+            proof: ProviderSignatureOf<T>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            ensure!(Providers::<T>::contains_key(&provider), Error::<T>::ProviderNotFound);
+            ensure!(
+                KeyOwner::<T>::get(&key).map_or(true, |owner| owner == provider),
+                Error::<T>::KeyAlreadyInUse
+            );
+            ensure!(
+                key.verify(&Self::key_ownership_payload(&provider), &proof),
+                Error::<T>::InvalidKeyProof
+            );
+
+            if let Some(old_key) = ProviderKeys::<T>::get(&provider) {
+                KeyOwner::<T>::remove(old_key);
+            }
+            KeyOwner::<T>::insert(&key, &provider);
+            ProviderKeys::<T>::insert(&provider, key);
+
+            Self::deposit_event(Event::ProviderKeysSet { provider });
+
+            Ok(())
+        }
+
+        /// Signal liveness with a heartbeat signed by the provider's session key
+        #[pallet::call_index(17)]
+        #[pallet::weight(Weight::from_parts(4_000, 0))]
+        pub fn heartbeat(
+            origin: OriginFor<T>,
+            heartbeat: Heartbeat<T::AccountId, BlockNumberFor<T>>,
+            _signature: ProviderSignatureOf<T>,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+
+            // Signature and key ownership are checked in `validate_unsigned`
+            LastHeartbeat::<T>::insert(&heartbeat.provider, heartbeat.block_number);
+            Providers::<T>::mutate(&heartbeat.provider, |maybe_provider| {
+                if let Some(provider_info) = maybe_provider {
+                    provider_info.last_active = frame_system::Pallet::<T>::block_number();
+                }
+            });
+
+            Self::deposit_event(Event::HeartbeatReceived {
+                provider: heartbeat.provider,
+                block_number: heartbeat.block_number,
+            });
+
+            Ok(())
+        }
+
+        /// Commit to the gradients computed in a round, signed by the provider's session key
+        #[pallet::call_index(18)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn commit_gradient(
+            origin: OriginFor<T>,
+            commitment: GradientCommitment<T::AccountId, T::Hash>,
+            _signature: ProviderSignatureOf<T>,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+
+            // Signature and key ownership are checked in `validate_unsigned`
+            GradientCommitments::<T>::insert(
+                (&commitment.task_id, &commitment.provider, commitment.round),
+                commitment.commitment,
+            );
+
+            Self::deposit_event(Event::GradientCommitted {
+                provider: commitment.provider,
+                task_id: commitment.task_id,
+                round: commitment.round,
+                commitment: commitment.commitment,
+            });
+
+            Ok(())
+        }
+
+        /// Issue a benchmark challenge against a provider's declared hardware
+        ///
+        /// Holds `ChallengeBond` from the challenger. The bond goes to the provider
+        /// if the challenge passes and is returned otherwise.
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn issue_challenge(
+            origin: OriginFor<T>,
+            provider: T::AccountId,
+        ) -> DispatchResult {
+            let challenger = ensure_signed(origin)?;
+
+            T::Currency::hold(&HoldReason::ChallengeBond.into(), &challenger, T::ChallengeBond::get())?;
+            Self::open_challenge(&provider, Some(challenger))?;
+
+            Ok(())
+        }
+
+        /// Post the result hash of a hardware challenge, signed by the provider's session key
+        #[pallet::call_index(12)]
+        #[pallet::weight(Weight::from_parts(8_000, 0))]
+        pub fn submit_challenge_result(
+            origin: OriginFor<T>,
+            result: ChallengeResult<T::AccountId, T::Hash>,
+            _signature: ProviderSignatureOf<T>,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+
+            // Signature and key ownership are checked in `validate_unsigned`
+            let ChallengeResult { provider, challenge_id, result_hash } = result;
+            let now = frame_system::Pallet::<T>::block_number();
+            let deadline = now.saturating_add(T::ChallengeVerificationPeriod::get());
+
+            Challenges::<T>::try_mutate(challenge_id, |maybe_challenge| {
+                let challenge = maybe_challenge.as_mut().ok_or(Error::<T>::ChallengeNotFound)?;
+
+                ensure!(challenge.provider == provider, Error::<T>::NotChallengedProvider);
+                ensure!(challenge.status == ChallengeStatus::AwaitingResult, Error::<T>::InvalidChallengeStatus);
+                ensure!(now <= challenge.deadline, Error::<T>::ChallengeDeadlinePassed);
+
+                Self::schedule_challenge_deadline(challenge_id, deadline)?;
+
+                challenge.result_hash = Some(result_hash);
+                challenge.status = ChallengeStatus::AwaitingVerification;
+                challenge.deadline = deadline;
+
+                Ok::<(), DispatchError>(())
+            })?;
+
+            Self::deposit_event(Event::ChallengeResultSubmitted { challenge_id, result_hash });
+
+            Ok(())
+        }
+
+        /// Record the verdict on a posted challenge result (verifiers only)
+        #[pallet::call_index(13)]
+        #[pallet::weight(Weight::from_parts(12_000, 0))]
+        pub fn verify_challenge(
+            origin: OriginFor<T>,
+            challenge_id: ChallengeId,
+            passed: bool,
+        ) -> DispatchResult {
+            T::VerifierOrigin::ensure_origin(origin)?;
+
+            let challenge = Challenges::<T>::get(challenge_id).ok_or(Error::<T>::ChallengeNotFound)?;
+            ensure!(
+                challenge.status == ChallengeStatus::AwaitingVerification,
+                Error::<T>::InvalidChallengeStatus
+            );
+
+            if passed {
+                Self::pass_challenge(challenge_id, challenge);
+            } else {
+                Self::fail_challenge(challenge_id, challenge);
+            }
+
+            Ok(())
+        }
+
+        /// Choose where rewards are paid
+        #[pallet::call_index(19)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn set_payee(
+            origin: OriginFor<T>,
+            payee: RewardDestination<T::AccountId>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            ensure!(Providers::<T>::contains_key(&provider), Error::<T>::ProviderNotFound);
+
+            Settings::<T>::mutate(&provider, |settings| settings.payee = payee.clone());
+
+            Self::deposit_event(Event::PayeeSet { provider, payee });
+
+            Ok(())
+        }
+
+        /// Set the share of rewards kept before the delegator share is split off
+        #[pallet::call_index(20)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn set_commission(
+            origin: OriginFor<T>,
+            commission: Perbill,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            ensure!(Providers::<T>::contains_key(&provider), Error::<T>::ProviderNotFound);
+
+            Settings::<T>::mutate(&provider, |settings| settings.commission = commission);
+
+            Self::deposit_event(Event::CommissionSet { provider, commission });
+
+            Ok(())
+        }
+
+        /// Add free balance to the provider's stake
+        #[pallet::call_index(21)]
+        #[pallet::weight(Weight::from_parts(6_000, 0))]
+        pub fn bond_extra(
+            origin: OriginFor<T>,
+            amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Self::bond(&provider, amount)?;
+
+            Self::deposit_event(Event::StakeBonded { provider, amount });
+
+            Ok(())
+        }
+
+        /// Stop taking work; also leaves an expired jail without returning to matchmaking
+        #[pallet::call_index(22)]
+        #[pallet::weight(Weight::from_parts(6_000, 0))]
+        pub fn chill(
+            origin: OriginFor<T>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Self::change_own_status(provider, ProviderStatus::Offline)
+        }
+
+        /// Return to matchmaking from `Offline` or from an expired jail
+        #[pallet::call_index(23)]
+        #[pallet::weight(Weight::from_parts(6_000, 0))]
+        pub fn reactivate(
+            origin: OriginFor<T>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Self::change_own_status(provider, ProviderStatus::Active)
+        }
+
+        /// Add native rewards to the stake as they settle
+        #[pallet::call_index(24)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn set_auto_compound(
+            origin: OriginFor<T>,
+            enabled: bool,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            ensure!(Providers::<T>::contains_key(&provider), Error::<T>::ProviderNotFound);
+
+            Settings::<T>::mutate(&provider, |settings| settings.auto_compound = enabled);
+
+            Self::deposit_event(Event::AutoCompoundSet { provider, enabled });
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> RewardSettings<T::AccountId, BalanceOf<T>> for Pallet<T> {
+        fn payee(who: &T::AccountId) -> RewardDestination<T::AccountId> {
+            Settings::<T>::get(who).payee
+        }
+
+        fn commission(who: &T::AccountId) -> Perbill {
+            Settings::<T>::get(who).commission
+        }
+
+        fn restake(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+            Self::bond(who, amount)?;
+
+            Self::deposit_event(Event::RewardRestaked { provider: who.clone(), amount });
+
+            Ok(())
+        }
+
+        fn auto_compound(who: &T::AccountId) -> bool {
+            Settings::<T>::get(who).auto_compound
+        }
+
+        fn compound(who: &T::AccountId, source: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+            Providers::<T>::try_mutate(who, |maybe_provider| {
+                let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
+                ensure!(provider_info.status != ProviderStatus::Unbonding, Error::<T>::StillUnbonding);
+                ensure!(provider_info.status != ProviderStatus::Banned, Error::<T>::ProviderBanned);
+
+                T::Currency::transfer_and_hold(
+                    &HoldReason::ProviderStake.into(),
+                    source,
+                    who,
+                    amount,
+                    Precision::Exact,
+                    Preservation::Preserve,
+                    Fortitude::Polite,
+                )?;
+                provider_info.stake = provider_info.stake.saturating_add(amount);
+
+                Ok::<(), DispatchError>(())
+            })?;
+
+            Self::deposit_event(Event::RewardCompounded { provider: who.clone(), amount });
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> ProviderStats<T::AccountId, T::Hash, BalanceOf<T>> for Pallet<T> {
+        fn on_reward_settled(who: &T::AccountId, task_id: &T::Hash, gradients: u64, earned: BalanceOf<T>) {
+            Providers::<T>::mutate(who, |maybe_provider| {
+                if let Some(provider_info) = maybe_provider {
+                    provider_info.total_tasks_completed = provider_info.total_tasks_completed.saturating_add(1);
+                    provider_info.total_gradients_computed =
+                        provider_info.total_gradients_computed.saturating_add(gradients);
+                    provider_info.total_tokens_earned = provider_info.total_tokens_earned.saturating_add(earned);
+                }
+            });
+            Self::record_earnings(who, task_id, gradients, earned);
+        }
+
+        fn on_stream_paid(who: &T::AccountId, task_id: &T::Hash, earned: BalanceOf<T>) {
+            Providers::<T>::mutate(who, |maybe_provider| {
+                if let Some(provider_info) = maybe_provider {
+                    provider_info.total_tokens_earned = provider_info.total_tokens_earned.saturating_add(earned);
+                }
+            });
+            Self::record_earnings(who, task_id, 0, earned);
+        }
+    }
+
+    impl<T: Config> ContributionInspect<T::AccountId, T::Hash> for Pallet<T> {
+        fn contributed(task_id: &T::Hash, who: &T::AccountId, round: u32) -> bool {
+            GradientCommitments::<T>::contains_key((task_id, who, round))
+        }
+
+        fn applied_slash_count(who: &T::AccountId) -> SlashIndex {
+            AppliedSlashCount::<T>::get(who)
+        }
+    }
+
+    impl<T: Config> ProviderInspect<T::AccountId> for Pallet<T> {
+        fn hardware(who: &T::AccountId) -> Option<HardwareInfo> {
+            Providers::<T>::get(who).map(|p| p.hardware_info)
+        }
+
+        fn candidates(requirements: &HardwareRequirements, count: u32) -> Vec<T::AccountId> {
+            Self::top_providers(requirements, count)
+                .into_iter()
+                .filter(|who| {
+                    Providers::<T>::get(who).is_some_and(|p| {
+                        p.stake >= Self::minimum_stake_for(p.hardware_info.gpu_tier)
+                    })
+                })
+                .collect()
+        }
+
+        fn candidates_weight(count: u32) -> Weight {
+            // Each scanned index entry reads the provider; each candidate reads it and its tier again
+            let scanned = T::MaxIndexScan::get().saturating_add(count) as u64;
+            T::DbWeight::get().reads(scanned.saturating_mul(2))
+        }
     }
 
     // Helper functions
@@ -474,6 +2015,123 @@ pub mod pallet {
             Providers::<T>::contains_key(account)
         }
 
+        /// Check if an account has been jailed too often to provide again
+        pub fn is_banned(account: &T::AccountId) -> bool {
+            Jails::<T>::get(account).times_jailed >= T::MaxJailings::get()
+        }
+
+        /// Suspend a provider for an escalating jail period, or ban it after `MaxJailings`
+        ///
+        /// An unbonding provider keeps unbonding so its withdrawal is not stuck; the jailing is
+        /// still recorded and counts towards a ban.
+        fn jail(who: &T::AccountId, provider_info: &mut Provider<T>) {
+            if matches!(provider_info.status, ProviderStatus::Suspended | ProviderStatus::Banned) {
+                return;
+            }
+
+            let jail = Jails::<T>::mutate(who, |jail| {
+                jail.times_jailed = jail.times_jailed.saturating_add(1);
+
+                let factor = 2u32.saturating_pow(jail.times_jailed.saturating_sub(1));
+                let duration = T::BaseJailDuration::get()
+                    .saturating_mul(factor.into())
+                    .min(T::MaxJailDuration::get());
+                jail.jailed_until = Some(frame_system::Pallet::<T>::block_number().saturating_add(duration));
+
+                jail.clone()
+            });
+
+            let unbonding = provider_info.status == ProviderStatus::Unbonding;
+            if jail.times_jailed >= T::MaxJailings::get() {
+                if !unbonding {
+                    provider_info.status = ProviderStatus::Banned;
+                }
+                Self::deposit_event(Event::ProviderBanned { provider: who.clone() });
+            } else {
+                if !unbonding {
+                    provider_info.status = ProviderStatus::Suspended;
+                }
+                Self::deposit_event(Event::ProviderJailed {
+                    provider: who.clone(),
+                    jailed_until: jail.jailed_until.unwrap_or_default(),
+                    times_jailed: jail.times_jailed,
+                });
+            }
+        }
+
+        /// Hold more of a provider's balance as stake
+        fn bond(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+            Providers::<T>::try_mutate(who, |maybe_provider| {
+                let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
+                ensure!(provider_info.status != ProviderStatus::Unbonding, Error::<T>::StillUnbonding);
+                ensure!(provider_info.status != ProviderStatus::Banned, Error::<T>::ProviderBanned);
+
+                T::Currency::hold(&HoldReason::ProviderStake.into(), who, amount)?;
+                provider_info.stake = provider_info.stake.saturating_add(amount);
+
+                Ok(())
+            })
+        }
+
+        /// Move a provider between `Active`/`Idle`/`Busy`, `Offline` and an expired jail.
+        ///
+        /// Leaving jail requires the stake to cover the tier minimum again and resets the
+        /// reputation to `ProbationReputation`.
+        fn change_own_status(provider: T::AccountId, new_status: ProviderStatus) -> DispatchResult {
+            let now = frame_system::Pallet::<T>::block_number();
+
+            let unjailed = Providers::<T>::try_mutate(&provider, |maybe_provider| {
+                let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
+
+                let unjailed = match provider_info.status {
+                    ProviderStatus::Banned => return Err(Error::<T>::ProviderBanned.into()),
+                    ProviderStatus::Unbonding => return Err(Error::<T>::StillUnbonding.into()),
+                    ProviderStatus::Suspended => {
+                        let jail = Jails::<T>::get(&provider);
+                        ensure!(jail.jailed_until.map_or(true, |until| now >= until), Error::<T>::StillJailed);
+                        true
+                    },
+                    _ => false,
+                };
+                ensure!(provider_info.status != new_status, Error::<T>::InvalidStatus);
+
+                if unjailed || new_status == ProviderStatus::Active {
+                    ensure!(
+                        provider_info.stake >= Self::minimum_stake_for(provider_info.hardware_info.gpu_tier),
+                        Error::<T>::InsufficientStake
+                    );
+                }
+                if unjailed {
+                    provider_info.reputation_score = T::ProbationReputation::get();
+                    Jails::<T>::mutate(&provider, |jail| jail.jailed_until = None);
+                }
+
+                provider_info.status = new_status.clone();
+                provider_info.last_active = now;
+
+                Ok::<_, DispatchError>(unjailed)
+            })?;
+            Self::reindex(&provider);
+
+            if unjailed {
+                Self::deposit_event(Event::ProviderUnjailed { provider, new_status });
+            } else {
+                Self::deposit_event(Event::StatusChanged { provider, new_status });
+            }
+
+            Ok(())
+        }
+
+        /// Get the account ID of the provider insurance pool
+        pub fn insurance_pool_account() -> T::AccountId {
+            T::InsurancePalletId::get().into_account_truncating()
+        }
+
+        /// Whether the provider's declared hardware has passed a challenge
+        pub fn is_hardware_verified(account: &T::AccountId) -> bool {
+            VerifiedHardware::<T>::contains_key(account)
+        }
+
         /// Get provider's stake amount
         pub fn get_stake(account: &T::AccountId) -> Option<BalanceOf<T>> {
             Providers::<T>::get(account).map(|p| p.stake)
@@ -483,5 +2141,536 @@ pub mod pallet {
         pub fn get_reputation(account: &T::AccountId) -> Option<u32> {
             Providers::<T>::get(account).map(|p| p.reputation_score)
         }
+
+        /// Check that `signature` over `payload` was made by the provider's registered session key
+        pub fn verify_provider_signature(
+            provider: &T::AccountId,
+            payload: &[u8],
+            signature: &ProviderSignatureOf<T>,
+        ) -> bool {
+            Providers::<T>::contains_key(provider) &&
+                ProviderKeys::<T>::get(provider).map_or(false, |key| key.verify(&payload, signature))
+        }
+
+        /// Message a session key signs to prove it belongs to `provider`
+        pub fn key_ownership_payload(provider: &T::AccountId) -> Vec<u8> {
+            (b"glin/provider-key", provider).encode()
+        }
+
+        /// Add work and earnings to a provider's history for a task
+        fn record_earnings(who: &T::AccountId, task_id: &T::Hash, gradients: u64, earned: BalanceOf<T>) {
+            Earnings::<T>::mutate(who, task_id, |maybe_earnings| {
+                let earnings = maybe_earnings.get_or_insert_with(Default::default);
+                earnings.gradients = earnings.gradients.saturating_add(gradients);
+                earnings.earned = earnings.earned.saturating_add(earned);
+                earnings.last_paid_at = frame_system::Pallet::<T>::block_number();
+            });
+        }
+
+        /// Every task a provider earned from, with its work and native earnings on each
+        pub fn earnings_history(who: &T::AccountId) -> Vec<(T::Hash, TaskEarnings<BalanceOf<T>, BlockNumberFor<T>>)> {
+            Earnings::<T>::iter_prefix(who).collect()
+        }
+
+        /// Providers with at least one endpoint, filtered by status and hardware
+        ///
+        /// Without a status filter, suspended, offline and unbonding providers are left out.
+        pub fn reachable_providers(
+            status: Option<ProviderStatus>,
+            requirements: HardwareRequirements,
+        ) -> Vec<ReachableProvider<T::AccountId>> {
+            Providers::<T>::iter()
+                .filter(|(_, p)| !p.endpoints.is_empty())
+                .filter(|(_, p)| match &status {
+                    Some(status) => &p.status == status,
+                    None => matches!(p.status, ProviderStatus::Active | ProviderStatus::Idle | ProviderStatus::Busy),
+                })
+                .filter(|(_, p)| requirements.is_met_by(&p.hardware_info))
+                .map(|(account, p)| ReachableProvider {
+                    account,
+                    status: p.status,
+                    hardware_info: p.hardware_info,
+                    reputation_score: p.reputation_score,
+                    endpoints: p.endpoints.into_inner(),
+                    peer_id: p.peer_id,
+                    encryption_key: p.encryption_key,
+                })
+                .collect()
+        }
+
+        /// Best matching providers for the given requirements, highest tier and reputation first
+        ///
+        /// Looks at no more than `MaxIndexScan` index entries. Providers within a bucket are
+        /// ordered by exact reputation.
+        pub fn top_providers(requirements: &HardwareRequirements, count: u32) -> Vec<T::AccountId> {
+            let mut selected = Vec::new();
+            let mut budget = T::MaxIndexScan::get();
+
+            for tier in GpuTier::DESCENDING.into_iter().filter(|tier| *tier >= requirements.min_gpu_tier) {
+                for bucket in (0..=Self::reputation_bucket(1000)).rev() {
+                    let mut matching: Vec<(u32, T::AccountId)> = Vec::new();
+
+                    for who in ProviderIndex::<T>::iter_key_prefix((tier, bucket)) {
+                        if budget == 0 {
+                            break;
+                        }
+                        budget -= 1;
+
+                        if let Some(p) = Providers::<T>::get(&who) {
+                            if requirements.is_met_by(&p.hardware_info) {
+                                matching.push((p.reputation_score, who));
+                            }
+                        }
+                    }
+
+                    matching.sort_by(|a, b| b.0.cmp(&a.0));
+                    for (_, who) in matching {
+                        if selected.len() as u32 >= count {
+                            return selected;
+                        }
+                        selected.push(who);
+                    }
+
+                    if budget == 0 {
+                        return selected;
+                    }
+                }
+            }
+
+            selected
+        }
+
+        fn reputation_bucket(reputation_score: u32) -> ReputationBucket {
+            (reputation_score.min(1000) / 100) as ReputationBucket
+        }
+
+        /// Move a provider to the index bucket matching its current tier, reputation and status
+        ///
+        /// Only active and idle providers are indexed.
+        pub(crate) fn reindex(who: &T::AccountId) {
+            let target = Providers::<T>::get(who)
+                .filter(|p| matches!(p.status, ProviderStatus::Active | ProviderStatus::Idle))
+                .map(|p| (p.hardware_info.gpu_tier, Self::reputation_bucket(p.reputation_score)));
+            let current = IndexPosition::<T>::get(who);
+
+            if target == current {
+                return;
+            }
+
+            if let Some((tier, bucket)) = current {
+                ProviderIndex::<T>::remove((tier, bucket, who));
+            }
+
+            match target {
+                Some((tier, bucket)) => {
+                    ProviderIndex::<T>::insert((tier, bucket, who), ());
+                    IndexPosition::<T>::insert(who, (tier, bucket));
+                },
+                None => IndexPosition::<T>::remove(who),
+            }
+        }
+
+        /// Highest GPU tier whose thresholds the hardware meets
+        pub fn derive_tier(hardware: &HardwareInfo) -> Option<GpuTier> {
+            GpuTier::DESCENDING.into_iter().find(|tier| {
+                TierTable::<T>::get(tier).map_or(false, |params| {
+                    hardware.vram_gb >= params.min_vram_gb &&
+                        hardware.compute_capability >= params.min_compute_capability
+                })
+            })
+        }
+
+        /// Minimum stake for a GPU tier; tiers missing from the table cannot be staked into
+        pub fn minimum_stake_for(tier: GpuTier) -> BalanceOf<T> {
+            TierTable::<T>::get(tier).map_or_else(BalanceOf::<T>::max_value, |params| params.min_stake)
+        }
+
+        /// Bound the GPU model and replace the declared tier with the derived one
+        fn validate_hardware(hardware_info: HardwareInfo) -> Result<HardwareInfo, DispatchError> {
+            let gpu_tier = Self::derive_tier(&hardware_info).ok_or(Error::<T>::HardwareBelowMinimumTier)?;
+
+            // Validate GPU model length
+            let gpu_model_bounded = hardware_info.gpu_model.try_into().map_err(|_| Error::<T>::InvalidGpuModel)?;
+
+            Ok(HardwareInfo {
+                gpu_model: gpu_model_bounded,
+                gpu_tier,
+                vram_gb: hardware_info.vram_gb,
+                compute_capability: hardware_info.compute_capability,
+                bandwidth_mbps: hardware_info.bandwidth_mbps,
+                cpu_cores: hardware_info.cpu_cores,
+                ram_gb: hardware_info.ram_gb,
+            })
+        }
+
+        /// Apply every unappealed slash falling due in this block
+        fn process_due_slashes(now: BlockNumberFor<T>) -> Weight {
+            let due = SlashSchedule::<T>::take(now);
+            let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+            for slash_id in due {
+                weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+                let Some(pending) = PendingSlashes::<T>::get(slash_id) else { continue };
+
+                // Appealed slashes wait for governance to confirm or cancel them
+                if pending.appeal.is_some() {
+                    continue;
+                }
+
+                if let Err(e) = Self::apply_slash(slash_id, pending) {
+                    log::warn!(target: "provider-staking", "failed to apply slash {}: {:?}", slash_id, e);
+                }
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(3, 5));
+            }
+
+            weight
+        }
+
+        /// Fail unanswered challenges and expire unverified ones
+        fn process_challenge_deadlines(now: BlockNumberFor<T>) -> Weight {
+            let due = ChallengeDeadlines::<T>::take(now);
+            let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+            for challenge_id in due {
+                weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+                let Some(challenge) = Challenges::<T>::get(challenge_id) else { continue };
+
+                // The challenge moved on to a later deadline
+                if challenge.deadline != now {
+                    continue;
+                }
+
+                match challenge.status {
+                    ChallengeStatus::AwaitingResult => {
+                        Self::fail_challenge(challenge_id, challenge);
+                    },
+                    ChallengeStatus::AwaitingVerification => {
+                        Self::close_challenge(challenge_id, &challenge);
+                        if let Some(challenger) = &challenge.challenger {
+                            let _ = T::Currency::release(
+                                &HoldReason::ChallengeBond.into(),
+                                challenger,
+                                T::ChallengeBond::get(),
+                                Precision::BestEffort,
+                            );
+                        }
+                        Self::deposit_event(Event::ChallengeExpired {
+                            challenge_id,
+                            provider: challenge.provider,
+                        });
+                    },
+                }
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(4, 6));
+            }
+
+            weight
+        }
+
+        /// Challenge a randomly drawn provider every `RandomChallengeInterval` blocks
+        fn draw_random_challenge(now: BlockNumberFor<T>) -> Weight {
+            let interval = T::RandomChallengeInterval::get();
+            if interval.is_zero() || !(now % interval).is_zero() {
+                return Weight::zero();
+            }
+
+            let (random, _) = T::Randomness::random(b"provider-staking/draw");
+
+            // Start iterating at a random point of the hashed key space
+            let mut start_key = Providers::<T>::final_prefix().to_vec();
+            start_key.extend_from_slice(&random.as_ref()[..16]);
+
+            let drawn = Providers::<T>::iter_keys_from(start_key)
+                .next()
+                .or_else(|| Providers::<T>::iter_keys().next());
+
+            if let Some(provider) = drawn {
+                // Providers that are busy with a challenge or not eligible are skipped this round
+                let _ = Self::open_challenge(&provider, None);
+            }
+
+            T::DbWeight::get().reads_writes(5, 4)
+        }
+
+        /// Issue a new challenge and schedule its response deadline
+        fn open_challenge(
+            provider: &T::AccountId,
+            challenger: Option<T::AccountId>,
+        ) -> Result<ChallengeId, DispatchError> {
+            let provider_info = Providers::<T>::get(provider).ok_or(Error::<T>::ProviderNotFound)?;
+            ensure!(
+                !matches!(
+                    provider_info.status,
+                    ProviderStatus::Suspended | ProviderStatus::Unbonding | ProviderStatus::Banned
+                ),
+                Error::<T>::ProviderNotActive
+            );
+            ensure!(!ActiveChallenge::<T>::contains_key(provider), Error::<T>::ChallengeAlreadyActive);
+
+            let challenge_id = NextChallengeId::<T>::get();
+            let now = frame_system::Pallet::<T>::block_number();
+            let deadline = now.saturating_add(T::ChallengeResponsePeriod::get());
+
+            let (random, _) = T::Randomness::random(&challenge_id.encode());
+            let seed = T::Hashing::hash_of(&(random, provider, challenge_id));
+
+            Self::schedule_challenge_deadline(challenge_id, deadline)?;
+
+            Challenges::<T>::insert(challenge_id, HardwareChallenge {
+                provider: provider.clone(),
+                challenger,
+                seed,
+                issued_at: now,
+                deadline,
+                result_hash: None,
+                status: ChallengeStatus::AwaitingResult,
+            });
+            ActiveChallenge::<T>::insert(provider, challenge_id);
+            NextChallengeId::<T>::put(challenge_id.wrapping_add(1));
+
+            Self::deposit_event(Event::ChallengeIssued {
+                challenge_id,
+                provider: provider.clone(),
+                seed,
+                deadline,
+            });
+
+            Ok(challenge_id)
+        }
+
+        fn schedule_challenge_deadline(challenge_id: ChallengeId, deadline: BlockNumberFor<T>) -> DispatchResult {
+            ChallengeDeadlines::<T>::try_mutate(deadline, |scheduled| {
+                scheduled.try_push(challenge_id).map_err(|_| Error::<T>::TooManyChallengesScheduled.into())
+            })
+        }
+
+        fn close_challenge(challenge_id: ChallengeId, challenge: &HardwareChallenge<T>) {
+            Challenges::<T>::remove(challenge_id);
+            ActiveChallenge::<T>::remove(&challenge.provider);
+        }
+
+        /// Mark the provider's hardware as verified and pay the challenger's bond to it
+        fn pass_challenge(challenge_id: ChallengeId, challenge: HardwareChallenge<T>) {
+            Self::close_challenge(challenge_id, &challenge);
+
+            if let Some(challenger) = &challenge.challenger {
+                let _ = T::Currency::transfer_on_hold(
+                    &HoldReason::ChallengeBond.into(),
+                    challenger,
+                    &challenge.provider,
+                    T::ChallengeBond::get(),
+                    Precision::BestEffort,
+                    Restriction::Free,
+                    Fortitude::Polite,
+                );
+            }
+
+            VerifiedHardware::<T>::insert(&challenge.provider, frame_system::Pallet::<T>::block_number());
+
+            Self::deposit_event(Event::ChallengePassed {
+                challenge_id,
+                provider: challenge.provider,
+            });
+        }
+
+        /// Close the challenge, return the challenger's bond and queue a `FalseHardwareClaim` slash
+        ///
+        /// The challenge is closed even if the slash cannot be queued, so it never
+        /// blocks the provider or the challenger's bond.
+        fn fail_challenge(challenge_id: ChallengeId, challenge: HardwareChallenge<T>) {
+            Self::close_challenge(challenge_id, &challenge);
+
+            if let Some(challenger) = &challenge.challenger {
+                if let Err(e) = T::Currency::release(
+                    &HoldReason::ChallengeBond.into(),
+                    challenger,
+                    T::ChallengeBond::get(),
+                    Precision::BestEffort,
+                ) {
+                    log::warn!(target: "provider-staking", "failed to release bond of challenge {}: {:?}", challenge_id, e);
+                }
+            }
+
+            VerifiedHardware::<T>::remove(&challenge.provider);
+
+            let evidence_hash = T::Hashing::hash_of(&(challenge.seed, challenge.result_hash));
+            if let Err(e) = Self::queue_slash(&challenge.provider, SlashReason::FalseHardwareClaim, None, Some(evidence_hash)) {
+                log::warn!(target: "provider-staking", "failed to queue slash for challenge {}: {:?}", challenge_id, e);
+            }
+
+            Self::deposit_event(Event::ChallengeFailed {
+                challenge_id,
+                provider: challenge.provider,
+            });
+        }
+
+        /// Get all pending slashes against a provider
+        pub fn pending_slashes_of(account: &T::AccountId) -> Vec<(SlashId, PendingSlash<T>)> {
+            ProviderPendingSlashes::<T>::iter_key_prefix(account)
+                .filter_map(|slash_id| PendingSlashes::<T>::get(slash_id).map(|pending| (slash_id, pending)))
+                .collect()
+        }
+
+        /// Get a slash record from the slash log or, once moved out of it, from the archive
+        pub fn slash_record(account: &T::AccountId, slash_index: SlashIndex) -> Option<SlashRecord<T>> {
+            SlashLog::<T>::get(account, slash_index).or_else(|| SlashArchive::<T>::get(account, slash_index))
+        }
+
+        /// Get the latest `MaxSlashRecords` entries of a provider's slash log, oldest first
+        pub fn slash_log_of(account: &T::AccountId) -> Vec<(SlashIndex, SlashRecord<T>)> {
+            let count = SlashCount::<T>::get(account);
+            (count.saturating_sub(T::MaxSlashRecords::get())..count)
+                .filter_map(|index| SlashLog::<T>::get(account, index).map(|record| (index, record)))
+                .collect()
+        }
+
+        /// Queue a slash of `SlashPercentage` of the provider's current stake
+        pub(crate) fn queue_slash(
+            provider: &T::AccountId,
+            reason: SlashReason,
+            task_id: Option<T::Hash>,
+            evidence_hash: Option<T::Hash>,
+        ) -> Result<SlashId, DispatchError> {
+            let provider_info = Providers::<T>::get(provider).ok_or(Error::<T>::ProviderNotFound)?;
+
+            let amount = T::SlashPercentage::get() * provider_info.stake;
+            let now = frame_system::Pallet::<T>::block_number();
+            let defer = T::SlashDeferDuration::get();
+            let mut apply_at = now.saturating_add(defer);
+
+            // Spill over into the next block with room, up to one more deferral period
+            let latest = apply_at.saturating_add(defer);
+            while SlashSchedule::<T>::decode_len(apply_at).unwrap_or(0) >= T::MaxSlashesPerBlock::get() as usize {
+                ensure!(apply_at < latest, Error::<T>::TooManySlashesScheduled);
+                apply_at = apply_at.saturating_add(One::one());
+            }
+
+            let slash_id = NextSlashId::<T>::get();
+
+            SlashSchedule::<T>::try_mutate(apply_at, |scheduled| {
+                scheduled.try_push(slash_id).map_err(|_| Error::<T>::TooManySlashesScheduled)
+            })?;
+
+            PendingSlashes::<T>::insert(slash_id, PendingSlash {
+                provider: provider.clone(),
+                reason: reason.clone(),
+                amount,
+                created_at: now,
+                apply_at,
+                task_id,
+                evidence_hash,
+                appeal: None,
+            });
+            ProviderPendingSlashes::<T>::insert(provider, slash_id, ());
+            NextSlashId::<T>::put(slash_id.wrapping_add(1));
+            PendingSlashCount::<T>::mutate(provider, |count| *count = count.saturating_add(1));
+
+            Self::deposit_event(Event::SlashDeferred {
+                slash_id,
+                provider: provider.clone(),
+                amount,
+                reason,
+                apply_at,
+            });
+
+            Ok(slash_id)
+        }
+
+        /// Remove a slash from the schedule of the block it was due in
+        fn unschedule_slash(slash_id: SlashId, apply_at: BlockNumberFor<T>) {
+            SlashSchedule::<T>::mutate_exists(apply_at, |maybe_scheduled| {
+                if let Some(scheduled) = maybe_scheduled {
+                    scheduled.retain(|id| *id != slash_id);
+                    if scheduled.is_empty() {
+                        *maybe_scheduled = None;
+                    }
+                }
+            });
+        }
+
+        /// Slash the provider's held stake and record the outcome
+        pub(crate) fn apply_slash(slash_id: SlashId, pending: PendingSlash<T>) -> DispatchResult {
+            let provider = pending.provider.clone();
+
+            let slash_amount = Providers::<T>::try_mutate(&provider, |maybe_provider| {
+                let provider_info = maybe_provider.as_mut().ok_or(Error::<T>::ProviderNotFound)?;
+
+                // Stake may have changed since the slash was queued
+                let slash_amount = pending.amount.min(provider_info.stake);
+
+                // Slash from the held stake
+                let (actual_slash, _) = <T::Currency as fungible::BalancedHold<T::AccountId>>::slash(
+                    &HoldReason::ProviderStake.into(),
+                    &provider,
+                    slash_amount,
+                );
+                let actual_slash_amount = actual_slash.peek();
+                T::OnSlash::on_unbalanced(actual_slash);
+
+                provider_info.stake = provider_info.stake.saturating_sub(actual_slash_amount);
+
+                // Update reputation
+                provider_info.reputation_score = provider_info.reputation_score.saturating_sub(100);
+
+                // Suspend if reputation too low or stake below minimum
+                if provider_info.reputation_score < 200 || provider_info.stake < Self::minimum_stake_for(provider_info.hardware_info.gpu_tier) {
+                    Self::jail(&provider, provider_info);
+                }
+
+                Ok::<_, DispatchError>(actual_slash_amount)
+            })?;
+            Self::reindex(&provider);
+
+            PendingSlashes::<T>::remove(slash_id);
+            ProviderPendingSlashes::<T>::remove(&provider, slash_id);
+            PendingSlashCount::<T>::mutate_exists(&provider, |count| {
+                *count = count.map(|c| c.saturating_sub(1)).filter(|c| *c > 0);
+            });
+
+            // Record slash
+            let slash_index = Self::record_slash(&pending, slash_amount, SlashOutcome::Applied);
+            AppliedSlashCount::<T>::mutate(&provider, |count| *count = count.saturating_add(1));
+            T::SlashHooks::on_provider_slashed(&provider);
+
+            Self::deposit_event(Event::ProviderSlashed {
+                provider,
+                amount: slash_amount,
+                reason: pending.reason,
+                slash_index,
+            });
+
+            Ok(())
+        }
+
+        /// Append the final outcome of a slash to the provider's slash log, archiving the oldest record once full
+        fn record_slash(
+            pending: &PendingSlash<T>,
+            amount: BalanceOf<T>,
+            outcome: SlashOutcome,
+        ) -> SlashIndex {
+            let slash_index = SlashCount::<T>::mutate(&pending.provider, |count| {
+                let index = *count;
+                *count = count.saturating_add(1);
+                index
+            });
+
+            SlashLog::<T>::insert(&pending.provider, slash_index, SlashRecord {
+                reason: pending.reason.clone(),
+                amount,
+                outcome,
+                task_id: pending.task_id,
+                evidence_hash: pending.evidence_hash,
+                resolved_at: frame_system::Pallet::<T>::block_number(),
+            });
+
+            if let Some(archived) = slash_index.checked_sub(T::MaxSlashRecords::get()) {
+                if let Some(record) = SlashLog::<T>::take(&pending.provider, archived) {
+                    SlashArchive::<T>::insert(&pending.provider, archived, record);
+                }
+            }
+
+            slash_index
+        }
     }
 }
\ No newline at end of file
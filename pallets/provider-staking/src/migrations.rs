@@ -0,0 +1,672 @@
+//! Storage migrations for the provider staking pallet.
+
+use super::*;
+use frame_support::{storage_alias, traits::UncheckedOnRuntimeUpgrade};
+use sp_std::collections::btree_map::BTreeMap;
+
+pub mod v1 {
+    use super::*;
+
+    /// `SlashHistory` value as of storage version 1
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct SlashRecord<T: Config> {
+        pub reason: SlashReason,
+        pub amount: BalanceOf<T>,
+        pub outcome: SlashOutcome,
+    }
+
+    /// Slash history keyed by `(provider, block)`, removed in storage version 2
+    #[storage_alias]
+    pub type SlashHistory<T: Config> = StorageDoubleMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        <T as frame_system::Config>::AccountId,
+        Blake2_128Concat,
+        BlockNumberFor<T>,
+        SlashRecord<T>,
+    >;
+
+    /// Converts `SlashHistory` values from `(SlashReason, Balance)` into `SlashRecord`.
+    ///
+    /// Every slash recorded before deferred slashing existed was applied immediately.
+    pub struct InnerMigrateToV1<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated = 0u64;
+
+            SlashHistory::<T>::translate::<(SlashReason, BalanceOf<T>), _>(|_, _, (reason, amount)| {
+                translated += 1;
+                Some(SlashRecord { reason, amount, outcome: SlashOutcome::Applied })
+            });
+
+            log::info!(target: "provider-staking", "migrated {} slash history entries to v1", translated);
+
+            T::DbWeight::get().reads_writes(translated, translated)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((SlashHistory::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let entries = u32::decode(&mut &state[..]).map_err(|_| "v1: invalid pre-upgrade state")?;
+            ensure!(SlashHistory::<T>::iter_values().count() as u32 == entries, "v1: slash history entries lost");
+            ensure!(
+                SlashHistory::<T>::iter_values().all(|record| record.outcome == SlashOutcome::Applied),
+                "v1: migrated slash not marked applied"
+            );
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV1<T> = frame_support::migrations::VersionedMigration<
+        0,
+        1,
+        InnerMigrateToV1<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v2 {
+    use super::*;
+
+    /// `PendingSlashes` value as of storage version 1
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct OldPendingSlash<T: Config> {
+        pub provider: T::AccountId,
+        pub reason: SlashReason,
+        pub amount: BalanceOf<T>,
+        pub created_at: BlockNumberFor<T>,
+        pub apply_at: BlockNumberFor<T>,
+        pub appeal: Option<BoundedVec<u8, ConstU32<64>>>,
+    }
+
+    /// Moves `SlashHistory` into the per-provider `SlashLog`.
+    ///
+    /// Entries are appended in block order, so a provider's slash indices follow
+    /// the order the slashes were recorded in. Pending slashes gain empty task and
+    /// evidence fields.
+    pub struct InnerMigrateToV2<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV2<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut reads = 0u64;
+            let mut writes = 0u64;
+
+            let mut by_provider: BTreeMap<T::AccountId, Vec<(BlockNumberFor<T>, v1::SlashRecord<T>)>> =
+                BTreeMap::new();
+            for (provider, block, record) in v1::SlashHistory::<T>::drain() {
+                reads += 1;
+                writes += 1;
+                by_provider.entry(provider).or_default().push((block, record));
+            }
+
+            for (provider, mut records) in by_provider {
+                records.sort_by_key(|(block, _)| *block);
+
+                let count = records.len() as SlashIndex;
+                for (index, (block, record)) in records.into_iter().enumerate() {
+                    SlashLog::<T>::insert(&provider, index as SlashIndex, SlashRecord {
+                        reason: record.reason,
+                        amount: record.amount,
+                        outcome: record.outcome,
+                        task_id: None,
+                        evidence_hash: None,
+                        resolved_at: block,
+                    });
+                    writes += 1;
+                }
+                SlashCount::<T>::insert(&provider, count);
+                writes += 1;
+            }
+
+            PendingSlashes::<T>::translate::<OldPendingSlash<T>, _>(|_, old| {
+                reads += 1;
+                writes += 1;
+                Some(PendingSlash {
+                    provider: old.provider,
+                    reason: old.reason,
+                    amount: old.amount,
+                    created_at: old.created_at,
+                    apply_at: old.apply_at,
+                    task_id: None,
+                    evidence_hash: None,
+                    appeal: old.appeal,
+                })
+            });
+
+            log::info!(target: "provider-staking", "migrated slash history to per-provider slash log");
+
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            let history = v1::SlashHistory::<T>::iter_keys().count() as u32;
+            let pending = PendingSlashes::<T>::iter_keys().count() as u32;
+            Ok((history, pending).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let (history, pending) = <(u32, u32)>::decode(&mut &state[..]).map_err(|_| "v2: invalid pre-upgrade state")?;
+
+            ensure!(v1::SlashHistory::<T>::iter_keys().next().is_none(), "v2: slash history not drained");
+            ensure!(SlashLog::<T>::iter_keys().count() as u32 == history, "v2: slash records lost");
+            let counted = SlashCount::<T>::iter_values().fold(0u32, |total, count| total.saturating_add(count));
+            ensure!(counted == history, "v2: slash counts do not match the slash log");
+            for (provider, index) in SlashLog::<T>::iter_keys() {
+                ensure!(index < SlashCount::<T>::get(&provider), "v2: slash index beyond the slash count");
+            }
+            ensure!(PendingSlashes::<T>::iter_values().count() as u32 == pending, "v2: pending slashes lost");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV2<T> = frame_support::migrations::VersionedMigration<
+        1,
+        2,
+        InnerMigrateToV2<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v3 {
+    use super::*;
+
+    /// Seeds the GPU tier table that replaces the single `MinimumStake`.
+    ///
+    /// Every provider's tier is re-derived from its hardware. Providers whose
+    /// hardware is below every tier, or whose stake is below their tier's minimum,
+    /// are taken offline until they update their hardware or bond more.
+    pub struct InnerMigrateToV3<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV3<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let table = T::InitialTierTable::get();
+            let seeded = table.len() as u64;
+
+            for (tier, params) in table {
+                TierTable::<T>::insert(tier, params);
+            }
+
+            let mut translated = 0u64;
+            let mut demoted = 0u64;
+
+            Providers::<T>::translate::<v4::OldProvider<T>, _>(|_, mut provider| {
+                translated += 1;
+
+                let derived = Pallet::<T>::derive_tier(&provider.hardware_info);
+                provider.hardware_info.gpu_tier = derived.unwrap_or_default();
+
+                let eligible = derived.map_or(false, |tier| provider.stake >= Pallet::<T>::minimum_stake_for(tier));
+                if !eligible && matches!(provider.status, ProviderStatus::Active | ProviderStatus::Idle | ProviderStatus::Busy) {
+                    provider.status = ProviderStatus::Offline;
+                    demoted += 1;
+                }
+
+                Some(provider)
+            });
+
+            log::info!(
+                target: "provider-staking",
+                "seeded {} GPU tiers, re-derived {} provider tiers, took {} under-staked providers offline",
+                seeded,
+                translated,
+                demoted,
+            );
+
+            T::DbWeight::get().reads_writes(translated.saturating_add(seeded), translated.saturating_add(seeded))
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((Providers::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let providers = u32::decode(&mut &state[..]).map_err(|_| "v3: invalid pre-upgrade state")?;
+
+            for (tier, params) in T::InitialTierTable::get() {
+                ensure!(TierTable::<T>::get(tier) == Some(params), "v3: tier table not seeded");
+            }
+
+            let mut checked = 0u32;
+            for provider in v4::old::Providers::<T>::iter_values() {
+                let derived = Pallet::<T>::derive_tier(&provider.hardware_info);
+                ensure!(provider.hardware_info.gpu_tier == derived.unwrap_or_default(), "v3: tier not re-derived");
+                if matches!(provider.status, ProviderStatus::Active | ProviderStatus::Idle | ProviderStatus::Busy) {
+                    ensure!(
+                        derived.map_or(false, |tier| provider.stake >= Pallet::<T>::minimum_stake_for(tier)),
+                        "v3: under-staked provider left matchable"
+                    );
+                }
+                checked += 1;
+            }
+            ensure!(checked == providers, "v3: providers lost");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV3<T> = frame_support::migrations::VersionedMigration<
+        2,
+        3,
+        InnerMigrateToV3<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v4 {
+    use super::*;
+
+    /// `Provider` as of storage version 3
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct OldProvider<T: Config> {
+        pub stake: BalanceOf<T>,
+        pub status: ProviderStatus,
+        pub hardware_info: HardwareInfo,
+        pub reputation_score: u32,
+        pub total_tasks_completed: u32,
+        pub total_gradients_computed: u64,
+        pub total_tokens_earned: BalanceOf<T>,
+        pub registered_at: BlockNumberFor<T>,
+        pub last_active: BlockNumberFor<T>,
+        pub unbonding_at: Option<BlockNumberFor<T>>,
+    }
+
+    /// Providers as stored before storage version 4
+    pub mod old {
+        use super::*;
+
+        #[storage_alias]
+        pub type Providers<T: Config> = StorageMap<
+            Pallet<T>,
+            Blake2_128Concat,
+            <T as frame_system::Config>::AccountId,
+            OldProvider<T>,
+        >;
+    }
+
+    /// Adds empty endpoints, peer ID and encryption key to every provider.
+    pub struct InnerMigrateToV4<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV4<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated = 0u64;
+
+            Providers::<T>::translate::<OldProvider<T>, _>(|_, old| {
+                translated += 1;
+                Some(Provider {
+                    stake: old.stake,
+                    status: old.status,
+                    hardware_info: old.hardware_info,
+                    reputation_score: old.reputation_score,
+                    total_tasks_completed: old.total_tasks_completed,
+                    total_gradients_computed: old.total_gradients_computed,
+                    total_tokens_earned: old.total_tokens_earned,
+                    registered_at: old.registered_at,
+                    last_active: old.last_active,
+                    unbonding_at: old.unbonding_at,
+                    endpoints: BoundedVec::default(),
+                    peer_id: None,
+                    encryption_key: None,
+                })
+            });
+
+            log::info!(target: "provider-staking", "migrated {} providers to v4", translated);
+
+            T::DbWeight::get().reads_writes(translated, translated)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            let stakes = old::Providers::<T>::iter_values()
+                .map(|provider| provider.stake)
+                .fold(BalanceOf::<T>::zero(), |total, stake| total.saturating_add(stake));
+            Ok((old::Providers::<T>::iter_keys().count() as u32, stakes).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let (providers, stakes) =
+                <(u32, BalanceOf<T>)>::decode(&mut &state[..]).map_err(|_| "v4: invalid pre-upgrade state")?;
+
+            let mut count = 0u32;
+            let mut total = BalanceOf::<T>::zero();
+            for provider in Providers::<T>::iter_values() {
+                ensure!(
+                    provider.endpoints.is_empty() && provider.peer_id.is_none() && provider.encryption_key.is_none(),
+                    "v4: network info not empty"
+                );
+                count += 1;
+                total = total.saturating_add(provider.stake);
+            }
+            ensure!(count == providers, "v4: providers lost");
+            ensure!(total == stakes, "v4: stakes changed");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV4<T> = frame_support::migrations::VersionedMigration<
+        3,
+        4,
+        InnerMigrateToV4<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v5 {
+    use super::*;
+    use frame_support::traits::ReservableCurrency;
+
+    /// Moves provider stakes and challenge bonds from anonymous reserves to holds.
+    ///
+    /// `OldCurrency` is the `ReservableCurrency` the pallet was configured with before the
+    /// switch to fungible holds.
+    pub struct InnerMigrateToV5<T, OldCurrency>(core::marker::PhantomData<(T, OldCurrency)>);
+
+    impl<T, OldCurrency> UncheckedOnRuntimeUpgrade for InnerMigrateToV5<T, OldCurrency>
+    where
+        T: Config,
+        OldCurrency: ReservableCurrency<T::AccountId, Balance = BalanceOf<T>>,
+    {
+        fn on_runtime_upgrade() -> Weight {
+            let mut moved = 0u64;
+
+            let reserved_stakes = Providers::<T>::iter().map(|(who, p)| (who, p.stake, HoldReason::ProviderStake));
+            let reserved_bonds = Challenges::<T>::iter_values()
+                .filter_map(|c| c.challenger)
+                .map(|who| (who, T::ChallengeBond::get(), HoldReason::ChallengeBond));
+
+            for (who, amount, reason) in reserved_stakes.chain(reserved_bonds).collect::<Vec<_>>() {
+                let missing = OldCurrency::unreserve(&who, amount);
+                let available = amount.saturating_sub(missing);
+
+                if let Err(e) = T::Currency::hold(&reason.into(), &who, available) {
+                    log::error!(target: "provider-staking", "failed to hold {:?} for {:?}: {:?}", available, who, e);
+                }
+                moved += 1;
+            }
+
+            log::info!(target: "provider-staking", "moved {} reserves to holds", moved);
+
+            T::DbWeight::get().reads_writes(moved.saturating_mul(3), moved.saturating_mul(3))
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            // What the migration can move: each stake or bond, capped by what is left reserved
+            let mut reserved: BTreeMap<T::AccountId, BalanceOf<T>> = BTreeMap::new();
+            let mut expected = BalanceOf::<T>::zero();
+            let stakes = Providers::<T>::iter().map(|(who, p)| (who, p.stake));
+            let bonds = Challenges::<T>::iter_values().filter_map(|c| c.challenger).map(|who| (who, T::ChallengeBond::get()));
+            for (who, amount) in stakes.chain(bonds).collect::<Vec<_>>() {
+                let left = reserved.entry(who.clone()).or_insert_with(|| OldCurrency::reserved_balance(&who));
+                let moved = amount.min(*left);
+                *left = left.saturating_sub(moved);
+                expected = expected.saturating_add(moved);
+            }
+            Ok(expected.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            use fungible::InspectHold;
+            use sp_std::collections::btree_set::BTreeSet;
+
+            let expected = BalanceOf::<T>::decode(&mut &state[..]).map_err(|_| "v5: invalid pre-upgrade state")?;
+
+            let mut held = BalanceOf::<T>::zero();
+            for (who, provider) in Providers::<T>::iter() {
+                let stake = T::Currency::balance_on_hold(&HoldReason::ProviderStake.into(), &who);
+                ensure!(stake <= provider.stake, "v5: more held than staked");
+                held = held.saturating_add(stake);
+            }
+            let challengers = Challenges::<T>::iter_values().filter_map(|c| c.challenger).collect::<BTreeSet<_>>();
+            for who in challengers {
+                held = held.saturating_add(T::Currency::balance_on_hold(&HoldReason::ChallengeBond.into(), &who));
+            }
+            ensure!(held == expected, "v5: reserves not moved to holds");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV5<T, OldCurrency> = frame_support::migrations::VersionedMigration<
+        4,
+        5,
+        InnerMigrateToV5<T, OldCurrency>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v6 {
+    use super::*;
+
+    /// Builds the sorted provider index from the existing providers.
+    pub struct InnerMigrateToV6<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV6<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let providers = Providers::<T>::iter_keys().collect::<Vec<_>>();
+            let count = providers.len() as u64;
+
+            for who in providers {
+                Pallet::<T>::reindex(&who);
+            }
+
+            log::info!(target: "provider-staking", "indexed {} providers", count);
+
+            T::DbWeight::get().reads_writes(count.saturating_mul(3), count.saturating_mul(2))
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            ensure!(IndexPosition::<T>::iter_keys().next().is_none(), "v6: providers already indexed");
+            Ok(Vec::new())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let mut matchable = 0usize;
+            for (who, provider) in Providers::<T>::iter() {
+                let position = IndexPosition::<T>::get(&who);
+                if matches!(provider.status, ProviderStatus::Active | ProviderStatus::Idle) {
+                    let (tier, bucket) = position.ok_or("v6: matchable provider not indexed")?;
+                    ensure!(tier == provider.hardware_info.gpu_tier, "v6: provider indexed under the wrong tier");
+                    ensure!(ProviderIndex::<T>::contains_key((tier, bucket, &who)), "v6: index entry missing");
+                    matchable += 1;
+                } else {
+                    ensure!(position.is_none(), "v6: unmatchable provider indexed");
+                }
+            }
+            ensure!(ProviderIndex::<T>::iter_keys().count() == matchable, "v6: stray index entries");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV6<T> = frame_support::migrations::VersionedMigration<
+        5,
+        6,
+        InnerMigrateToV6<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v7 {
+    use super::*;
+
+    /// `ProviderSettings` as of storage version 6
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct OldProviderSettings<AccountId> {
+        pub payee: RewardDestination<AccountId>,
+        pub commission: Perbill,
+    }
+
+    /// Adds the auto-compound flag, off, to every provider's reward settings.
+    pub struct InnerMigrateToV7<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV7<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated = 0u64;
+
+            Settings::<T>::translate::<OldProviderSettings<T::AccountId>, _>(|_, old| {
+                translated += 1;
+                Some(ProviderSettings { payee: old.payee, commission: old.commission, auto_compound: false })
+            });
+
+            log::info!(target: "provider-staking", "migrated {} reward settings to v7", translated);
+
+            T::DbWeight::get().reads_writes(translated, translated)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((Settings::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let settings = u32::decode(&mut &state[..]).map_err(|_| "v7: invalid pre-upgrade state")?;
+            let mut count = 0u32;
+            for provider_settings in Settings::<T>::iter_values() {
+                ensure!(!provider_settings.auto_compound, "v7: auto-compounding turned on");
+                count += 1;
+            }
+            ensure!(count == settings, "v7: reward settings lost");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV7<T> = frame_support::migrations::VersionedMigration<
+        6,
+        7,
+        InnerMigrateToV7<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v8 {
+    use super::*;
+
+    /// Indexes every pending slash under its provider.
+    pub struct InnerMigrateToV8<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV8<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut indexed = 0u64;
+
+            for (slash_id, pending) in PendingSlashes::<T>::iter() {
+                ProviderPendingSlashes::<T>::insert(&pending.provider, slash_id, ());
+                indexed += 1;
+            }
+
+            log::info!(target: "provider-staking", "indexed {} pending slashes in v8", indexed);
+
+            T::DbWeight::get().reads_writes(indexed, indexed)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((PendingSlashes::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let pending = u32::decode(&mut &state[..]).map_err(|_| "v8: invalid pre-upgrade state")?;
+            let mut indexed = 0u32;
+            for (provider, slash_id) in ProviderPendingSlashes::<T>::iter_keys() {
+                let slash = PendingSlashes::<T>::get(slash_id).ok_or("v8: index points at no pending slash")?;
+                ensure!(slash.provider == provider, "v8: slash indexed under the wrong provider");
+                indexed += 1;
+            }
+            ensure!(indexed == pending, "v8: pending slashes left unindexed");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV8<T> = frame_support::migrations::VersionedMigration<
+        7,
+        8,
+        InnerMigrateToV8<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v9 {
+    use super::*;
+
+    /// Counts the applied slashes in the slash log and archive of every provider.
+    pub struct InnerMigrateToV9<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> InnerMigrateToV9<T> {
+        fn applied_counts() -> (BTreeMap<T::AccountId, SlashIndex>, u64) {
+            let mut counts = BTreeMap::<T::AccountId, SlashIndex>::new();
+            let mut reads = 0u64;
+            for (provider, _, record) in SlashLog::<T>::iter().chain(SlashArchive::<T>::iter()) {
+                reads += 1;
+                if record.outcome == SlashOutcome::Applied {
+                    let count = counts.entry(provider).or_default();
+                    *count = count.saturating_add(1);
+                }
+            }
+            (counts, reads)
+        }
+    }
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV9<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let (counts, reads) = Self::applied_counts();
+            let writes = counts.len() as u64;
+
+            for (provider, count) in counts {
+                AppliedSlashCount::<T>::insert(provider, count);
+            }
+
+            log::info!(target: "provider-staking", "counted applied slashes of {} providers in v9", writes);
+
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            ensure!(AppliedSlashCount::<T>::iter_keys().next().is_none(), "v9: applied slashes already counted");
+            Ok(Vec::new())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let (counts, _) = Self::applied_counts();
+            ensure!(
+                AppliedSlashCount::<T>::iter().count() == counts.len(),
+                "v9: applied slash counts for providers without applied slashes"
+            );
+            for (provider, count) in counts {
+                ensure!(AppliedSlashCount::<T>::get(&provider) == count, "v9: applied slash count mismatch");
+            }
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV9<T> = frame_support::migrations::VersionedMigration<
+        8,
+        9,
+        InnerMigrateToV9<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
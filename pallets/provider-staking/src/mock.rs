@@ -0,0 +1,209 @@
+//! Test runtime for the provider staking pallet.
+//!
+//! Tasks come from an in-memory mock so compensation claims can be set up per test. Slashed
+//! funds go to the insurance pool, as in the runtime.
+
+use crate as pallet_provider_staking;
+use crate::{
+    DepositToInsurancePool, GpuTier, HardwareInfo, OnProviderSlashed, TaskInspect, TierParams,
+};
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{fungible::InspectHold, ConstU32, ConstU64, Hooks, Randomness},
+    PalletId,
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    BuildStorage, Percent,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = u64;
+pub type Balance = u64;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
+pub const CREATOR: AccountId = 4;
+
+pub const INITIAL_BALANCE: Balance = 10_000;
+pub const CHALLENGE_BOND: Balance = 50;
+pub const CONSUMER_STAKE: Balance = 100;
+
+frame_support::construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        Balances: pallet_balances,
+        ProviderStaking: pallet_provider_staking,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+parameter_types! {
+    pub const SlashPercentage: Percent = Percent::from_percent(10);
+    pub const InsurancePalletId: PalletId = PalletId(*b"py/insur");
+    pub InitialTierTable: Vec<(GpuTier, TierParams<Balance>)> = vec![
+        (GpuTier::Consumer, TierParams { min_vram_gb: 8, min_compute_capability: 75, min_stake: CONSUMER_STAKE }),
+        (GpuTier::Prosumer, TierParams { min_vram_gb: 16, min_compute_capability: 86, min_stake: 500 }),
+        (GpuTier::Professional, TierParams { min_vram_gb: 40, min_compute_capability: 80, min_stake: 1000 }),
+    ];
+}
+
+impl pallet_provider_staking::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type Currency = Balances;
+    type MaxProviders = ConstU32<10>;
+    type SlashPercentage = SlashPercentage;
+    type UnstakingPeriod = ConstU64<20>;
+    type SlashDeferDuration = ConstU64<10>;
+    type MaxSlashesPerBlock = ConstU32<2>;
+    type MaxSlashRecords = ConstU32<3>;
+    type GovernanceOrigin = EnsureRoot<AccountId>;
+    type InitialTierTable = InitialTierTable;
+    type OnSlash = DepositToInsurancePool<Test>;
+    type SlashHooks = MockSlashHooks;
+    type InsurancePalletId = InsurancePalletId;
+    type Tasks = MockTasks;
+    type Randomness = MockRandomness;
+    type VerifierOrigin = EnsureRoot<AccountId>;
+    type ChallengeResponsePeriod = ConstU64<5>;
+    type ChallengeVerificationPeriod = ConstU64<5>;
+    type RandomChallengeInterval = ConstU64<0>;
+    type ChallengeBond = ConstU64<CHALLENGE_BOND>;
+    type MaxChallengesPerBlock = ConstU32<4>;
+    type MaxEndpoints = ConstU32<4>;
+    type ProviderKey = crate::crypto::Public;
+    type UnsignedPriority = ConstU64<{ u64::MAX / 2 }>;
+    type HeartbeatInterval = ConstU64<10>;
+    type MaxGradientRounds = ConstU32<100>;
+    type MaxIndexScan = ConstU32<100>;
+    type BaseJailDuration = ConstU64<10>;
+    type MaxJailDuration = ConstU64<100>;
+    type MaxJailings = ConstU32<3>;
+    type ProbationReputation = ConstU32<300>;
+}
+
+thread_local! {
+    static TASKS: RefCell<BTreeMap<H256, (AccountId, Balance)>> = RefCell::new(BTreeMap::new());
+    static SLASHED: RefCell<Vec<AccountId>> = RefCell::new(Vec::new());
+}
+
+pub struct MockTasks;
+
+impl TaskInspect<AccountId, H256, Balance> for MockTasks {
+    fn creator_and_bounty(task_id: &H256) -> Option<(AccountId, Balance)> {
+        TASKS.with(|tasks| tasks.borrow().get(task_id).copied())
+    }
+
+    fn is_active_provider(_task_id: &H256, _who: &AccountId) -> bool {
+        false
+    }
+}
+
+/// Records every provider the pallet reports as slashed
+pub struct MockSlashHooks;
+
+impl OnProviderSlashed<AccountId> for MockSlashHooks {
+    fn on_provider_slashed(who: &AccountId) {
+        SLASHED.with(|slashed| slashed.borrow_mut().push(*who));
+    }
+}
+
+/// Randomness derived from the subject alone
+pub struct MockRandomness;
+
+impl Randomness<H256, u64> for MockRandomness {
+    fn random(subject: &[u8]) -> (H256, u64) {
+        (BlakeTwo256::hash(subject), 0)
+    }
+}
+
+pub fn task_id(n: u8) -> H256 {
+    H256::repeat_byte(n)
+}
+
+pub fn add_task(task: H256, creator: AccountId, bounty: Balance) {
+    TASKS.with(|tasks| tasks.borrow_mut().insert(task, (creator, bounty)));
+}
+
+/// Providers reported to the slash hooks, in order
+pub fn slashed() -> Vec<AccountId> {
+    SLASHED.with(|slashed| slashed.borrow().clone())
+}
+
+pub fn hardware(vram_gb: u32, compute_capability: u32) -> HardwareInfo {
+    HardwareInfo {
+        gpu_model: b"RTX 3080".to_vec().try_into().unwrap(),
+        gpu_tier: GpuTier::Consumer,
+        vram_gb,
+        compute_capability,
+        bandwidth_mbps: 1000,
+        cpu_cores: 16,
+        ram_gb: 64,
+    }
+}
+
+/// Hardware that reaches the consumer tier only
+pub fn consumer_hardware() -> HardwareInfo {
+    hardware(10, 80)
+}
+
+pub fn balance(who: AccountId) -> Balance {
+    Balances::free_balance(who)
+}
+
+pub fn staked(who: AccountId) -> Balance {
+    Balances::balance_on_hold(&crate::HoldReason::ProviderStake.into(), &who)
+}
+
+pub fn bonded(who: AccountId) -> Balance {
+    Balances::balance_on_hold(&crate::HoldReason::ChallengeBond.into(), &who)
+}
+
+pub fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        System::set_block_number(System::block_number() + 1);
+        ProviderStaking::on_initialize(System::block_number());
+    }
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![
+            (ALICE, INITIAL_BALANCE),
+            (BOB, INITIAL_BALANCE),
+            (CHARLIE, INITIAL_BALANCE),
+            (CREATOR, INITIAL_BALANCE),
+            // The insurance pool keeps its existential deposit while paying out
+            (ProviderStaking::insurance_pool_account(), 1),
+        ],
+        ..Default::default()
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    pallet_provider_staking::GenesisConfig::<Test>::default()
+        .assimilate_storage(&mut storage)
+        .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,1111 @@
+use crate::{
+    crypto, migrations, mock::*, AppliedSlashCount, Call, ChallengeId, ChallengeResult, ChallengeStatus, Challenges,
+    Endpoint, Error, Event, GpuTier, HardwareChallenge, HardwareRequirements, Heartbeat, IndexPosition, PendingSlashes,
+    Provider, ProviderIndex, ProviderPendingSlashes, ProviderSettings, ProviderStats, ProviderStatus, Providers,
+    RewardDestination, RewardSettings, Settings, SlashId, SlashOutcome, SlashReason, TaskEarnings, TierParams,
+    TierTable, UNSIGNED_NOT_CHALLENGED_PROVIDER,
+};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::unhashed,
+    traits::{fungible::InspectHold, OnRuntimeUpgrade, ReservableCurrency, StorageVersion},
+    BoundedVec,
+};
+use sp_core::{Pair, H256};
+use sp_runtime::{
+    traits::ValidateUnsigned,
+    transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidity},
+    DispatchError, Perbill,
+};
+
+fn register(who: AccountId, stake: Balance) {
+    assert_ok!(ProviderStaking::register_provider(RuntimeOrigin::signed(who), stake, consumer_hardware()));
+}
+
+fn slash(who: AccountId, task: Option<H256>) {
+    assert_ok!(ProviderStaking::slash_provider(RuntimeOrigin::root(), who, SlashReason::MaliciousGradient, task, None));
+}
+
+fn stake_of(who: AccountId) -> Balance {
+    ProviderStaking::providers(who).expect("provider registered").stake
+}
+
+fn status_of(who: AccountId) -> ProviderStatus {
+    ProviderStaking::providers(who).expect("provider registered").status
+}
+
+/// Session key of a provider, derived from its account
+fn session_key(who: AccountId) -> crypto::Pair {
+    crypto::Pair::from_seed(&[who as u8; 32])
+}
+
+fn set_session_key(who: AccountId) {
+    let key = session_key(who);
+    let proof = key.sign(&ProviderStaking::key_ownership_payload(&who));
+    assert_ok!(ProviderStaking::set_provider_keys(RuntimeOrigin::signed(who), key.public(), proof));
+}
+
+fn validate(call: &Call<Test>) -> TransactionValidity {
+    ProviderStaking::validate_unsigned(TransactionSource::External, call)
+}
+
+/// Sign a challenge result with the provider's session key, validate it as the pool would and dispatch it
+fn submit_result(who: AccountId, challenge_id: ChallengeId) {
+    let result = ChallengeResult { provider: who, challenge_id, result_hash: H256::repeat_byte(7) };
+    let signature = session_key(who).sign(&result.encode());
+    assert_ok!(validate(&Call::submit_challenge_result { result: result.clone(), signature: signature.clone() }));
+    assert_ok!(ProviderStaking::submit_challenge_result(RuntimeOrigin::none(), result, signature));
+}
+
+fn run_migration<M: OnRuntimeUpgrade>() {
+    #[cfg(feature = "try-runtime")]
+    let state = M::pre_upgrade().expect("pre-upgrade checks pass");
+    M::on_runtime_upgrade();
+    #[cfg(feature = "try-runtime")]
+    M::post_upgrade(state).expect("post-upgrade checks pass");
+}
+
+#[test]
+fn registration_holds_the_stake() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, 150);
+
+        assert_eq!(staked(ALICE), 150);
+        assert_eq!(balance(ALICE), INITIAL_BALANCE - 150);
+        let provider = ProviderStaking::providers(ALICE).expect("registered");
+        assert_eq!((provider.status, provider.hardware_info.gpu_tier), (ProviderStatus::Active, GpuTier::Consumer));
+        assert!(IndexPosition::<Test>::contains_key(ALICE));
+
+        assert_noop!(
+            ProviderStaking::register_provider(RuntimeOrigin::signed(BOB), CONSUMER_STAKE - 1, consumer_hardware()),
+            Error::<Test>::StakeBelowMinimum
+        );
+        assert_noop!(
+            ProviderStaking::register_provider(RuntimeOrigin::signed(BOB), CONSUMER_STAKE, hardware(4, 80)),
+            Error::<Test>::HardwareBelowMinimumTier
+        );
+    });
+}
+
+#[test]
+fn stake_is_released_after_unbonding() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        assert_ok!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(ALICE)));
+        assert!(!IndexPosition::<Test>::contains_key(ALICE));
+
+        run_to_block(20);
+        assert_noop!(ProviderStaking::withdraw_stake(RuntimeOrigin::signed(ALICE)), Error::<Test>::StillUnbonding);
+
+        run_to_block(21);
+        assert_ok!(ProviderStaking::withdraw_stake(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(staked(ALICE), 0);
+        assert_eq!(balance(ALICE), INITIAL_BALANCE);
+        assert!(!ProviderStaking::is_provider(&ALICE));
+        assert_eq!(ProviderStaking::provider_count(), 0);
+    });
+}
+
+#[test]
+fn pending_slashes_block_withdrawal() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        assert_ok!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(ALICE)));
+        run_to_block(15);
+        slash(ALICE, None);
+
+        run_to_block(21);
+        assert_noop!(
+            ProviderStaking::withdraw_stake(RuntimeOrigin::signed(ALICE)),
+            Error::<Test>::PendingSlashExists
+        );
+
+        assert_ok!(ProviderStaking::cancel_slash(RuntimeOrigin::root(), 0));
+        assert_ok!(ProviderStaking::withdraw_stake(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(balance(ALICE), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn deferred_slash_moves_stake_to_the_insurance_pool() {
+    new_test_ext().execute_with(|| {
+        let pool = ProviderStaking::insurance_pool_account();
+        register(ALICE, CONSUMER_STAKE);
+        slash(ALICE, None);
+
+        let (slash_id, pending) = ProviderStaking::pending_slashes_of(&ALICE).pop().expect("slash queued");
+        assert_eq!((pending.amount, pending.apply_at), (10, 11));
+
+        run_to_block(10);
+        assert_eq!(staked(ALICE), CONSUMER_STAKE);
+
+        run_to_block(11);
+        assert_eq!(staked(ALICE), 90);
+        assert_eq!(stake_of(ALICE), 90);
+        assert_eq!(balance(pool), 11);
+        assert!(PendingSlashes::<Test>::get(slash_id).is_none());
+        assert_eq!(ProviderStaking::pending_slash_count(ALICE), 0);
+        assert_eq!(slashed(), vec![ALICE]);
+
+        let log = ProviderStaking::slash_log_of(&ALICE);
+        assert_eq!(log.len(), 1);
+        assert_eq!((log[0].1.amount, log[0].1.outcome.clone()), (10, SlashOutcome::Applied));
+
+        // The stake fell below the tier minimum
+        assert_eq!(status_of(ALICE), ProviderStatus::Suspended);
+        assert_eq!(ProviderStaking::providers(ALICE).unwrap().reputation_score, 400);
+    });
+}
+
+#[test]
+fn appealed_slash_waits_for_governance() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        slash(ALICE, None);
+        let slash_id = 0;
+
+        assert_noop!(
+            ProviderStaking::appeal_slash(RuntimeOrigin::signed(BOB), slash_id, b"cid".to_vec()),
+            Error::<Test>::NotSlashedProvider
+        );
+        assert_ok!(ProviderStaking::appeal_slash(RuntimeOrigin::signed(ALICE), slash_id, b"cid".to_vec()));
+        assert_noop!(
+            ProviderStaking::appeal_slash(RuntimeOrigin::signed(ALICE), slash_id, b"cid".to_vec()),
+            Error::<Test>::AlreadyAppealed
+        );
+
+        run_to_block(15);
+        assert_eq!(staked(ALICE), CONSUMER_STAKE);
+        assert!(PendingSlashes::<Test>::contains_key(slash_id));
+
+        assert_ok!(ProviderStaking::confirm_slash(RuntimeOrigin::root(), slash_id));
+        assert_eq!(staked(ALICE), 90);
+        assert!(PendingSlashes::<Test>::get(slash_id).is_none());
+        assert_eq!(ProviderStaking::pending_slash_count(ALICE), 0);
+    });
+}
+
+#[test]
+fn slashes_cannot_be_appealed_once_due() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        slash(ALICE, None);
+
+        // Due but not yet processed
+        System::set_block_number(11);
+        assert_noop!(
+            ProviderStaking::appeal_slash(RuntimeOrigin::signed(ALICE), 0, b"cid".to_vec()),
+            Error::<Test>::AppealWindowClosed
+        );
+    });
+}
+
+#[test]
+fn cancelled_slash_is_logged_without_moving_funds() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        slash(ALICE, None);
+
+        assert_ok!(ProviderStaking::cancel_slash(RuntimeOrigin::root(), 0));
+        assert_noop!(ProviderStaking::cancel_slash(RuntimeOrigin::root(), 0), Error::<Test>::SlashNotFound);
+
+        run_to_block(11);
+        assert_eq!(staked(ALICE), CONSUMER_STAKE);
+        assert_eq!(ProviderStaking::pending_slash_count(ALICE), 0);
+        assert!(ProviderStaking::pending_slashes_of(&ALICE).is_empty());
+        assert!(slashed().is_empty());
+
+        let log = ProviderStaking::slash_log_of(&ALICE);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].1.outcome, SlashOutcome::Cancelled);
+        System::assert_last_event(Event::SlashCancelled { slash_id: 0, provider: ALICE, slash_index: 0 }.into());
+    });
+}
+
+#[test]
+fn old_slash_records_move_to_the_archive() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, 1000);
+        slash(ALICE, None);
+        run_to_block(11);
+
+        for slash_id in 1..4 {
+            slash(ALICE, None);
+            assert_ok!(ProviderStaking::cancel_slash(RuntimeOrigin::root(), slash_id));
+        }
+
+        let indices = ProviderStaking::slash_log_of(&ALICE).into_iter().map(|(index, _)| index).collect::<Vec<_>>();
+        assert_eq!(indices, vec![1, 2, 3]);
+        assert_eq!(ProviderStaking::slash_count(ALICE), 4);
+        assert_eq!(ProviderStaking::applied_slash_count(ALICE), 1);
+        assert!(ProviderStaking::slash_log(ALICE, 0).is_none());
+
+        let archived = ProviderStaking::slash_archive(ALICE, 0).expect("record archived");
+        assert_eq!((archived.amount, archived.outcome.clone()), (100, SlashOutcome::Applied));
+        assert_eq!(ProviderStaking::slash_record(&ALICE, 0), Some(archived));
+    });
+}
+
+#[test]
+fn slashes_spill_into_later_blocks() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, 1000);
+
+        for _ in 0..3 {
+            slash(ALICE, None);
+        }
+        let apply_at = |slash_id: SlashId| PendingSlashes::<Test>::get(slash_id).unwrap().apply_at;
+        assert_eq!((apply_at(0), apply_at(1), apply_at(2)), (11, 11, 12));
+
+        // Two slashes per block, up to one more deferral period
+        for _ in 3..22 {
+            slash(ALICE, None);
+        }
+        assert_eq!(apply_at(21), 21);
+        assert_noop!(
+            ProviderStaking::slash_provider(RuntimeOrigin::root(), ALICE, SlashReason::Downtime, None, None),
+            Error::<Test>::TooManySlashesScheduled
+        );
+    });
+}
+
+#[test]
+fn compensation_is_paid_from_the_insurance_pool() {
+    new_test_ext().execute_with(|| {
+        let pool = ProviderStaking::insurance_pool_account();
+        let task = task_id(1);
+        add_task(task, CREATOR, 60);
+        register(ALICE, 1000);
+        slash(ALICE, Some(task));
+        slash(ALICE, None);
+        run_to_block(11);
+        assert_eq!(balance(pool), 201);
+
+        assert_noop!(
+            ProviderStaking::claim_compensation(RuntimeOrigin::signed(BOB), ALICE, 0),
+            Error::<Test>::NotTaskCreator
+        );
+        assert_noop!(
+            ProviderStaking::claim_compensation(RuntimeOrigin::signed(CREATOR), ALICE, 1),
+            Error::<Test>::NotCompensable
+        );
+
+        // Capped by the task's bounty
+        assert_ok!(ProviderStaking::claim_compensation(RuntimeOrigin::signed(CREATOR), ALICE, 0));
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE + 60);
+        assert_eq!(balance(pool), 141);
+        assert_eq!(ProviderStaking::compensation_paid(ALICE, 0), Some(60));
+        assert_eq!(ProviderStaking::task_compensation(task), 60);
+
+        assert_noop!(
+            ProviderStaking::claim_compensation(RuntimeOrigin::signed(CREATOR), ALICE, 0),
+            Error::<Test>::AlreadyCompensated
+        );
+    });
+}
+
+#[test]
+fn archived_slashes_can_still_be_compensated() {
+    new_test_ext().execute_with(|| {
+        let task = task_id(1);
+        add_task(task, CREATOR, 1000);
+        register(ALICE, 1000);
+        slash(ALICE, Some(task));
+        run_to_block(11);
+        for slash_id in 1..4 {
+            slash(ALICE, None);
+            assert_ok!(ProviderStaking::cancel_slash(RuntimeOrigin::root(), slash_id));
+        }
+        assert!(ProviderStaking::slash_archive(ALICE, 0).is_some());
+
+        assert_ok!(ProviderStaking::claim_compensation(RuntimeOrigin::signed(CREATOR), ALICE, 0));
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE + 100);
+        assert_eq!(ProviderStaking::compensation_paid(ALICE, 0), Some(100));
+        assert_noop!(
+            ProviderStaking::claim_compensation(RuntimeOrigin::signed(CREATOR), ALICE, 0),
+            Error::<Test>::AlreadyCompensated
+        );
+    });
+}
+
+#[test]
+fn passed_challenge_pays_the_bond_to_the_provider() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        set_session_key(ALICE);
+        assert_ok!(ProviderStaking::issue_challenge(RuntimeOrigin::signed(BOB), ALICE));
+        assert_eq!(bonded(BOB), CHALLENGE_BOND);
+        assert_noop!(
+            ProviderStaking::issue_challenge(RuntimeOrigin::signed(CHARLIE), ALICE),
+            Error::<Test>::ChallengeAlreadyActive
+        );
+
+        assert_noop!(
+            ProviderStaking::verify_challenge(RuntimeOrigin::root(), 0, true),
+            Error::<Test>::InvalidChallengeStatus
+        );
+        submit_result(ALICE, 0);
+        assert_ok!(ProviderStaking::verify_challenge(RuntimeOrigin::root(), 0, true));
+
+        assert_eq!(bonded(BOB), 0);
+        assert_eq!(balance(BOB), INITIAL_BALANCE - CHALLENGE_BOND);
+        assert_eq!(balance(ALICE), INITIAL_BALANCE - CONSUMER_STAKE + CHALLENGE_BOND);
+        assert!(ProviderStaking::is_hardware_verified(&ALICE));
+        assert!(ProviderStaking::active_challenge(ALICE).is_none());
+    });
+}
+
+#[test]
+fn failed_challenge_returns_the_bond_and_queues_a_slash() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        set_session_key(ALICE);
+        assert_ok!(ProviderStaking::issue_challenge(RuntimeOrigin::signed(BOB), ALICE));
+        submit_result(ALICE, 0);
+        assert_ok!(ProviderStaking::verify_challenge(RuntimeOrigin::root(), 0, false));
+
+        assert_eq!(bonded(BOB), 0);
+        assert_eq!(balance(BOB), INITIAL_BALANCE);
+        assert!(ProviderStaking::challenges(0).is_none());
+
+        let (_, pending) = ProviderStaking::pending_slashes_of(&ALICE).pop().expect("slash queued");
+        assert_eq!(pending.reason, SlashReason::FalseHardwareClaim);
+        assert!(pending.evidence_hash.is_some());
+    });
+}
+
+#[test]
+fn unanswered_challenge_fails_at_its_deadline() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        assert_ok!(ProviderStaking::issue_challenge(RuntimeOrigin::signed(BOB), ALICE));
+
+        run_to_block(6);
+        assert!(ProviderStaking::challenges(0).is_none());
+        assert_eq!(bonded(BOB), 0);
+        assert_eq!(ProviderStaking::pending_slash_count(ALICE), 1);
+        System::assert_last_event(Event::ChallengeFailed { challenge_id: 0, provider: ALICE }.into());
+    });
+}
+
+#[test]
+fn unverified_result_expires_without_a_slash() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        set_session_key(ALICE);
+        assert_ok!(ProviderStaking::issue_challenge(RuntimeOrigin::signed(BOB), ALICE));
+        run_to_block(2);
+        submit_result(ALICE, 0);
+
+        // The response deadline no longer applies
+        run_to_block(6);
+        assert_eq!(ProviderStaking::challenges(0).map(|c| c.status), Some(ChallengeStatus::AwaitingVerification));
+
+        run_to_block(7);
+        assert!(ProviderStaking::challenges(0).is_none());
+        assert_eq!(bonded(BOB), 0);
+        assert_eq!(balance(BOB), INITIAL_BALANCE);
+        assert_eq!(ProviderStaking::pending_slash_count(ALICE), 0);
+        System::assert_last_event(Event::ChallengeExpired { challenge_id: 0, provider: ALICE }.into());
+    });
+}
+
+#[test]
+fn tiers_are_derived_from_the_declared_hardware() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(ProviderStaking::derive_tier(&hardware(10, 80)), Some(GpuTier::Consumer));
+        // Prosumer memory without the compute capability to match
+        assert_eq!(ProviderStaking::derive_tier(&hardware(24, 80)), Some(GpuTier::Consumer));
+        assert_eq!(ProviderStaking::derive_tier(&hardware(24, 89)), Some(GpuTier::Prosumer));
+        assert_eq!(ProviderStaking::derive_tier(&hardware(80, 90)), Some(GpuTier::Professional));
+        assert_eq!(ProviderStaking::derive_tier(&hardware(4, 90)), None);
+
+        // The declared tier is ignored
+        let mut declared = consumer_hardware();
+        declared.gpu_tier = GpuTier::Professional;
+        assert_ok!(ProviderStaking::register_provider(RuntimeOrigin::signed(ALICE), CONSUMER_STAKE, declared));
+        assert_eq!(ProviderStaking::providers(ALICE).unwrap().hardware_info.gpu_tier, GpuTier::Consumer);
+        assert_noop!(
+            ProviderStaking::register_provider(RuntimeOrigin::signed(BOB), 999, hardware(80, 90)),
+            Error::<Test>::StakeBelowMinimum
+        );
+
+        assert_ok!(ProviderStaking::set_tier_params(
+            RuntimeOrigin::root(),
+            GpuTier::Prosumer,
+            TierParams { min_vram_gb: 10, min_compute_capability: 80, min_stake: 500 },
+        ));
+        assert_eq!(ProviderStaking::derive_tier(&consumer_hardware()), Some(GpuTier::Prosumer));
+        assert_eq!(ProviderStaking::minimum_stake_for(GpuTier::Prosumer), 500);
+    });
+}
+
+#[test]
+fn hardware_updates_must_be_covered_by_the_stake() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, 600);
+
+        assert_noop!(
+            ProviderStaking::update_hardware(RuntimeOrigin::signed(ALICE), hardware(80, 90)),
+            Error::<Test>::InsufficientStake
+        );
+        assert_noop!(
+            ProviderStaking::update_hardware(RuntimeOrigin::signed(ALICE), hardware(4, 90)),
+            Error::<Test>::HardwareBelowMinimumTier
+        );
+        assert_noop!(
+            ProviderStaking::update_hardware(RuntimeOrigin::signed(BOB), hardware(24, 89)),
+            Error::<Test>::ProviderNotFound
+        );
+
+        assert_ok!(ProviderStaking::update_hardware(RuntimeOrigin::signed(ALICE), hardware(24, 89)));
+        assert_eq!(ProviderStaking::providers(ALICE).unwrap().hardware_info.gpu_tier, GpuTier::Prosumer);
+        System::assert_last_event(Event::HardwareUpdated { provider: ALICE, gpu_tier: GpuTier::Prosumer }.into());
+
+        // Moving down a tier is always covered
+        assert_ok!(ProviderStaking::update_hardware(RuntimeOrigin::signed(ALICE), consumer_hardware()));
+        assert_eq!(ProviderStaking::providers(ALICE).unwrap().hardware_info.gpu_tier, GpuTier::Consumer);
+    });
+}
+
+fn multiaddr(addr: &[u8]) -> Endpoint {
+    Endpoint::Multiaddr(BoundedVec::truncate_from(addr.to_vec()))
+}
+
+#[test]
+fn endpoints_are_validated_and_bounded() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        let https = Endpoint::Https(BoundedVec::truncate_from(b"https://alice.example".to_vec()));
+
+        assert_noop!(
+            ProviderStaking::set_network_info(RuntimeOrigin::signed(ALICE), vec![multiaddr(b"ip4/1.2.3.4")], None, None),
+            Error::<Test>::InvalidEndpoint
+        );
+        assert_noop!(
+            ProviderStaking::set_network_info(
+                RuntimeOrigin::signed(ALICE),
+                vec![Endpoint::Https(BoundedVec::truncate_from(b"http://alice.example".to_vec()))],
+                None,
+                None,
+            ),
+            Error::<Test>::InvalidEndpoint
+        );
+        assert_noop!(
+            ProviderStaking::set_network_info(RuntimeOrigin::signed(ALICE), vec![https.clone(); 5], None, None),
+            Error::<Test>::TooManyEndpoints
+        );
+        assert_noop!(
+            ProviderStaking::set_network_info(RuntimeOrigin::signed(BOB), vec![https.clone()], None, None),
+            Error::<Test>::ProviderNotFound
+        );
+
+        let peer_id = BoundedVec::truncate_from(vec![0x12, 0x20, 7]);
+        assert_ok!(ProviderStaking::set_network_info(
+            RuntimeOrigin::signed(ALICE),
+            vec![multiaddr(b"/ip4/1.2.3.4/tcp/30333"), https.clone()],
+            Some(peer_id.clone()),
+            Some([9; 32]),
+        ));
+        let provider = ProviderStaking::providers(ALICE).expect("registered");
+        assert_eq!(provider.endpoints.to_vec(), vec![multiaddr(b"/ip4/1.2.3.4/tcp/30333"), https]);
+        assert_eq!((provider.peer_id, provider.encryption_key), (Some(peer_id), Some([9; 32])));
+        System::assert_last_event(Event::NetworkInfoUpdated { provider: ALICE }.into());
+    });
+}
+
+#[test]
+fn only_reachable_providers_are_listed() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        register(BOB, CONSUMER_STAKE);
+        register(CHARLIE, CONSUMER_STAKE);
+        for who in [ALICE, BOB] {
+            assert_ok!(ProviderStaking::set_network_info(
+                RuntimeOrigin::signed(who),
+                vec![multiaddr(b"/ip4/1.2.3.4/tcp/30333")],
+                None,
+                None,
+            ));
+        }
+        assert_ok!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(BOB)));
+
+        let reachable = |status: Option<ProviderStatus>, requirements: HardwareRequirements| {
+            ProviderStaking::reachable_providers(status, requirements)
+                .into_iter()
+                .map(|provider| provider.account)
+                .collect::<Vec<_>>()
+        };
+        // CHARLIE has no endpoints and unbonding providers are left out by default
+        assert_eq!(reachable(None, HardwareRequirements::default()), vec![ALICE]);
+        assert_eq!(reachable(Some(ProviderStatus::Unbonding), HardwareRequirements::default()), vec![BOB]);
+        assert!(reachable(None, HardwareRequirements { min_vram_gb: 16, ..Default::default() }).is_empty());
+    });
+}
+
+#[test]
+fn challenge_results_must_be_signed_by_the_challenged_provider() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        register(BOB, CONSUMER_STAKE);
+        set_session_key(ALICE);
+        set_session_key(BOB);
+        assert_ok!(ProviderStaking::issue_challenge(RuntimeOrigin::signed(CHARLIE), ALICE));
+
+        let result = ChallengeResult { provider: ALICE, challenge_id: 0, result_hash: H256::repeat_byte(7) };
+        let signature = session_key(ALICE).sign(&result.encode());
+        let forged = session_key(BOB).sign(&result.encode());
+        assert_eq!(
+            validate(&Call::submit_challenge_result { result: result.clone(), signature: forged }),
+            InvalidTransaction::BadProof.into()
+        );
+        let claimed = ChallengeResult { provider: BOB, ..result.clone() };
+        let signature_of_bob = session_key(BOB).sign(&claimed.encode());
+        assert_eq!(
+            validate(&Call::submit_challenge_result { result: claimed, signature: signature_of_bob }),
+            InvalidTransaction::Custom(UNSIGNED_NOT_CHALLENGED_PROVIDER).into()
+        );
+        // The stash account cannot answer for the session key
+        assert_noop!(
+            ProviderStaking::submit_challenge_result(RuntimeOrigin::signed(ALICE), result.clone(), signature.clone()),
+            DispatchError::BadOrigin
+        );
+
+        submit_result(ALICE, 0);
+        assert_eq!(
+            validate(&Call::submit_challenge_result { result, signature }),
+            InvalidTransaction::Stale.into()
+        );
+    });
+}
+
+#[test]
+fn session_keys_are_proven_and_can_be_rotated() {
+    new_test_ext().execute_with(|| {
+        let key = session_key(ALICE);
+        let proof = key.sign(&ProviderStaking::key_ownership_payload(&ALICE));
+        assert_noop!(
+            ProviderStaking::set_provider_keys(RuntimeOrigin::signed(ALICE), key.public(), proof.clone()),
+            Error::<Test>::ProviderNotFound
+        );
+        register(ALICE, CONSUMER_STAKE);
+        register(BOB, CONSUMER_STAKE);
+
+        // The proof binds the key to one stash account
+        assert_noop!(
+            ProviderStaking::set_provider_keys(RuntimeOrigin::signed(BOB), key.public(), proof.clone()),
+            Error::<Test>::InvalidKeyProof
+        );
+        assert_ok!(ProviderStaking::set_provider_keys(RuntimeOrigin::signed(ALICE), key.public(), proof));
+        assert_eq!(ProviderStaking::provider_keys(ALICE), Some(key.public()));
+        assert_eq!(ProviderStaking::key_owner(key.public()), Some(ALICE));
+        System::assert_last_event(Event::ProviderKeysSet { provider: ALICE }.into());
+
+        let proof_for_bob = key.sign(&ProviderStaking::key_ownership_payload(&BOB));
+        assert_noop!(
+            ProviderStaking::set_provider_keys(RuntimeOrigin::signed(BOB), key.public(), proof_for_bob.clone()),
+            Error::<Test>::KeyAlreadyInUse
+        );
+
+        // Rotating releases the old key
+        let rotated = session_key(CHARLIE);
+        let rotated_proof = rotated.sign(&ProviderStaking::key_ownership_payload(&ALICE));
+        assert_ok!(ProviderStaking::set_provider_keys(RuntimeOrigin::signed(ALICE), rotated.public(), rotated_proof));
+        assert_eq!(ProviderStaking::provider_keys(ALICE), Some(rotated.public()));
+        assert!(ProviderStaking::key_owner(key.public()).is_none());
+        assert_ok!(ProviderStaking::set_provider_keys(RuntimeOrigin::signed(BOB), key.public(), proof_for_bob));
+
+        // Only the current key signs for the provider
+        assert!(!ProviderStaking::verify_provider_signature(&ALICE, b"payload", &key.sign(b"payload")));
+        assert!(ProviderStaking::verify_provider_signature(&ALICE, b"payload", &rotated.sign(b"payload")));
+    });
+}
+
+#[test]
+fn heartbeats_are_checked_and_rate_limited() {
+    new_test_ext().execute_with(|| {
+        let heartbeat = |block_number: u64, key: &crypto::Pair| {
+            let heartbeat = Heartbeat { provider: ALICE, block_number };
+            let signature = key.sign(&heartbeat.encode());
+            (heartbeat, signature)
+        };
+        let validate_heartbeat = |block_number: u64, key: &crypto::Pair| {
+            let (heartbeat, signature) = heartbeat(block_number, key);
+            validate(&Call::heartbeat { heartbeat, signature })
+        };
+        register(ALICE, CONSUMER_STAKE);
+        run_to_block(20);
+
+        // No session key yet
+        assert_eq!(validate_heartbeat(20, &session_key(ALICE)), InvalidTransaction::BadProof.into());
+        set_session_key(ALICE);
+        assert_eq!(validate_heartbeat(20, &session_key(BOB)), InvalidTransaction::BadProof.into());
+        assert_eq!(validate_heartbeat(21, &session_key(ALICE)), InvalidTransaction::Future.into());
+
+        assert_ok!(validate_heartbeat(20, &session_key(ALICE)));
+        let (beat, signature) = heartbeat(20, &session_key(ALICE));
+        assert_noop!(
+            ProviderStaking::heartbeat(RuntimeOrigin::signed(ALICE), beat.clone(), signature.clone()),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(ProviderStaking::heartbeat(RuntimeOrigin::none(), beat, signature));
+        assert_eq!(ProviderStaking::last_heartbeat(ALICE), Some(20));
+        System::assert_last_event(Event::HeartbeatReceived { provider: ALICE, block_number: 20 }.into());
+
+        // One heartbeat per interval, and none for blocks before the last one
+        run_to_block(29);
+        assert_eq!(validate_heartbeat(29, &session_key(ALICE)), InvalidTransaction::Stale.into());
+        run_to_block(30);
+        assert_eq!(validate_heartbeat(15, &session_key(ALICE)), InvalidTransaction::Stale.into());
+        assert_ok!(validate_heartbeat(30, &session_key(ALICE)));
+    });
+}
+
+#[test]
+fn top_providers_rank_by_tier_then_reputation() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        register(BOB, CONSUMER_STAKE);
+        register(CREATOR, CONSUMER_STAKE);
+        assert_ok!(ProviderStaking::register_provider(RuntimeOrigin::signed(CHARLIE), 1000, hardware(80, 90)));
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), ALICE, 720));
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), BOB, 750));
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), CREATOR, 900));
+
+        // ALICE and BOB share a bucket and are ordered by exact reputation
+        let any = HardwareRequirements::default();
+        assert_eq!(ProviderStaking::top_providers(&any, 10), vec![CHARLIE, CREATOR, BOB, ALICE]);
+        assert_eq!(ProviderStaking::top_providers(&any, 2), vec![CHARLIE, CREATOR]);
+
+        let professional = HardwareRequirements { min_gpu_tier: GpuTier::Professional, ..Default::default() };
+        assert_eq!(ProviderStaking::top_providers(&professional, 10), vec![CHARLIE]);
+        let large_memory = HardwareRequirements { min_vram_gb: 40, ..Default::default() };
+        assert_eq!(ProviderStaking::top_providers(&large_memory, 10), vec![CHARLIE]);
+    });
+}
+
+#[test]
+fn providers_are_reindexed_as_they_change() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, 600);
+        assert_eq!(ProviderStaking::index_position(ALICE), Some((GpuTier::Consumer, 5)));
+
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), ALICE, 720));
+        assert_eq!(ProviderStaking::index_position(ALICE), Some((GpuTier::Consumer, 7)));
+        assert!(ProviderIndex::<Test>::contains_key((GpuTier::Consumer, 7, ALICE)));
+        assert!(!ProviderIndex::<Test>::contains_key((GpuTier::Consumer, 5, ALICE)));
+
+        assert_ok!(ProviderStaking::update_hardware(RuntimeOrigin::signed(ALICE), hardware(24, 89)));
+        assert_eq!(ProviderStaking::index_position(ALICE), Some((GpuTier::Prosumer, 7)));
+        assert_eq!(ProviderIndex::<Test>::iter_keys().count(), 1);
+
+        // Only active and idle providers can be matched
+        assert_ok!(ProviderStaking::chill(RuntimeOrigin::signed(ALICE)));
+        assert!(ProviderStaking::index_position(ALICE).is_none());
+        assert_eq!(ProviderIndex::<Test>::iter_keys().count(), 0);
+        assert!(ProviderStaking::top_providers(&HardwareRequirements::default(), 10).is_empty());
+
+        assert_ok!(ProviderStaking::reactivate(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(ProviderStaking::top_providers(&HardwareRequirements::default(), 10), vec![ALICE]);
+
+        assert_ok!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(ALICE)));
+        assert!(ProviderStaking::index_position(ALICE).is_none());
+        assert_eq!(ProviderIndex::<Test>::iter_keys().count(), 0);
+    });
+}
+
+#[test]
+fn jail_periods_escalate_until_the_provider_is_banned() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), ALICE, 100));
+        assert_eq!(status_of(ALICE), ProviderStatus::Suspended);
+        assert!(ProviderStaking::index_position(ALICE).is_none());
+        System::assert_has_event(Event::ProviderJailed { provider: ALICE, jailed_until: 11, times_jailed: 1 }.into());
+
+        // The jail has to be served in full
+        run_to_block(10);
+        assert_noop!(ProviderStaking::reactivate(RuntimeOrigin::signed(ALICE)), Error::<Test>::StillJailed);
+        assert_noop!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(ALICE)), Error::<Test>::StillJailed);
+
+        run_to_block(11);
+        assert_ok!(ProviderStaking::reactivate(RuntimeOrigin::signed(ALICE)));
+        System::assert_last_event(Event::ProviderUnjailed { provider: ALICE, new_status: ProviderStatus::Active }.into());
+        assert_eq!(ProviderStaking::providers(ALICE).unwrap().reputation_score, 300);
+        assert_eq!(ProviderStaking::jails(ALICE).jailed_until, None);
+        assert!(ProviderStaking::index_position(ALICE).is_some());
+
+        // Each jailing doubles the period
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), ALICE, 100));
+        System::assert_has_event(Event::ProviderJailed { provider: ALICE, jailed_until: 31, times_jailed: 2 }.into());
+        run_to_block(31);
+        assert_ok!(ProviderStaking::reactivate(RuntimeOrigin::signed(ALICE)));
+
+        // The third jailing is a ban
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), ALICE, 100));
+        System::assert_has_event(Event::ProviderBanned { provider: ALICE }.into());
+        assert_eq!(status_of(ALICE), ProviderStatus::Banned);
+        assert!(ProviderStaking::is_banned(&ALICE));
+
+        run_to_block(500);
+        assert_noop!(ProviderStaking::reactivate(RuntimeOrigin::signed(ALICE)), Error::<Test>::ProviderBanned);
+        assert_noop!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(ALICE)), Error::<Test>::ProviderBanned);
+        assert_noop!(
+            ProviderStaking::update_status(RuntimeOrigin::root(), ALICE, ProviderStatus::Active),
+            Error::<Test>::ProviderBanned
+        );
+    });
+}
+
+#[test]
+fn leaving_jail_requires_the_minimum_stake() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        slash(ALICE, None);
+
+        // The applied slash leaves the stake below the tier minimum
+        run_to_block(11);
+        assert_eq!(status_of(ALICE), ProviderStatus::Suspended);
+        assert_eq!(ProviderStaking::jails(ALICE).jailed_until, Some(21));
+
+        run_to_block(21);
+        assert_noop!(ProviderStaking::reactivate(RuntimeOrigin::signed(ALICE)), Error::<Test>::InsufficientStake);
+
+        assert_ok!(ProviderStaking::bond_extra(RuntimeOrigin::signed(ALICE), 10));
+        assert_ok!(ProviderStaking::chill(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(status_of(ALICE), ProviderStatus::Offline);
+        assert_eq!(ProviderStaking::providers(ALICE).unwrap().reputation_score, 300);
+        System::assert_last_event(Event::ProviderUnjailed { provider: ALICE, new_status: ProviderStatus::Offline }.into());
+    });
+}
+
+#[test]
+fn jailing_does_not_interrupt_unbonding() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        assert_ok!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(ALICE)));
+
+        assert_ok!(ProviderStaking::update_reputation(RuntimeOrigin::root(), ALICE, 100));
+        assert_eq!(status_of(ALICE), ProviderStatus::Unbonding);
+        assert_eq!(ProviderStaking::jails(ALICE).times_jailed, 1);
+        System::assert_has_event(Event::ProviderJailed { provider: ALICE, jailed_until: 11, times_jailed: 1 }.into());
+
+        run_to_block(21);
+        assert_ok!(ProviderStaking::withdraw_stake(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(balance(ALICE), INITIAL_BALANCE);
+
+        // The jailing still counts towards a ban
+        assert_eq!(ProviderStaking::jails(ALICE).times_jailed, 1);
+    });
+}
+
+#[test]
+fn rewards_are_restaked_and_compounded() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+
+        assert_ok!(ProviderStaking::bond_extra(RuntimeOrigin::signed(ALICE), 50));
+        assert_ok!(<ProviderStaking as RewardSettings<_, _>>::restake(&ALICE, 25));
+        assert_eq!((stake_of(ALICE), staked(ALICE)), (175, 175));
+
+        assert_ok!(<ProviderStaking as RewardSettings<_, _>>::compound(&ALICE, &CREATOR, 200));
+        assert_eq!((stake_of(ALICE), staked(ALICE)), (375, 375));
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE - 200);
+
+        assert_ok!(ProviderStaking::start_unbonding(RuntimeOrigin::signed(ALICE)));
+        assert_noop!(
+            <ProviderStaking as RewardSettings<_, _>>::compound(&ALICE, &CREATOR, 200),
+            Error::<Test>::StillUnbonding
+        );
+        assert_noop!(
+            <ProviderStaking as RewardSettings<_, _>>::restake(&ALICE, 25),
+            Error::<Test>::StillUnbonding
+        );
+    });
+}
+
+#[test]
+fn settled_rewards_and_stream_payments_build_the_earnings_history() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        let (first, second) = (H256::repeat_byte(1), H256::repeat_byte(2));
+
+        System::set_block_number(5);
+        <ProviderStaking as ProviderStats<_, _, _>>::on_stream_paid(&ALICE, &first, 30);
+        System::set_block_number(8);
+        <ProviderStaking as ProviderStats<_, _, _>>::on_reward_settled(&ALICE, &first, 12, 200);
+        <ProviderStaking as ProviderStats<_, _, _>>::on_reward_settled(&ALICE, &second, 3, 50);
+
+        let provider = ProviderStaking::providers(ALICE).expect("registered");
+        assert_eq!(
+            (provider.total_tasks_completed, provider.total_gradients_computed, provider.total_tokens_earned),
+            (2, 15, 280)
+        );
+
+        let mut history = ProviderStaking::earnings_history(&ALICE);
+        history.sort_by_key(|(task_id, _)| *task_id);
+        assert_eq!(
+            history,
+            vec![
+                (first, TaskEarnings { gradients: 12, earned: 230, last_paid_at: 8 }),
+                (second, TaskEarnings { gradients: 3, earned: 50, last_paid_at: 8 }),
+            ]
+        );
+        assert!(ProviderStaking::earnings_history(&BOB).is_empty());
+    });
+}
+
+#[test]
+fn migration_to_v1_marks_old_slashes_applied() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<ProviderStaking>();
+        let key = migrations::v1::SlashHistory::<Test>::hashed_key_for(ALICE, 5);
+        unhashed::put(&key, &(SlashReason::Downtime, 10 as Balance));
+
+        run_migration::<migrations::v1::MigrateToV1<Test>>();
+
+        let record = migrations::v1::SlashHistory::<Test>::get(ALICE, 5).expect("record kept");
+        assert_eq!((record.reason, record.amount, record.outcome), (SlashReason::Downtime, 10, SlashOutcome::Applied));
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(1));
+    });
+}
+
+#[test]
+fn migration_to_v2_moves_slash_history_to_the_slash_log() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(1).put::<ProviderStaking>();
+        let record = |amount| migrations::v1::SlashRecord::<Test> {
+            reason: SlashReason::Downtime,
+            amount,
+            outcome: SlashOutcome::Applied,
+        };
+        migrations::v1::SlashHistory::<Test>::insert(ALICE, 9, record(30));
+        migrations::v1::SlashHistory::<Test>::insert(ALICE, 3, record(10));
+        migrations::v1::SlashHistory::<Test>::insert(BOB, 4, record(20));
+        unhashed::put(
+            &PendingSlashes::<Test>::hashed_key_for(0),
+            &migrations::v2::OldPendingSlash::<Test> {
+                provider: ALICE,
+                reason: SlashReason::MaliciousGradient,
+                amount: 5,
+                created_at: 1,
+                apply_at: 11,
+                appeal: None,
+            },
+        );
+
+        run_migration::<migrations::v2::MigrateToV2<Test>>();
+
+        let alice = ProviderStaking::slash_log_of(&ALICE)
+            .into_iter()
+            .map(|(index, record)| (index, record.amount, record.resolved_at))
+            .collect::<Vec<_>>();
+        assert_eq!(alice, vec![(0, 10, 3), (1, 30, 9)]);
+        assert_eq!(ProviderStaking::slash_count(BOB), 1);
+        assert_eq!(migrations::v1::SlashHistory::<Test>::iter().count(), 0);
+
+        let pending = PendingSlashes::<Test>::get(0).expect("pending slash kept");
+        assert_eq!((pending.provider, pending.amount, pending.task_id), (ALICE, 5, None));
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(2));
+    });
+}
+
+fn old_provider(stake: Balance, status: ProviderStatus, vram_gb: u32) -> migrations::v4::OldProvider<Test> {
+    migrations::v4::OldProvider {
+        stake,
+        status,
+        hardware_info: hardware(vram_gb, 80),
+        reputation_score: 500,
+        total_tasks_completed: 0,
+        total_gradients_computed: 0,
+        total_tokens_earned: 0,
+        registered_at: 1,
+        last_active: 1,
+        unbonding_at: None,
+    }
+}
+
+#[test]
+fn migration_to_v3_seeds_tiers_and_takes_ineligible_providers_offline() {
+    new_test_ext().execute_with(|| {
+        use migrations::v4::old;
+
+        StorageVersion::new(2).put::<ProviderStaking>();
+        let _ = TierTable::<Test>::clear(u32::MAX, None);
+        old::Providers::<Test>::insert(ALICE, old_provider(CONSUMER_STAKE, ProviderStatus::Active, 10));
+        old::Providers::<Test>::insert(BOB, old_provider(CONSUMER_STAKE, ProviderStatus::Idle, 48));
+        old::Providers::<Test>::insert(CHARLIE, old_provider(CONSUMER_STAKE, ProviderStatus::Active, 4));
+
+        run_migration::<migrations::v3::MigrateToV3<Test>>();
+
+        assert_eq!(ProviderStaking::tier_params(GpuTier::Prosumer).map(|p| p.min_stake), Some(500));
+        let alice = old::Providers::<Test>::get(ALICE).expect("provider kept");
+        assert_eq!((alice.status, alice.hardware_info.gpu_tier), (ProviderStatus::Active, GpuTier::Consumer));
+        // Re-derived as professional, but staked for the consumer tier only
+        let bob = old::Providers::<Test>::get(BOB).expect("provider kept");
+        assert_eq!((bob.status, bob.hardware_info.gpu_tier), (ProviderStatus::Offline, GpuTier::Professional));
+        // Below every tier
+        assert_eq!(old::Providers::<Test>::get(CHARLIE).map(|p| p.status), Some(ProviderStatus::Offline));
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(3));
+    });
+}
+
+#[test]
+fn migration_to_v4_adds_empty_network_info() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(3).put::<ProviderStaking>();
+        migrations::v4::old::Providers::<Test>::insert(ALICE, old_provider(CONSUMER_STAKE, ProviderStatus::Active, 10));
+
+        run_migration::<migrations::v4::MigrateToV4<Test>>();
+
+        let provider = ProviderStaking::providers(ALICE).expect("provider kept");
+        assert_eq!(provider.stake, CONSUMER_STAKE);
+        assert!(provider.endpoints.is_empty());
+        assert!(provider.peer_id.is_none() && provider.encryption_key.is_none());
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(4));
+    });
+}
+
+fn provider(stake: Balance, status: ProviderStatus) -> Provider<Test> {
+    Provider {
+        stake,
+        status,
+        hardware_info: consumer_hardware(),
+        reputation_score: 500,
+        total_tasks_completed: 0,
+        total_gradients_computed: 0,
+        total_tokens_earned: 0,
+        registered_at: 1,
+        last_active: 1,
+        unbonding_at: None,
+        endpoints: BoundedVec::default(),
+        peer_id: None,
+        encryption_key: None,
+    }
+}
+
+#[test]
+fn migration_to_v5_moves_reserves_to_holds() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(4).put::<ProviderStaking>();
+        Providers::<Test>::insert(ALICE, provider(CONSUMER_STAKE, ProviderStatus::Active));
+        assert_ok!(Balances::reserve(&ALICE, CONSUMER_STAKE));
+        // Stake the reserve no longer fully covers
+        Providers::<Test>::insert(BOB, provider(200, ProviderStatus::Active));
+        assert_ok!(Balances::reserve(&BOB, 150));
+        Challenges::<Test>::insert(0, HardwareChallenge {
+            provider: ALICE,
+            challenger: Some(CHARLIE),
+            seed: H256::zero(),
+            issued_at: 1,
+            deadline: 6,
+            result_hash: None,
+            status: ChallengeStatus::AwaitingResult,
+        });
+        assert_ok!(Balances::reserve(&CHARLIE, CHALLENGE_BOND));
+
+        run_migration::<migrations::v5::MigrateToV5<Test, Balances>>();
+
+        assert_eq!(staked(ALICE), CONSUMER_STAKE);
+        assert_eq!(Balances::total_balance_on_hold(&ALICE), CONSUMER_STAKE);
+        assert_eq!(staked(BOB), 150);
+        assert_eq!(bonded(CHARLIE), CHALLENGE_BOND);
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(5));
+    });
+}
+
+#[test]
+fn migration_to_v6_indexes_matchable_providers() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        register(BOB, CONSUMER_STAKE);
+        assert_ok!(ProviderStaking::chill(RuntimeOrigin::signed(BOB)));
+        StorageVersion::new(5).put::<ProviderStaking>();
+        let _ = ProviderIndex::<Test>::clear(u32::MAX, None);
+        let _ = IndexPosition::<Test>::clear(u32::MAX, None);
+
+        run_migration::<migrations::v6::MigrateToV6<Test>>();
+
+        assert_eq!(ProviderStaking::index_position(ALICE), Some((GpuTier::Consumer, 5)));
+        assert!(ProviderStaking::index_position(BOB).is_none());
+        assert_eq!(ProviderIndex::<Test>::iter_keys().count(), 1);
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(6));
+    });
+}
+
+#[test]
+fn migration_to_v7_leaves_auto_compounding_off() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(6).put::<ProviderStaking>();
+        unhashed::put(
+            &Settings::<Test>::hashed_key_for(ALICE),
+            &migrations::v7::OldProviderSettings::<AccountId> {
+                payee: RewardDestination::Staked,
+                commission: Perbill::from_percent(50),
+            },
+        );
+
+        run_migration::<migrations::v7::MigrateToV7<Test>>();
+
+        assert_eq!(
+            ProviderStaking::settings(ALICE),
+            ProviderSettings {
+                payee: RewardDestination::Staked,
+                commission: Perbill::from_percent(50),
+                auto_compound: false,
+            }
+        );
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(7));
+    });
+}
+
+#[test]
+fn migration_to_v8_indexes_pending_slashes_by_provider() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, CONSUMER_STAKE);
+        register(BOB, CONSUMER_STAKE);
+        slash(ALICE, None);
+        slash(BOB, None);
+        slash(ALICE, None);
+        StorageVersion::new(7).put::<ProviderStaking>();
+        let _ = ProviderPendingSlashes::<Test>::clear(u32::MAX, None);
+
+        run_migration::<migrations::v8::MigrateToV8<Test>>();
+
+        let alice = ProviderStaking::pending_slashes_of(&ALICE).into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(alice.len(), 2);
+        assert!(alice.contains(&0) && alice.contains(&2));
+        assert_eq!(ProviderStaking::pending_slashes_of(&BOB).len(), 1);
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(8));
+    });
+}
+
+#[test]
+fn migration_to_v9_counts_applied_slashes() {
+    new_test_ext().execute_with(|| {
+        register(ALICE, 1000);
+        register(BOB, CONSUMER_STAKE);
+        slash(ALICE, None);
+        slash(BOB, None);
+        run_to_block(11);
+        for slash_id in 2..5 {
+            slash(ALICE, None);
+            assert_ok!(ProviderStaking::cancel_slash(RuntimeOrigin::root(), slash_id));
+        }
+        slash(ALICE, None);
+        run_to_block(22);
+        assert!(ProviderStaking::slash_archive(ALICE, 0).is_some());
+
+        StorageVersion::new(8).put::<ProviderStaking>();
+        let _ = AppliedSlashCount::<Test>::clear(u32::MAX, None);
+
+        run_migration::<migrations::v9::MigrateToV9<Test>>();
+
+        assert_eq!(ProviderStaking::applied_slash_count(ALICE), 2);
+        assert_eq!(ProviderStaking::applied_slash_count(BOB), 1);
+        assert_eq!(ProviderStaking::slash_count(ALICE), 5);
+        assert_eq!(StorageVersion::get::<ProviderStaking>(), StorageVersion::new(9));
+    });
+}
@@ -4,40 +4,77 @@ use codec::DecodeWithMemTracking;
 use frame_support::{
     dispatch::DispatchResult,
     pallet_prelude::*,
-    traits::{Currency, ExistenceRequirement},
+    storage::with_storage_layer,
+    traits::{
+        fungible::{self, MutateHold},
+        fungibles::{self, Inspect, Mutate},
+        tokens::{Fortitude, Precision, Preservation, Restriction},
+    },
     PalletId,
 };
 use frame_system::pallet_prelude::*;
 use scale_info::prelude::vec::Vec;
 use sp_std;
 use sp_runtime::{
-    traits::{AccountIdConversion, Saturating, Zero, Hash as HashT},
-    Permill,
+    helpers_128bit::multiply_by_rational_with_rounding,
+    traits::{AccountIdConversion, CheckedAdd, CheckedMul, Convert, Saturating, Zero, Hash as HashT},
+    ArithmeticError, Permill, Rounding, SaturatedConversion,
 };
 
 pub use pallet::*;
 
-type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+pub mod merkle;
+pub mod migrations;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use pallet_provider_staking::{
+    ContributionInspect, GpuTier, OnProviderSlashed, ProviderStats, RewardDestination, RewardSettings, SlashIndex,
+};
+use pallet_task_registry::{TaskEscrow, TaskHooks};
+
+type BalanceOf<T> = <<T as Config>::Currency as fungible::Inspect<<T as frame_system::Config>::AccountId>>::Balance;
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
 
+    /// The current storage version.
+    pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
-        /// Currency type for rewards
-        type Currency: Currency<Self::AccountId>;
+        /// Overarching hold reason
+        type RuntimeHoldReason: From<HoldReason>;
+
+        /// Native currency, used for bonds
+        type Currency: fungible::Mutate<Self::AccountId>
+            + fungible::MutateHold<Self::AccountId, Reason = Self::RuntimeHoldReason>;
+
+        /// Identifier of the assets bounties and rewards are paid in
+        type AssetKind: Parameter + MaxEncodedLen + Ord;
+
+        /// Native currency and other assets, used for rewards and fees
+        type Assets: fungibles::Mutate<Self::AccountId, AssetId = Self::AssetKind, Balance = BalanceOf<Self>>;
+
+        /// The native asset
+        #[pallet::constant]
+        type NativeAsset: Get<Self::AssetKind>;
 
         /// Maximum providers in a single batch
         #[pallet::constant]
         type MaxProvidersPerBatch: Get<u32>;
 
-        /// Minimum reward amount
+        /// Minimum reward amount in the native asset; other assets use their minimum balance
         #[pallet::constant]
         type MinimumReward: Get<BalanceOf<Self>>;
 
@@ -49,15 +86,176 @@ pub mod pallet {
         #[pallet::constant]
         type SettlementPeriod: Get<BlockNumberFor<Self>>;
 
-        /// Platform fee percentage
+        /// Blocks after the settlement period during which merkle batch rewards can be claimed;
+        /// the unclaimed rest of the bounty is then refunded
+        #[pallet::constant]
+        type ProofClaimPeriod: Get<BlockNumberFor<Self>>;
+
+        /// Platform fee until governance sets one
+        #[pallet::constant]
+        type DefaultPlatformFee: Get<Permill>;
+
+        /// Highest platform fee governance can set
+        #[pallet::constant]
+        type MaxPlatformFee: Get<Permill>;
+
+        /// Origin that sets the platform fee and where it goes
+        type FeeOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Account receiving fees routed to the treasury
+        type TreasuryAccount: Get<Self::AccountId>;
+
+        /// Account receiving the validator share of split fees
+        type ValidatorFeePot: Get<Self::AccountId>;
+
+        /// Account receiving the staker share of split fees
+        type StakerFeePot: Get<Self::AccountId>;
+
+        /// Provider payout destinations and commission rates
+        type Providers: RewardSettings<Self::AccountId, BalanceOf<Self>>;
+
+        /// Escrowed bounties of completed tasks
+        type Tasks: TaskEscrow<Self::AccountId, Self::Hash, Self::AssetKind, BalanceOf<Self>>;
+
+        /// Hardware multiplier of a GPU tier for on-chain reward computation (100 = 1.0x)
+        type TierMultiplier: Convert<GpuTier, u32>;
+
+        /// Maximum batches waiting for settlement
+        #[pallet::constant]
+        type MaxQueuedBatches: Get<u32>;
+
+        /// Share of a native batch's bounty its coordinator bonds, lost if the batch is found
+        /// fraudulent
+        #[pallet::constant]
+        type CoordinatorBondRatio: Get<Permill>;
+
+        /// Minimum coordinator bond per batch, and the bond of batches paid in other assets
+        #[pallet::constant]
+        type MinCoordinatorBond: Get<BalanceOf<Self>>;
+
+        /// Bond posted with a batch challenge, lost if the challenge is rejected
+        #[pallet::constant]
+        type BatchChallengeBond: Get<BalanceOf<Self>>;
+
+        /// Origin that resolves batch challenges
+        type ArbiterOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Share of a vesting native reward paid out when it is claimed
         #[pallet::constant]
-        type PlatformFeePercentage: Get<Permill>;
+        type ImmediateRelease: Get<Permill>;
+
+        /// Blocks over which the rest of a vesting reward is released linearly
+        #[pallet::constant]
+        type VestingPeriod: Get<BlockNumberFor<Self>>;
+
+        /// Native claims below this amount are paid out in full; other assets never vest
+        #[pallet::constant]
+        type VestingThreshold: Get<BalanceOf<Self>>;
+
+        /// Maximum concurrent vesting schedules per provider
+        #[pallet::constant]
+        type MaxVestingSchedules: Get<u32>;
+
+        /// Gradient commitments and slash counts of providers, used to unlock payment streams
+        type Contributions: ContributionInspect<Self::AccountId, Self::Hash>;
+
+        /// Maximum open payment streams per task
+        #[pallet::constant]
+        type MaxStreamsPerTask: Get<u32>;
+
+        /// Maximum open payment streams per provider
+        #[pallet::constant]
+        type MaxStreamsPerProvider: Get<u32>;
+
+        /// Provider work and earnings counters, updated as rewards settle
+        type Stats: ProviderStats<Self::AccountId, Self::Hash, BalanceOf<Self>>;
+    }
+
+    /// Reasons this pallet places holds on funds
+    #[pallet::composite_enum]
+    pub enum HoldReason {
+        /// Bond of a batch coordinator until the batch settles
+        CoordinatorBond,
+        /// Bond of an open batch challenge
+        BatchChallengeBond,
+    }
+
+    /// Open challenge against a batch, freezing its settlement
+    #[derive(Encode, Decode, Clone, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct BatchChallenge<T: Config> {
+        pub challenger: T::AccountId,
+        pub evidence: BoundedVec<u8, ConstU32<64>>, // Evidence CID
+        pub bond: BalanceOf<T>,
+        pub raised_at: BlockNumberFor<T>,
+    }
+
+    /// Where collected platform fees are sent
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, Copy, RuntimeDebug, TypeInfo, PartialEq, Eq, MaxEncodedLen, Default)]
+    pub enum FeeDestination {
+        /// Transfer to the treasury account
+        #[default]
+        Treasury,
+        /// Burn, reducing total issuance
+        Burn,
+        /// Share between validators and stakers, with the remainder going to the treasury
+        Split { validators: Permill, stakers: Permill },
+    }
+
+    /// Part of a claimed native reward released linearly over time
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    pub struct VestingSchedule<Balance, BlockNumber> {
+        pub locked: Balance,
+        pub released: Balance,
+        pub starting_block: BlockNumber,
+        pub duration: BlockNumber,
+    }
+
+    impl<Balance, BlockNumber> VestingSchedule<Balance, BlockNumber>
+    where
+        Balance: sp_runtime::traits::AtLeast32BitUnsigned + Copy,
+        BlockNumber: sp_runtime::traits::AtLeast32BitUnsigned + Copy,
+    {
+        /// Amount vested by `now` that has not been released yet
+        pub fn releasable(&self, now: BlockNumber) -> Balance {
+            let elapsed = now.saturating_sub(self.starting_block).min(self.duration);
+            let vested = if elapsed >= self.duration {
+                self.locked
+            } else {
+                multiply_by_rational_with_rounding(
+                    self.locked.saturated_into::<u128>(),
+                    elapsed.saturated_into::<u128>(),
+                    self.duration.saturated_into::<u128>(),
+                    Rounding::Down,
+                )
+                .unwrap_or(0)
+                .saturated_into()
+            };
+            vested.saturating_sub(self.released)
+        }
+
+        /// Amount not released yet
+        pub fn remaining(&self) -> Balance {
+            self.locked.saturating_sub(self.released)
+        }
+    }
+
+    /// Fixed payment per closed round to a provider, funded from its task's bounty
+    #[derive(Encode, Decode, Clone, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct PaymentStream<T: Config> {
+        pub asset: T::AssetKind,
+        pub per_round: BalanceOf<T>,
+        pub remaining: BalanceOf<T>,
+        /// Provider's applied slash count when the stream opened; any later applied slash stops the stream
+        pub slashes_at_open: SlashIndex,
     }
 
     #[derive(Encode, Decode, DecodeWithMemTracking, Clone, TypeInfo, PartialEq, MaxEncodedLen)]
     #[scale_info(skip_type_params(T))]
     pub struct RewardBatch<T: Config> {
         pub task_id: T::Hash,
+        pub asset: T::AssetKind,
         pub total_bounty: BalanceOf<T>,
         pub coordinator: T::AccountId,
         pub created_at: BlockNumberFor<T>,
@@ -85,6 +283,35 @@ pub mod pallet {
         }
     }
 
+    /// Proof that a reward is a leaf of a batch's merkle root, see [`crate::merkle`]
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    pub struct MerkleProof<Hash> {
+        pub leaf_index: u32,
+        pub siblings: BoundedVec<Hash, ConstU32<32>>,
+    }
+
+    /// Contribution of a provider from which the pallet computes its reward
+    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    pub struct ContributionMetrics<AccountId> {
+        pub provider: AccountId,
+        pub gradients_contributed: u64,
+        pub quality_score: u32, // 0-1000
+        pub gpu_tier: GpuTier,
+    }
+
+    /// Progress of the settlement engine through the batch it is settling
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct SettlementCursor<T: Config> {
+        pub batch_id: T::Hash,
+        pub asset: T::AssetKind,
+        /// Raw storage key of the last reward visited, `None` before the first one
+        pub last_key: Option<BoundedVec<u8, ConstU32<256>>>,
+        pub settled: u32,
+        pub failed: u32,
+        pub total_fee: BalanceOf<T>,
+    }
+
     #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, MaxEncodedLen)]
     pub struct RewardMetrics {
         pub total_gradients: u64,
@@ -112,35 +339,243 @@ pub mod pallet {
         ProviderReward<T>,
     >;
 
+    /// Leaves claimed by proof, 32 leaf indices per word
+    #[pallet::storage]
+    pub type ClaimedBitmap<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        Twox64Concat,
+        u32, // leaf_index / 32
+        u32,
+        ValueQuery,
+    >;
+
+    /// Total claimed by proof per batch
+    #[pallet::storage]
+    #[pallet::getter(fn claimed_by_proof)]
+    pub type ClaimedByProof<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Part of each batch's bounty refunded to the task creator
+    #[pallet::storage]
+    #[pallet::getter(fn refunded_bounty)]
+    pub type RefundedBounty<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Aggregate metrics of batches whose rewards were computed on-chain
+    #[pallet::storage]
+    #[pallet::getter(fn batch_metrics)]
+    pub type BatchMetrics<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        RewardMetrics,
+    >;
+
+    /// Coordinator delegated by a task's creator to create its reward batch
+    #[pallet::storage]
+    #[pallet::getter(fn delegated_coordinators)]
+    pub type DelegatedCoordinators<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Task ID
+        T::AccountId,
+    >;
+
+    /// Bond held from the coordinator of each unsettled batch
+    #[pallet::storage]
+    #[pallet::getter(fn coordinator_bonds)]
+    pub type CoordinatorBonds<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        (T::AccountId, BalanceOf<T>),
+    >;
+
+    /// Open challenges by batch
+    #[pallet::storage]
+    #[pallet::getter(fn batch_challenges)]
+    pub type BatchChallenges<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        BatchChallenge<T>,
+    >;
+
+    /// Unsettled batches in creation order, which is also the order they fall due
+    #[pallet::storage]
+    #[pallet::getter(fn settlement_queue)]
+    pub type SettlementQueue<T: Config> =
+        StorageValue<_, BoundedVec<T::Hash, T::MaxQueuedBatches>, ValueQuery>;
+
+    /// Batch the settlement engine is part-way through
+    #[pallet::storage]
+    #[pallet::getter(fn current_settlement)]
+    pub type CurrentSettlement<T: Config> = StorageValue<_, SettlementCursor<T>>;
+
+    /// Rewards that failed to settle and wait for `retry_settlement`
+    #[pallet::storage]
+    #[pallet::getter(fn failed_settlements)]
+    pub type FailedSettlements<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        Blake2_128Concat,
+        T::AccountId, // Provider
+        DispatchError,
+    >;
+
+    /// Reward batch paying out each task's bounty
+    #[pallet::storage]
+    #[pallet::getter(fn task_batches)]
+    pub type TaskBatches<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Task ID
+        T::Hash, // Batch ID
+    >;
+
+    /// Total allocated to providers per batch by `submit_rewards`
+    #[pallet::storage]
+    #[pallet::getter(fn batch_allocated)]
+    pub type BatchAllocated<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Batch ID
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Rewards allocated in batches that have not settled yet, per asset
     #[pallet::storage]
     #[pallet::getter(fn pending_rewards)]
-    pub type PendingRewards<T: Config> = StorageMap<
+    pub type PendingRewards<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::AssetKind,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Settled rewards owed to each provider's delegators, held in escrow, per asset
+    #[pallet::storage]
+    #[pallet::getter(fn delegator_rewards)]
+    pub type DelegatorRewards<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId, // Provider
+        Blake2_128Concat,
+        T::AssetKind,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Settled rewards, net of fees and delegator share, that a provider can claim, per asset
+    #[pallet::storage]
+    #[pallet::getter(fn claimable_rewards)]
+    pub type ClaimableRewards<T: Config> = StorageDoubleMap<
         _,
         Blake2_128Concat,
         T::AccountId,
+        Blake2_128Concat,
+        T::AssetKind,
         BalanceOf<T>,
         ValueQuery,
     >;
 
+    /// Vesting schedules of claimed native rewards by provider
+    #[pallet::storage]
+    #[pallet::getter(fn vesting)]
+    pub type Vesting<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        BoundedVec<VestingSchedule<BalanceOf<T>, BlockNumberFor<T>>, T::MaxVestingSchedules>,
+        ValueQuery,
+    >;
+
+    /// Open payment streams by task and provider
+    #[pallet::storage]
+    #[pallet::getter(fn payment_streams)]
+    pub type PaymentStreams<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Task ID
+        Blake2_128Concat,
+        T::AccountId, // Provider
+        PaymentStream<T>,
+    >;
+
+    /// Tasks each provider has an open payment stream for
+    #[pallet::storage]
+    #[pallet::getter(fn provider_streams)]
+    pub type ProviderStreams<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        BoundedVec<T::Hash, T::MaxStreamsPerProvider>,
+        ValueQuery,
+    >;
+
+    /// Number of open payment streams per task
+    #[pallet::storage]
+    #[pallet::getter(fn stream_count)]
+    pub type StreamCount<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, u32, ValueQuery>;
+
+    /// Last round closed for each task with payment streams
+    #[pallet::storage]
+    #[pallet::getter(fn last_closed_round)]
+    pub type LastClosedRound<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, u32>;
+
     #[pallet::storage]
     #[pallet::getter(fn total_rewards_distributed)]
-    pub type TotalRewardsDistributed<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+    pub type TotalRewardsDistributed<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetKind, BalanceOf<T>, ValueQuery>;
 
     #[pallet::storage]
     #[pallet::getter(fn last_settlement_block)]
     pub type LastSettlementBlock<T: Config> = StorageValue<_, BlockNumberFor<T>, ValueQuery>;
 
+    /// Platform fee taken from every settled reward
+    #[pallet::storage]
+    #[pallet::getter(fn platform_fee)]
+    pub type PlatformFee<T: Config> = StorageValue<_, Permill, ValueQuery, T::DefaultPlatformFee>;
+
+    /// Where collected platform fees are sent
+    #[pallet::storage]
+    #[pallet::getter(fn fee_destination)]
+    pub type FeeRouting<T: Config> = StorageValue<_, FeeDestination, ValueQuery>;
+
     #[pallet::storage]
     #[pallet::getter(fn platform_fees_collected)]
-    pub type PlatformFeesCollected<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+    pub type PlatformFeesCollected<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetKind, BalanceOf<T>, ValueQuery>;
+
+    /// Platform fees taken at settlement that could not be routed, held in escrow until
+    /// `route_unrouted_fees`
+    #[pallet::storage]
+    #[pallet::getter(fn unrouted_fees)]
+    pub type UnroutedFees<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetKind, BalanceOf<T>, ValueQuery>;
 
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        /// Reward batch created [batch_id, task_id, total_bounty]
+        /// Reward batch created [batch_id, task_id, asset, total_bounty]
         BatchCreated {
             batch_id: T::Hash,
             task_id: T::Hash,
+            asset: T::AssetKind,
             total_bounty: BalanceOf<T>,
         },
 
@@ -157,72 +592,338 @@ pub mod pallet {
             amount: BalanceOf<T>,
         },
 
-        /// Rewards claimed [provider, amount]
+        /// Allocated reward settled and made claimable [batch_id, provider, amount]
+        RewardSettled {
+            batch_id: T::Hash,
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Rewards claimed [provider, asset, amount]
         RewardsClaimed {
             provider: T::AccountId,
+            asset: T::AssetKind,
             amount: BalanceOf<T>,
         },
 
-        /// Settlement executed [block_number, batches_settled]
-        SettlementExecuted {
-            block_number: BlockNumberFor<T>,
-            batches_settled: u32,
+        /// Reward claimed against the batch's merkle root [batch_id, provider, leaf_index, amount]
+        RewardClaimedWithProof {
+            batch_id: T::Hash,
+            provider: T::AccountId,
+            leaf_index: u32,
+            amount: BalanceOf<T>,
         },
 
-        /// Platform fee collected [amount]
-        PlatformFeeCollected {
+        /// Delegator share of a settled reward set aside [provider, amount]
+        DelegatorRewardAccrued {
+            provider: T::AccountId,
             amount: BalanceOf<T>,
         },
-    }
 
-    #[pallet::error]
-    #[derive(PartialEq)]
-    pub enum Error<T> {
-        /// Batch not found
-        BatchNotFound,
-        /// Batch already settled
-        BatchAlreadySettled,
-        /// No rewards to claim
-        NoRewardsToClaim,
-        /// Too many providers in batch
-        TooManyProviders,
-        /// Invalid reward amount
-        InvalidRewardAmount,
-        /// Insufficient escrow balance
-        InsufficientEscrowBalance,
-        /// Not authorized
-        NotAuthorized,
-        /// Batch already exists
-        BatchAlreadyExists,
-        /// Provider not in batch
-        ProviderNotInBatch,
-        /// Invalid quality score
-        InvalidQualityScore,
-        /// Settlement too early
-        SettlementTooEarly,
-    }
+        /// Accrued delegator share paid to the provider for distribution [provider, asset, amount]
+        DelegatorRewardsClaimed {
+            provider: T::AccountId,
+            asset: T::AssetKind,
+            amount: BalanceOf<T>,
+        },
 
-    #[pallet::call]
-    impl<T: Config> Pallet<T> {
-        /// Create a reward batch for a completed task
-        #[pallet::call_index(0)]
-        #[pallet::weight(Weight::from_parts(10_000, 0))]
-        pub fn create_batch(
-            origin: OriginFor<T>,
-            task_id: T::Hash,
-            total_bounty: BalanceOf<T>,
-            merkle_root: T::Hash,
-        ) -> DispatchResult {
-            let coordinator = ensure_signed(origin)?;
+        /// Settlement engine paused part-way through a batch [batch_id, settled, failed]
+        SettlementProgress {
+            batch_id: T::Hash,
+            settled: u32,
+            failed: u32,
+        },
 
-            // Generate batch ID
-            let batch_id = T::Hashing::hash_of(&(task_id, coordinator.clone(), frame_system::Pallet::<T>::block_number()));
+        /// Every reward of a batch was processed [batch_id, settled, failed, total_fee]
+        BatchSettled {
+            batch_id: T::Hash,
+            settled: u32,
+            failed: u32,
+            total_fee: BalanceOf<T>,
+        },
 
-            ensure!(!RewardBatches::<T>::contains_key(&batch_id), Error::<T>::BatchAlreadyExists);
+        /// Reward could not be settled and was set aside for retry [batch_id, provider, error]
+        SettlementFailed {
+            batch_id: T::Hash,
+            provider: T::AccountId,
+            error: DispatchError,
+        },
 
-            // Create batch
+        /// Batch challenged during its challenge window [batch_id, challenger, evidence]
+        BatchChallenged {
+            batch_id: T::Hash,
+            challenger: T::AccountId,
+            evidence: BoundedVec<u8, ConstU32<64>>,
+        },
+
+        /// Challenge upheld; the batch was discarded and its bounty refunded [batch_id, refunded_to, amount]
+        ChallengeUpheld {
+            batch_id: T::Hash,
+            refunded_to: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Challenge rejected; settlement resumes [batch_id]
+        ChallengeRejected {
+            batch_id: T::Hash,
+        },
+
+        /// Task creator changed the delegated coordinator [task_id, coordinator]
+        CoordinatorDelegated {
+            task_id: T::Hash,
+            coordinator: Option<T::AccountId>,
+        },
+
+        /// Coordinator bond returned after a clean settlement [batch_id, coordinator, amount]
+        CoordinatorBondReleased {
+            batch_id: T::Hash,
+            coordinator: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Coordinator bond awarded to the challenger of a fraudulent batch [batch_id, coordinator, amount]
+        CoordinatorSlashed {
+            batch_id: T::Hash,
+            coordinator: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Claimed reward put on a vesting schedule [provider, immediate, locked]
+        RewardVesting {
+            provider: T::AccountId,
+            immediate: BalanceOf<T>,
+            locked: BalanceOf<T>,
+        },
+
+        /// Vested rewards released [provider, amount]
+        Vested {
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+
+        /// Platform fee changed by governance [fee]
+        PlatformFeeSet {
+            fee: Permill,
+        },
+
+        /// Fee destination changed by governance [destination]
+        FeeDestinationSet {
+            destination: FeeDestination,
+        },
+
+        /// Platform fee collected [asset, amount]
+        PlatformFeeCollected {
+            asset: T::AssetKind,
+            amount: BalanceOf<T>,
+        },
+
+        /// Reward paid to a `Staked` provider could not be restaked and stays free [provider, amount, error]
+        RestakeFailed {
+            provider: T::AccountId,
+            amount: BalanceOf<T>,
+            error: DispatchError,
+        },
+
+        /// Platform fees could not be routed and were kept for retry [asset, amount, error]
+        FeeRoutingFailed {
+            asset: T::AssetKind,
+            amount: BalanceOf<T>,
+            error: DispatchError,
+        },
+
+        /// Payment stream opened from a task's bounty [task_id, provider, asset, per_round, rounds]
+        StreamOpened {
+            task_id: T::Hash,
+            provider: T::AccountId,
+            asset: T::AssetKind,
+            per_round: BalanceOf<T>,
+            rounds: u32,
+        },
+
+        /// Round closed and streams of contributing providers paid [task_id, round]
+        RoundClosed {
+            task_id: T::Hash,
+            round: u32,
+        },
+
+        /// Round payment made claimable [task_id, provider, round, amount]
+        StreamPaid {
+            task_id: T::Hash,
+            provider: T::AccountId,
+            round: u32,
+            amount: BalanceOf<T>,
+        },
+
+        /// Every round of a stream was paid [task_id, provider]
+        StreamCompleted {
+            task_id: T::Hash,
+            provider: T::AccountId,
+        },
+
+        /// Stream stopped and its remainder returned to the task creator [task_id, provider, refunded]
+        StreamStopped {
+            task_id: T::Hash,
+            provider: T::AccountId,
+            refunded: BalanceOf<T>,
+        },
+
+        /// Unallocated or unclaimed bounty returned to the task creator [batch_id, creator, amount]
+        BountyRefunded {
+            batch_id: T::Hash,
+            creator: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+    }
+
+    #[pallet::error]
+    #[derive(PartialEq)]
+    pub enum Error<T> {
+        /// Batch not found
+        BatchNotFound,
+        /// Batch already settled
+        BatchAlreadySettled,
+        /// No rewards to claim
+        NoRewardsToClaim,
+        /// Too many providers in batch
+        TooManyProviders,
+        /// Invalid reward amount
+        InvalidRewardAmount,
+        /// Insufficient escrow balance
+        InsufficientEscrowBalance,
+        /// Not authorized
+        NotAuthorized,
+        /// Batch already exists
+        BatchAlreadyExists,
+        /// Provider not in batch
+        ProviderNotInBatch,
+        /// Invalid quality score
+        InvalidQualityScore,
+        /// Settlement too early
+        SettlementTooEarly,
+        /// Merkle proof does not match the batch root
+        InvalidProof,
+        /// Reward already claimed
+        AlreadyClaimed,
+        /// Batch rewards were submitted individually, not committed by root
+        NotAMerkleBatch,
+        /// Rewards of this batch are already being claimed by proof
+        ProofClaimsStarted,
+        /// Provider already has a reward in this batch
+        DuplicateReward,
+        /// Task is unknown, not completed or already paid out
+        TaskNotPayable,
+        /// Batch already has rewards allocated
+        RewardsAlreadySubmitted,
+        /// Submitted metrics earn no reward
+        NoContribution,
+        /// Too many batches are waiting for settlement
+        SettlementQueueFull,
+        /// Batch is being settled
+        SettlementInProgress,
+        /// No failed settlement for this provider and batch
+        NoFailedSettlement,
+        /// Provider's pending rewards no longer cover the allocation
+        AllocationMissing,
+        /// Only the task creator and the task's providers can challenge its batch
+        NotAParticipant,
+        /// Challenge window has closed
+        ChallengeWindowClosed,
+        /// Batch already has an open challenge
+        AlreadyChallenged,
+        /// Batch has no open challenge
+        NoOpenChallenge,
+        /// Batch is frozen by an open challenge
+        BatchChallenged,
+        /// Evidence CID too long
+        EvidenceTooLong,
+        /// Task does not exist
+        TaskNotFound,
+        /// Provider has too many vesting schedules; call `vest` first
+        TooManyVestingSchedules,
+        /// Nothing has vested yet
+        NothingToVest,
+        /// Platform fee above `MaxPlatformFee`
+        FeeTooHigh,
+        /// Fee split shares add up to more than 100%
+        InvalidFeeSplit,
+        /// Provider has not joined the task
+        ProviderNotInTask,
+        /// Provider already has a payment stream for this task
+        StreamExists,
+        /// Task has too many payment streams
+        TooManyStreams,
+        /// No unrouted platform fees in this asset
+        NoUnroutedFees,
+        /// No payment stream for this provider and task
+        StreamNotFound,
+        /// Round is not after the task's last closed round
+        RoundAlreadyClosed,
+        /// Only the provider can stop its stream until it leaves the task or is slashed, or
+        /// the task stops running
+        StreamActive,
+        /// Task is not running
+        TaskNotRunning,
+        /// Proof claims of this batch have closed
+        ClaimPeriodOver,
+        /// Proof claims of this batch are still open
+        ClaimPeriodNotOver,
+        /// Nothing of the batch's bounty is left to refund
+        NothingToRefund,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::settle_step(now, remaining_weight)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+            Self::do_try_state()
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Create a reward batch for a completed task, moving its escrowed bounty into the batch
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(15_000, 0))]
+        pub fn create_batch(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+            merkle_root: T::Hash,
+        ) -> DispatchResult {
+            let coordinator = ensure_signed(origin)?;
+
+            ensure!(!TaskBatches::<T>::contains_key(&task_id), Error::<T>::TaskNotPayable);
+            let (asset, escrowed) = T::Tasks::escrowed(&task_id).ok_or(Error::<T>::TaskNotPayable)?;
+            ensure!(Self::is_coordinator(&task_id, &coordinator), Error::<T>::NotAuthorized);
+
+            // Generate batch ID
+            let batch_id = T::Hashing::hash_of(&(task_id, coordinator.clone(), frame_system::Pallet::<T>::block_number()));
+
+            ensure!(!RewardBatches::<T>::contains_key(&batch_id), Error::<T>::BatchAlreadyExists);
+
+            SettlementQueue::<T>::try_append(batch_id).map_err(|_| Error::<T>::SettlementQueueFull)?;
+
+            // Bonds are native, so only native bounties can size them
+            let bond = if Self::is_native(&asset) {
+                (T::CoordinatorBondRatio::get() * escrowed).max(T::MinCoordinatorBond::get())
+            } else {
+                T::MinCoordinatorBond::get()
+            };
+            T::Currency::hold(&HoldReason::CoordinatorBond.into(), &coordinator, bond)?;
+            CoordinatorBonds::<T>::insert(&batch_id, (coordinator.clone(), bond));
+            DelegatedCoordinators::<T>::remove(&task_id);
+
+            // Lock exactly the task's escrowed bounty for this batch
+            let total_bounty = T::Tasks::release(&task_id, &Self::account_id())?;
+            TaskBatches::<T>::insert(&task_id, &batch_id);
+
+            // Create batch
             let batch = RewardBatch {
                 task_id,
+                asset: asset.clone(),
                 total_bounty,
                 coordinator,
                 created_at: frame_system::Pallet::<T>::block_number(),
@@ -235,6 +936,7 @@ pub mod pallet {
             Self::deposit_event(Event::BatchCreated {
                 batch_id,
                 task_id,
+                asset,
                 total_bounty,
             });
 
@@ -254,33 +956,27 @@ pub mod pallet {
             let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
 
             ensure!(!batch.settled, Error::<T>::BatchAlreadySettled);
+            ensure!(!Self::is_settling(&batch_id), Error::<T>::SettlementInProgress);
             ensure!(batch.coordinator == who, Error::<T>::NotAuthorized);
+            ensure!(ClaimedByProof::<T>::get(&batch_id).is_zero(), Error::<T>::ProofClaimsStarted);
             ensure!(rewards.len() <= T::MaxProvidersPerBatch::get() as usize, Error::<T>::TooManyProviders);
 
             // Calculate total rewards
             let mut total_rewards: BalanceOf<T> = Zero::zero();
+            let minimum_reward = Self::minimum_reward(&batch.asset);
 
             for reward in rewards.iter() {
-                ensure!(reward.amount >= T::MinimumReward::get(), Error::<T>::InvalidRewardAmount);
+                ensure!(reward.amount >= minimum_reward, Error::<T>::InvalidRewardAmount);
                 ensure!(reward.quality_score <= 1000, Error::<T>::InvalidQualityScore);
                 total_rewards = total_rewards.saturating_add(reward.amount);
 
-                // Store individual rewards
-                BatchRewards::<T>::insert(&batch_id, &reward.provider, reward.clone());
-
-                // Add to pending rewards
-                PendingRewards::<T>::mutate(&reward.provider, |pending| {
-                    *pending = pending.saturating_add(reward.amount);
-                });
-
-                Self::deposit_event(Event::RewardAllocated {
-                    provider: reward.provider.clone(),
-                    amount: reward.amount,
-                });
+                Self::allocate(batch_id, &batch.asset, reward)?;
             }
 
-            // Ensure total doesn't exceed bounty
-            ensure!(total_rewards <= batch.total_bounty, Error::<T>::InvalidRewardAmount);
+            // Ensure the batch's allocations across all submissions don't exceed the bounty
+            let allocated = BatchAllocated::<T>::get(&batch_id).saturating_add(total_rewards);
+            ensure!(allocated <= batch.total_bounty, Error::<T>::InvalidRewardAmount);
+            BatchAllocated::<T>::insert(&batch_id, allocated);
 
             Self::deposit_event(Event::RewardsDistributed {
                 batch_id,
@@ -291,7 +987,7 @@ pub mod pallet {
             Ok(())
         }
 
-        /// Settle a batch and transfer rewards
+        /// Settle a whole batch at once, ahead of the settlement engine
         #[pallet::call_index(2)]
         #[pallet::weight(Weight::from_parts(20_000, 0))]
         pub fn settle_batch(
@@ -300,69 +996,23 @@ pub mod pallet {
         ) -> DispatchResult {
             ensure_root(origin)?;
 
-            RewardBatches::<T>::try_mutate(&batch_id, |maybe_batch| {
-                let batch = maybe_batch.as_mut().ok_or(Error::<T>::BatchNotFound)?;
-
-                ensure!(!batch.settled, Error::<T>::BatchAlreadySettled);
-
-                // Check if settlement period has passed
-                let current_block = frame_system::Pallet::<T>::block_number();
-                let settlement_due = batch.created_at.saturating_add(T::SettlementPeriod::get());
-                ensure!(current_block >= settlement_due, Error::<T>::SettlementTooEarly);
-
-                batch.settled = true;
+            let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
 
-                Ok::<(), DispatchError>(())
-            })?;
+            ensure!(!batch.settled, Error::<T>::BatchAlreadySettled);
+            ensure!(!Self::is_settling(&batch_id), Error::<T>::SettlementInProgress);
+            ensure!(!BatchChallenges::<T>::contains_key(&batch_id), Error::<T>::BatchChallenged);
 
-            // Process all rewards in batch
-            let mut settled_count = 0u32;
-            let mut total_settled: BalanceOf<T> = Zero::zero();
-            let mut total_fee: BalanceOf<T> = Zero::zero();
+            // Check if settlement period has passed
+            let current_block = frame_system::Pallet::<T>::block_number();
+            let settlement_due = batch.created_at.saturating_add(T::SettlementPeriod::get());
+            ensure!(current_block >= settlement_due, Error::<T>::SettlementTooEarly);
 
+            // Bounded by `MaxProvidersPerBatch`
+            let mut cursor = SettlementCursor::<T>::new(batch_id, batch.asset);
             for (provider, reward) in BatchRewards::<T>::iter_prefix(&batch_id) {
-                // Calculate platform fee
-                let fee = T::PlatformFeePercentage::get() * reward.amount;
-                let net_reward = reward.amount.saturating_sub(fee);
-                total_fee = total_fee.saturating_add(fee);
-
-                // Transfer from escrow to provider
-                let escrow_account = Self::account_id();
-                T::Currency::transfer(
-                    &escrow_account,
-                    &provider,
-                    net_reward,
-                    ExistenceRequirement::KeepAlive,
-                )?;
-
-                // Update platform fees
-                PlatformFeesCollected::<T>::mutate(|total| *total = total.saturating_add(fee));
-
-                // Update statistics
-                TotalRewardsDistributed::<T>::mutate(|total| *total = total.saturating_add(net_reward));
-
-                // Clear pending reward
-                PendingRewards::<T>::mutate(&provider, |pending| {
-                    *pending = pending.saturating_sub(reward.amount);
-                });
-
-                settled_count += 1;
-                total_settled = total_settled.saturating_add(net_reward);
-            }
-
-            // Update last settlement block
-            LastSettlementBlock::<T>::put(frame_system::Pallet::<T>::block_number());
-
-            Self::deposit_event(Event::SettlementExecuted {
-                block_number: frame_system::Pallet::<T>::block_number(),
-                batches_settled: settled_count,
-            });
-
-            if !total_fee.is_zero() {
-                Self::deposit_event(Event::PlatformFeeCollected {
-                    amount: total_fee,
-                });
+                Self::settle_or_record(&mut cursor, provider, &reward);
             }
+            Self::finish_settlement(cursor);
 
             Ok(())
         }
@@ -372,126 +1022,1288 @@ pub mod pallet {
         #[pallet::weight(Weight::from_parts(8_000, 0))]
         pub fn claim_rewards(
             origin: OriginFor<T>,
+            asset: T::AssetKind,
         ) -> DispatchResult {
             let provider = ensure_signed(origin)?;
 
-            let pending = PendingRewards::<T>::get(&provider);
+            // Only settled rewards can be claimed; allocations wait for their batch to settle
+            let pending = ClaimableRewards::<T>::take(&provider, &asset);
             ensure!(!pending.is_zero(), Error::<T>::NoRewardsToClaim);
 
-            // Transfer from escrow to provider
-            let escrow_account = Self::account_id();
-            T::Currency::transfer(
-                &escrow_account,
-                &provider,
-                pending,
-                ExistenceRequirement::KeepAlive,
-            )?;
-
-            // Clear pending rewards
-            PendingRewards::<T>::remove(&provider);
+            // Transfer from escrow to the provider's payee, vesting large rewards
+            Self::pay_out_vesting(&provider, &asset, pending)?;
 
             // Update total distributed
-            TotalRewardsDistributed::<T>::mutate(|total| *total = total.saturating_add(pending));
+            TotalRewardsDistributed::<T>::mutate(&asset, |total| *total = total.saturating_add(pending));
 
             Self::deposit_event(Event::RewardsClaimed {
                 provider,
+                asset,
                 amount: pending,
             });
 
             Ok(())
         }
 
-        /// Execute periodic settlement (can be called by anyone)
-        #[pallet::call_index(4)]
-        #[pallet::weight(Weight::from_parts(25_000, 0))]
-        pub fn periodic_settlement(
+        /// Submit contribution metrics and let the pallet compute rewards that add up to the bounty
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(20_000, 0))]
+        pub fn submit_metrics(
             origin: OriginFor<T>,
+            batch_id: T::Hash,
+            metrics: Vec<ContributionMetrics<T::AccountId>>,
         ) -> DispatchResult {
-            let _who = ensure_signed(origin)?;
+            let who = ensure_signed(origin)?;
 
-            let current_block = frame_system::Pallet::<T>::block_number();
-            let last_settlement = LastSettlementBlock::<T>::get();
-            let next_settlement = last_settlement.saturating_add(T::SettlementPeriod::get());
-
-            ensure!(current_block >= next_settlement, Error::<T>::SettlementTooEarly);
-
-            let mut batches_settled = 0u32;
-
-            // Settle all eligible batches
-            for (batch_id, mut batch) in RewardBatches::<T>::iter() {
-                if !batch.settled {
-                    let settlement_due = batch.created_at.saturating_add(T::SettlementPeriod::get());
-                    if current_block >= settlement_due {
-                        batch.settled = true;
-                        RewardBatches::<T>::insert(&batch_id, &batch);
-                        batches_settled += 1;
-
-                        // Process rewards for this batch
-                        for (provider, reward) in BatchRewards::<T>::iter_prefix(&batch_id) {
-                            let fee = T::PlatformFeePercentage::get() * reward.amount;
-                            let net_reward = reward.amount.saturating_sub(fee);
-
-                            // Transfer rewards
-                            let escrow_account = Self::account_id();
-                            let _ = T::Currency::transfer(
-                                &escrow_account,
-                                &provider,
-                                net_reward,
-                                ExistenceRequirement::KeepAlive,
-                            );
-
-                            PendingRewards::<T>::mutate(&provider, |pending| {
-                                *pending = pending.saturating_sub(reward.amount);
-                            });
-                        }
-                    }
+            let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
+
+            ensure!(!batch.settled, Error::<T>::BatchAlreadySettled);
+            ensure!(!Self::is_settling(&batch_id), Error::<T>::SettlementInProgress);
+            ensure!(batch.coordinator == who, Error::<T>::NotAuthorized);
+            ensure!(ClaimedByProof::<T>::get(&batch_id).is_zero(), Error::<T>::ProofClaimsStarted);
+            ensure!(BatchAllocated::<T>::get(&batch_id).is_zero(), Error::<T>::RewardsAlreadySubmitted);
+            ensure!(metrics.len() <= T::MaxProvidersPerBatch::get() as usize, Error::<T>::TooManyProviders);
+            ensure!(metrics.iter().all(|m| m.quality_score <= 1000), Error::<T>::InvalidQualityScore);
+
+            let amounts = Self::compute_rewards(batch.total_bounty, Self::minimum_reward(&batch.asset), &metrics)?;
+
+            for (m, amount) in metrics.iter().zip(amounts) {
+                if amount.is_zero() {
+                    continue;
                 }
+                Self::allocate(batch_id, &batch.asset, &ProviderReward {
+                    provider: m.provider.clone(),
+                    amount,
+                    gradients_contributed: m.gradients_contributed,
+                    quality_score: m.quality_score,
+                    hardware_multiplier: T::TierMultiplier::convert(m.gpu_tier),
+                })?;
             }
+            BatchAllocated::<T>::insert(&batch_id, batch.total_bounty);
+
+            let participants = metrics.len() as u32;
+            let total_gradients = metrics.iter().fold(0u64, |t, m| t.saturating_add(m.gradients_contributed));
+            let total_quality = metrics.iter().fold(0u64, |t, m| t.saturating_add(m.quality_score as u64));
+            BatchMetrics::<T>::insert(&batch_id, RewardMetrics {
+                total_gradients,
+                avg_quality_score: (total_quality / participants.max(1) as u64) as u32,
+                participants,
+            });
 
-            LastSettlementBlock::<T>::put(current_block);
-
-            Self::deposit_event(Event::SettlementExecuted {
-                block_number: current_block,
-                batches_settled,
+            Self::deposit_event(Event::RewardsDistributed {
+                batch_id,
+                provider_count: participants,
+                total_amount: batch.total_bounty,
             });
 
             Ok(())
         }
-    }
 
-    // Helper functions
-    impl<T: Config> Pallet<T> {
-        /// Get the account ID of the pallet's escrow account
-        pub fn account_id() -> T::AccountId {
-            T::PalletId::get().into_account_truncating()
+        /// Claim a reward committed in the batch's merkle root once its settlement period passed
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(12_000, 0))]
+        pub fn claim_with_proof(
+            origin: OriginFor<T>,
+            batch_id: T::Hash,
+            amount: BalanceOf<T>,
+            proof: MerkleProof<T::Hash>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
+
+            let current_block = frame_system::Pallet::<T>::block_number();
+            let settlement_due = batch.created_at.saturating_add(T::SettlementPeriod::get());
+            ensure!(current_block >= settlement_due, Error::<T>::SettlementTooEarly);
+            ensure!(
+                current_block < settlement_due.saturating_add(T::ProofClaimPeriod::get()),
+                Error::<T>::ClaimPeriodOver
+            );
+            ensure!(!BatchChallenges::<T>::contains_key(&batch_id), Error::<T>::BatchChallenged);
+            ensure!(BatchRewards::<T>::iter_prefix(&batch_id).next().is_none(), Error::<T>::NotAMerkleBatch);
+
+            let leaf_index = proof.leaf_index;
+            let word = leaf_index / 32;
+            let bit = 1u32 << (leaf_index % 32);
+            ensure!(ClaimedBitmap::<T>::get(&batch_id, word) & bit == 0, Error::<T>::AlreadyClaimed);
+
+            let leaf = merkle::leaf::<T::Hashing, _, _>(leaf_index, &provider, &amount);
+            ensure!(
+                merkle::verify::<T::Hashing>(&batch.merkle_root, leaf, leaf_index, &proof.siblings),
+                Error::<T>::InvalidProof
+            );
+
+            // A bad root can never pay out more than the batch bounty
+            let claimed = ClaimedByProof::<T>::get(&batch_id).saturating_add(amount);
+            ensure!(claimed <= batch.total_bounty, Error::<T>::InvalidRewardAmount);
+
+            ClaimedBitmap::<T>::mutate(&batch_id, word, |bits| *bits |= bit);
+            ClaimedByProof::<T>::insert(&batch_id, claimed);
+
+            // Proof claims settle and pay out in one step
+            let fee = PlatformFee::<T>::get() * amount;
+            let net_reward = amount.saturating_sub(fee);
+            let commission = Self::split_commission(&provider, &batch.asset, net_reward);
+            T::Stats::on_reward_settled(&provider, &batch.task_id, 0, Self::native_amount(&batch.asset, amount));
+            if !Self::try_compound(&provider, &batch.asset, commission) {
+                Self::pay_out_vesting(&provider, &batch.asset, commission)?;
+                TotalRewardsDistributed::<T>::mutate(&batch.asset, |total| *total = total.saturating_add(commission));
+            }
+            Self::route_fee(&batch.asset, fee)?;
+            Self::collect_fee(batch.asset, fee);
+
+            Self::deposit_event(Event::RewardClaimedWithProof {
+                batch_id,
+                provider,
+                leaf_index,
+                amount,
+            });
+
+            Ok(())
         }
 
-        /// Calculate reward for a provider based on contribution
-        pub fn calculate_reward(
-            base_bounty: BalanceOf<T>,
-            gradients: u64,
-            total_gradients: u64,
-            quality_score: u32,
-            hardware_multiplier: u32,
-        ) -> BalanceOf<T> {
-            if total_gradients == 0 {
-                return Zero::zero();
+        /// Settle a reward that failed during its batch's settlement
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn retry_settlement(
+            origin: OriginFor<T>,
+            batch_id: T::Hash,
+            provider: T::AccountId,
+        ) -> DispatchResult {
+            let _who = ensure_signed(origin)?;
+
+            ensure!(FailedSettlements::<T>::contains_key(&batch_id, &provider), Error::<T>::NoFailedSettlement);
+            let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
+            let reward = BatchRewards::<T>::get(&batch_id, &provider).ok_or(Error::<T>::ProviderNotInBatch)?;
+
+            let fee = Self::settle_reward(batch_id, &batch.asset, &provider, &reward)?;
+            Self::route_fee(&batch.asset, fee)?;
+            FailedSettlements::<T>::remove(&batch_id, &provider);
+            Self::collect_fee(batch.asset, fee);
+
+            // The batch is clean once its last failed reward settles
+            if FailedSettlements::<T>::iter_prefix(&batch_id).next().is_none() &&
+                !Self::is_settling(&batch_id)
+            {
+                Self::release_coordinator_bond(batch_id);
             }
 
-            // Base reward proportional to contribution
-            let contribution_ratio = Permill::from_rational(gradients, total_gradients);
-            let base_reward = contribution_ratio * base_bounty;
+            Ok(())
+        }
 
-            // Apply quality multiplier (0-1000 score maps to 0-100%)
-            let quality_multiplier = Permill::from_rational(quality_score, 1000u32);
-            let quality_adjusted = quality_multiplier * base_reward;
+        /// Contest a batch during its challenge window, freezing its settlement
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn challenge_batch(
+            origin: OriginFor<T>,
+            batch_id: T::Hash,
+            evidence: Vec<u8>,
+        ) -> DispatchResult {
+            let challenger = ensure_signed(origin)?;
 
-            // Apply hardware multiplier (stored as u32, 100 = 1.0x)
-            let hardware_adjusted = quality_adjusted
-                .saturating_mul(hardware_multiplier.into())
-                / 100u32.into();
+            let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
 
-            hardware_adjusted
+            // The challenge window is the settlement period
+            let current_block = frame_system::Pallet::<T>::block_number();
+            let window_end = batch.created_at.saturating_add(T::SettlementPeriod::get());
+            ensure!(!batch.settled && current_block < window_end, Error::<T>::ChallengeWindowClosed);
+            ensure!(!BatchChallenges::<T>::contains_key(&batch_id), Error::<T>::AlreadyChallenged);
+            ensure!(
+                T::Tasks::creator(&batch.task_id).as_ref() == Some(&challenger) ||
+                    T::Tasks::is_provider(&batch.task_id, &challenger),
+                Error::<T>::NotAParticipant
+            );
+
+            let evidence: BoundedVec<u8, ConstU32<64>> = evidence.try_into().map_err(|_| Error::<T>::EvidenceTooLong)?;
+            let bond = T::BatchChallengeBond::get();
+            T::Currency::hold(&HoldReason::BatchChallengeBond.into(), &challenger, bond)?;
+
+            BatchChallenges::<T>::insert(&batch_id, BatchChallenge {
+                challenger: challenger.clone(),
+                evidence: evidence.clone(),
+                bond,
+                raised_at: current_block,
+            });
+
+            Self::deposit_event(Event::BatchChallenged { batch_id, challenger, evidence });
+
+            Ok(())
+        }
+
+        /// Resolve an open batch challenge (arbiter only).
+        ///
+        /// An upheld challenge discards the batch, refunds its bounty to the task creator and
+        /// awards the coordinator's bond to the challenger. A rejected one awards the challenge
+        /// bond to the coordinator and lets settlement resume.
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(25_000, 0))]
+        pub fn resolve_challenge(
+            origin: OriginFor<T>,
+            batch_id: T::Hash,
+            upheld: bool,
+        ) -> DispatchResult {
+            T::ArbiterOrigin::ensure_origin(origin)?;
+
+            let challenge = BatchChallenges::<T>::take(&batch_id).ok_or(Error::<T>::NoOpenChallenge)?;
+            let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
+
+            if upheld {
+                T::Currency::release(
+                    &HoldReason::BatchChallengeBond.into(),
+                    &challenge.challenger,
+                    challenge.bond,
+                    Precision::BestEffort,
+                )?;
+                if let Some((coordinator, bond)) = CoordinatorBonds::<T>::take(&batch_id) {
+                    let slashed = T::Currency::transfer_on_hold(
+                        &HoldReason::CoordinatorBond.into(),
+                        &coordinator,
+                        &challenge.challenger,
+                        bond,
+                        Precision::BestEffort,
+                        Restriction::Free,
+                        Fortitude::Force,
+                    )?;
+                    Self::deposit_event(Event::CoordinatorSlashed { batch_id, coordinator, amount: slashed });
+                }
+
+                let creator = T::Tasks::creator(&batch.task_id).ok_or(Error::<T>::TaskNotPayable)?;
+                Self::discard_batch(batch_id, &batch.asset);
+                T::Assets::transfer(batch.asset, &Self::account_id(), &creator, batch.total_bounty, Preservation::Expendable)?;
+
+                Self::deposit_event(Event::ChallengeUpheld {
+                    batch_id,
+                    refunded_to: creator,
+                    amount: batch.total_bounty,
+                });
+            } else {
+                T::Currency::transfer_on_hold(
+                    &HoldReason::BatchChallengeBond.into(),
+                    &challenge.challenger,
+                    &batch.coordinator,
+                    challenge.bond,
+                    Precision::BestEffort,
+                    Restriction::Free,
+                    Fortitude::Force,
+                )?;
+
+                Self::deposit_event(Event::ChallengeRejected { batch_id });
+            }
+
+            Ok(())
+        }
+
+        /// Delegate batch creation for a task to another account, or revoke the delegation
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn delegate_coordinator(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+            coordinator: Option<T::AccountId>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let creator = T::Tasks::creator(&task_id).ok_or(Error::<T>::TaskNotFound)?;
+            ensure!(creator == who, Error::<T>::NotAuthorized);
+            ensure!(!TaskBatches::<T>::contains_key(&task_id), Error::<T>::TaskNotPayable);
+
+            DelegatedCoordinators::<T>::set(&task_id, coordinator.clone());
+
+            Self::deposit_event(Event::CoordinatorDelegated { task_id, coordinator });
+
+            Ok(())
+        }
+
+        /// Release the vested part of the caller's reward schedules
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn vest(
+            origin: OriginFor<T>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let released = Self::release_vested(&provider)?;
+            ensure!(!released.is_zero(), Error::<T>::NothingToVest);
+
+            Ok(())
+        }
+
+        /// Set the platform fee (governance only)
+        #[pallet::call_index(12)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn set_platform_fee(
+            origin: OriginFor<T>,
+            fee: Permill,
+        ) -> DispatchResult {
+            T::FeeOrigin::ensure_origin(origin)?;
+
+            ensure!(fee <= T::MaxPlatformFee::get(), Error::<T>::FeeTooHigh);
+            PlatformFee::<T>::put(fee);
+
+            Self::deposit_event(Event::PlatformFeeSet { fee });
+
+            Ok(())
+        }
+
+        /// Set where platform fees are sent (governance only)
+        #[pallet::call_index(13)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn set_fee_destination(
+            origin: OriginFor<T>,
+            destination: FeeDestination,
+        ) -> DispatchResult {
+            T::FeeOrigin::ensure_origin(origin)?;
+
+            if let FeeDestination::Split { validators, stakers } = destination {
+                ensure!(
+                    validators.deconstruct().saturating_add(stakers.deconstruct()) <= Permill::ACCURACY,
+                    Error::<T>::InvalidFeeSplit
+                );
+            }
+            FeeRouting::<T>::put(destination);
+
+            Self::deposit_event(Event::FeeDestinationSet { destination });
+
+            Ok(())
+        }
+
+        /// Stream `per_round` of a running task's bounty to a provider for each of `rounds`
+        /// rounds it contributes to (task creator only)
+        #[pallet::call_index(14)]
+        #[pallet::weight(Weight::from_parts(15_000, 0))]
+        pub fn open_stream(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+            provider: T::AccountId,
+            per_round: BalanceOf<T>,
+            rounds: u32,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let creator = T::Tasks::creator(&task_id).ok_or(Error::<T>::TaskNotFound)?;
+            ensure!(creator == who, Error::<T>::NotAuthorized);
+            ensure!(T::Tasks::is_provider(&task_id, &provider), Error::<T>::ProviderNotInTask);
+            ensure!(!PaymentStreams::<T>::contains_key(&task_id, &provider), Error::<T>::StreamExists);
+            ensure!(rounds > 0, Error::<T>::InvalidRewardAmount);
+
+            StreamCount::<T>::try_mutate(&task_id, |count| {
+                ensure!(*count < T::MaxStreamsPerTask::get(), Error::<T>::TooManyStreams);
+                *count = count.saturating_add(1);
+                Ok::<(), DispatchError>(())
+            })?;
+            ProviderStreams::<T>::try_append(&provider, task_id).map_err(|_| Error::<T>::TooManyStreams)?;
+
+            // Move the whole stream out of the task's bounty into escrow up front
+            let total = per_round.checked_mul(&rounds.into()).ok_or(ArithmeticError::Overflow)?;
+            let asset = T::Tasks::draw(&task_id, &Self::account_id(), total)?;
+            ensure!(per_round >= Self::minimum_reward(&asset), Error::<T>::InvalidRewardAmount);
+
+            PaymentStreams::<T>::insert(&task_id, &provider, PaymentStream {
+                asset: asset.clone(),
+                per_round,
+                remaining: total,
+                slashes_at_open: T::Contributions::applied_slash_count(&provider),
+            });
+
+            Self::deposit_event(Event::StreamOpened { task_id, provider, asset, per_round, rounds });
+
+            Ok(())
+        }
+
+        /// Close a round, paying every stream whose provider committed a gradient for it.
+        ///
+        /// Streams of providers that left the task or were slashed are stopped instead and
+        /// their remainder returned to the task creator.
+        #[pallet::call_index(15)]
+        #[pallet::weight(Weight::from_parts(25_000, 0))]
+        pub fn close_round(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+            round: u32,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            ensure!(Self::is_coordinator(&task_id, &who), Error::<T>::NotAuthorized);
+            ensure!(T::Tasks::is_running(&task_id), Error::<T>::TaskNotRunning);
+            ensure!(
+                LastClosedRound::<T>::get(&task_id).map_or(true, |last| round > last),
+                Error::<T>::RoundAlreadyClosed
+            );
+            LastClosedRound::<T>::insert(&task_id, round);
+
+            // Bounded by `MaxStreamsPerTask`
+            let streams = PaymentStreams::<T>::iter_prefix(&task_id).collect::<Vec<_>>();
+            let mut fees: Option<(T::AssetKind, BalanceOf<T>)> = None;
+            for (provider, mut stream) in streams {
+                if Self::stream_broken(&task_id, &provider, &stream) {
+                    Self::end_stream(task_id, provider, stream)?;
+                    continue;
+                }
+                if !T::Contributions::contributed(&task_id, &provider, round) {
+                    continue;
+                }
+
+                let amount = stream.per_round.min(stream.remaining);
+                stream.remaining = stream.remaining.saturating_sub(amount);
+
+                let fee = PlatformFee::<T>::get() * amount;
+                let commission = Self::split_commission(&provider, &stream.asset, amount.saturating_sub(fee));
+                Self::credit_reward(&provider, &stream.asset, commission)?;
+                T::Stats::on_stream_paid(&provider, &task_id, Self::native_amount(&stream.asset, amount));
+                let collected = fees.get_or_insert_with(|| (stream.asset.clone(), Zero::zero()));
+                collected.1 = collected.1.saturating_add(fee);
+
+                Self::deposit_event(Event::StreamPaid {
+                    task_id,
+                    provider: provider.clone(),
+                    round,
+                    amount: commission,
+                });
+
+                if stream.remaining.is_zero() {
+                    Self::remove_stream(&task_id, &provider);
+                    Self::deposit_event(Event::StreamCompleted { task_id, provider });
+                } else {
+                    PaymentStreams::<T>::insert(&task_id, &provider, stream);
+                }
+            }
+
+            if let Some((asset, fee)) = fees {
+                Self::route_fee(&asset, fee)?;
+                Self::collect_fee(asset, fee);
+            }
+
+            Self::deposit_event(Event::RoundClosed { task_id, round });
+
+            Ok(())
+        }
+
+        /// Stop a payment stream and return its remainder to the task creator.
+        ///
+        /// The provider can stop its own stream at any time; anyone can stop it once the
+        /// provider left the task or was slashed, and the creator once the task stopped running.
+        #[pallet::call_index(16)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn stop_stream(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+            provider: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let stream = PaymentStreams::<T>::get(&task_id, &provider).ok_or(Error::<T>::StreamNotFound)?;
+            ensure!(
+                who == provider ||
+                    Self::stream_broken(&task_id, &provider, &stream) ||
+                    (T::Tasks::creator(&task_id).as_ref() == Some(&who) && !T::Tasks::is_running(&task_id)),
+                Error::<T>::StreamActive
+            );
+
+            Self::end_stream(task_id, provider, stream)
+        }
+
+        /// Claim the delegator share set aside from the caller's rewards.
+        ///
+        /// Delegators are not tracked on chain, so the share is paid out like the provider's
+        /// own rewards: compounded, vested and sent to its payee.
+        #[pallet::call_index(17)]
+        #[pallet::weight(Weight::from_parts(8_000, 0))]
+        pub fn claim_delegator_rewards(
+            origin: OriginFor<T>,
+            asset: T::AssetKind,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let owed = DelegatorRewards::<T>::take(&provider, &asset);
+            ensure!(!owed.is_zero(), Error::<T>::NoRewardsToClaim);
+
+            if !Self::try_compound(&provider, &asset, owed) {
+                Self::pay_out_vesting(&provider, &asset, owed)?;
+                TotalRewardsDistributed::<T>::mutate(&asset, |total| *total = total.saturating_add(owed));
+            }
+
+            Self::deposit_event(Event::DelegatorRewardsClaimed { provider, asset, amount: owed });
+
+            Ok(())
+        }
+
+        /// Route platform fees that could not be routed when their batch settled
+        #[pallet::call_index(18)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn route_unrouted_fees(
+            origin: OriginFor<T>,
+            asset: T::AssetKind,
+        ) -> DispatchResult {
+            let _who = ensure_signed(origin)?;
+
+            let unrouted = UnroutedFees::<T>::take(&asset);
+            ensure!(!unrouted.is_zero(), Error::<T>::NoUnroutedFees);
+
+            Self::route_fee(&asset, unrouted)?;
+            Self::collect_fee(asset, unrouted);
+
+            Ok(())
+        }
+
+        /// Refund what no proof claimed of a merkle batch's bounty once its claim period is over
+        #[pallet::call_index(19)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn refund_unclaimed(
+            origin: OriginFor<T>,
+            batch_id: T::Hash,
+        ) -> DispatchResult {
+            let _who = ensure_signed(origin)?;
+
+            let batch = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?;
+
+            ensure!(BatchAllocated::<T>::get(&batch_id).is_zero(), Error::<T>::NotAMerkleBatch);
+            ensure!(batch.settled, Error::<T>::SettlementTooEarly);
+            let claims_close = batch
+                .created_at
+                .saturating_add(T::SettlementPeriod::get())
+                .saturating_add(T::ProofClaimPeriod::get());
+            ensure!(frame_system::Pallet::<T>::block_number() >= claims_close, Error::<T>::ClaimPeriodNotOver);
+
+            let unclaimed = batch
+                .total_bounty
+                .saturating_sub(ClaimedByProof::<T>::get(&batch_id))
+                .saturating_sub(RefundedBounty::<T>::get(&batch_id));
+            ensure!(!unclaimed.is_zero(), Error::<T>::NothingToRefund);
+
+            Self::refund_bounty(batch_id, &batch, unclaimed)
+        }
+    }
+
+    impl<T: Config> SettlementCursor<T> {
+        fn new(batch_id: T::Hash, asset: T::AssetKind) -> Self {
+            Self { batch_id, asset, last_key: None, settled: 0, failed: 0, total_fee: Zero::zero() }
+        }
+    }
+
+    // Helper functions
+    impl<T: Config> Pallet<T> {
+        /// Get the account ID of the pallet's escrow account
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account_truncating()
+        }
+
+        /// Whether an asset is the native currency
+        fn is_native(asset: &T::AssetKind) -> bool {
+            *asset == T::NativeAsset::get()
+        }
+
+        /// `amount` if it is in the native asset, zero otherwise; provider stats count native
+        /// earnings only
+        fn native_amount(asset: &T::AssetKind, amount: BalanceOf<T>) -> BalanceOf<T> {
+            if Self::is_native(asset) { amount } else { Zero::zero() }
+        }
+
+        /// Smallest reward a batch paid in `asset` can allocate
+        fn minimum_reward(asset: &T::AssetKind) -> BalanceOf<T> {
+            if Self::is_native(asset) {
+                T::MinimumReward::get()
+            } else {
+                T::Assets::minimum_balance(asset.clone())
+            }
+        }
+
+        /// Whether `who` may create the reward batch of a task
+        pub fn is_coordinator(task_id: &T::Hash, who: &T::AccountId) -> bool {
+            T::Tasks::creator(task_id).as_ref() == Some(who) ||
+                DelegatedCoordinators::<T>::get(task_id).as_ref() == Some(who)
+        }
+
+        /// Return a coordinator's bond once its batch settled cleanly
+        fn release_coordinator_bond(batch_id: T::Hash) {
+            if let Some((coordinator, bond)) = CoordinatorBonds::<T>::take(&batch_id) {
+                let released =
+                    T::Currency::release(&HoldReason::CoordinatorBond.into(), &coordinator, bond, Precision::BestEffort)
+                        .unwrap_or_default();
+                Self::deposit_event(Event::CoordinatorBondReleased { batch_id, coordinator, amount: released });
+            }
+        }
+
+        /// Whether a stream's provider left the task or was slashed since the stream opened
+        fn stream_broken(task_id: &T::Hash, provider: &T::AccountId, stream: &PaymentStream<T>) -> bool {
+            !T::Tasks::is_provider(task_id, provider) ||
+                T::Contributions::applied_slash_count(provider) > stream.slashes_at_open
+        }
+
+        /// Remove a stream and refund what it has not paid out to the task creator
+        fn end_stream(task_id: T::Hash, provider: T::AccountId, stream: PaymentStream<T>) -> DispatchResult {
+            let creator = T::Tasks::creator(&task_id).ok_or(Error::<T>::TaskNotFound)?;
+
+            Self::remove_stream(&task_id, &provider);
+            if !stream.remaining.is_zero() {
+                T::Assets::transfer(stream.asset, &Self::account_id(), &creator, stream.remaining, Preservation::Expendable)?;
+            }
+
+            Self::deposit_event(Event::StreamStopped { task_id, provider, refunded: stream.remaining });
+
+            Ok(())
+        }
+
+        /// Remove a stream and its index entries
+        fn remove_stream(task_id: &T::Hash, provider: &T::AccountId) {
+            PaymentStreams::<T>::remove(task_id, provider);
+            StreamCount::<T>::mutate_exists(task_id, |count| {
+                *count = count.map(|c| c.saturating_sub(1)).filter(|c| *c > 0);
+            });
+            ProviderStreams::<T>::mutate_exists(provider, |maybe_tasks| {
+                if let Some(tasks) = maybe_tasks {
+                    tasks.retain(|task| task != task_id);
+                    if tasks.is_empty() {
+                        *maybe_tasks = None;
+                    }
+                }
+            });
+        }
+
+        /// Record a provider's reward in a batch as allocated
+        fn allocate(batch_id: T::Hash, asset: &T::AssetKind, reward: &ProviderReward<T>) -> DispatchResult {
+            ensure!(
+                !BatchRewards::<T>::contains_key(&batch_id, &reward.provider),
+                Error::<T>::DuplicateReward
+            );
+
+            BatchRewards::<T>::insert(&batch_id, &reward.provider, reward.clone());
+            PendingRewards::<T>::mutate(&reward.provider, asset, |pending| {
+                *pending = pending.saturating_add(reward.amount);
+            });
+
+            Self::deposit_event(Event::RewardAllocated {
+                provider: reward.provider.clone(),
+                amount: reward.amount,
+            });
+
+            Ok(())
+        }
+
+        /// Compute each provider's share of `bounty` from its metrics with `calculate_reward`.
+        ///
+        /// The raw rewards are scaled so they add up to exactly `bounty`: every share is rounded
+        /// down, and the rounding dust goes to the provider with the largest raw reward (the
+        /// first one listed on a tie). Shares below `minimum_reward` go to that provider too, so
+        /// every computed reward can be allocated.
+        pub fn compute_rewards(
+            bounty: BalanceOf<T>,
+            minimum_reward: BalanceOf<T>,
+            metrics: &[ContributionMetrics<T::AccountId>],
+        ) -> Result<Vec<BalanceOf<T>>, DispatchError> {
+            let total_gradients = metrics.iter().fold(0u64, |t, m| t.saturating_add(m.gradients_contributed));
+
+            let raw: Vec<u128> = metrics
+                .iter()
+                .map(|m| {
+                    Self::calculate_reward(
+                        bounty,
+                        m.gradients_contributed,
+                        total_gradients,
+                        m.quality_score,
+                        T::TierMultiplier::convert(m.gpu_tier),
+                    )
+                    .saturated_into::<u128>()
+                })
+                .collect();
+            let total_raw = raw.iter().fold(0u128, |t, r| t.saturating_add(*r));
+            ensure!(total_raw > 0, Error::<T>::NoContribution);
+
+            let bounty_raw = bounty.saturated_into::<u128>();
+            let mut amounts: Vec<u128> = raw
+                .iter()
+                .map(|r| multiply_by_rational_with_rounding(*r, bounty_raw, total_raw, Rounding::Down).unwrap_or(0))
+                .collect();
+
+            let largest = raw
+                .iter()
+                .enumerate()
+                .fold(0usize, |best, (i, r)| if *r > raw[best] { i } else { best });
+            let minimum_raw = minimum_reward.saturated_into::<u128>();
+            for (i, amount) in amounts.iter_mut().enumerate() {
+                if i != largest && *amount < minimum_raw {
+                    *amount = 0;
+                }
+            }
+
+            let distributed = amounts.iter().fold(0u128, |t, a| t.saturating_add(*a));
+            amounts[largest] = amounts[largest].saturating_add(bounty_raw.saturating_sub(distributed));
+            ensure!(amounts[largest] >= minimum_raw, Error::<T>::InvalidRewardAmount);
+
+            Ok(amounts.into_iter().map(|a| a.saturated_into()).collect())
+        }
+
+        /// Whether the settlement engine is part-way through a batch
+        fn is_settling(batch_id: &T::Hash) -> bool {
+            CurrentSettlement::<T>::get().is_some_and(|cursor| cursor.batch_id == *batch_id)
+        }
+
+        /// First queued, unchallenged batch whose settlement period has passed, and the weight
+        /// of finding it.
+        ///
+        /// Batches settled by root or discarded are dropped from the queue on the way; the
+        /// queue is only written back when that changed it.
+        fn next_due_batch(now: BlockNumberFor<T>) -> (Option<(T::Hash, T::AssetKind)>, Weight) {
+            let mut queue = SettlementQueue::<T>::get();
+            let queued = queue.len();
+            queue.retain(|batch_id| RewardBatches::<T>::get(batch_id).is_some_and(|batch| !batch.settled));
+            let mut reads = 1u64.saturating_add(queued as u64);
+
+            let mut due = None;
+            for batch_id in queue.iter() {
+                reads = reads.saturating_add(1);
+                if BatchChallenges::<T>::contains_key(batch_id) {
+                    continue;
+                }
+                reads = reads.saturating_add(1);
+                let Some(batch) = RewardBatches::<T>::get(batch_id) else { continue };
+                // The queue is in creation order, so no later batch is due either
+                if now >= batch.created_at.saturating_add(T::SettlementPeriod::get()) {
+                    due = Some((*batch_id, batch.asset));
+                }
+                break;
+            }
+
+            let mut weight = T::DbWeight::get().reads(reads);
+            if queue.len() != queued {
+                SettlementQueue::<T>::put(queue);
+                weight = weight.saturating_add(T::DbWeight::get().writes(1));
+            }
+            (due, weight)
+        }
+
+        /// Worst-case weight of `next_due_batch`: every queued batch read twice and checked
+        /// for a challenge, and the queue rewritten
+        fn max_due_batch_scan() -> Weight {
+            let queued = T::MaxQueuedBatches::get() as u64;
+            T::DbWeight::get().reads_writes(queued.saturating_mul(3).saturating_add(1), 1)
+        }
+
+        /// Remove a batch and reverse its allocations
+        fn discard_batch(batch_id: T::Hash, asset: &T::AssetKind) {
+            // Bounded by `MaxProvidersPerBatch`
+            for (provider, reward) in BatchRewards::<T>::drain_prefix(&batch_id) {
+                PendingRewards::<T>::mutate_exists(&provider, asset, |pending| {
+                    *pending = pending.map(|p| p.saturating_sub(reward.amount)).filter(|p| !p.is_zero());
+                });
+            }
+            RewardBatches::<T>::remove(&batch_id);
+            BatchAllocated::<T>::remove(&batch_id);
+            BatchMetrics::<T>::remove(&batch_id);
+            SettlementQueue::<T>::mutate(|queue| queue.retain(|queued| *queued != batch_id));
+        }
+
+        /// Settle due batches reward by reward until `limit` is used up.
+        ///
+        /// The engine keeps a cursor into the batch it is working on, so a batch of any size
+        /// settles over as many blocks as it needs. Rewards that fail are recorded in
+        /// `FailedSettlements` and do not hold up the rest of the batch.
+        pub(crate) fn settle_step(now: BlockNumberFor<T>, limit: Weight) -> Weight {
+            let db = T::DbWeight::get();
+            let per_reward = db.reads_writes(8, 7);
+            // Includes routing the batch's fees, up to three transfers when they are split, and
+            // refunding what the batch did not allocate
+            let per_batch = db.reads_writes(19, 18);
+            let mut used = db.reads(2);
+
+            if used.saturating_add(per_batch).any_gt(limit) {
+                return Weight::zero();
+            }
+
+            loop {
+                let mut cursor = match CurrentSettlement::<T>::get() {
+                    Some(cursor) => cursor,
+                    None => {
+                        if used.saturating_add(Self::max_due_batch_scan()).saturating_add(per_batch).any_gt(limit) {
+                            break;
+                        }
+                        let (due, scan) = Self::next_due_batch(now);
+                        used = used.saturating_add(scan);
+                        match due {
+                            Some((batch_id, asset)) => SettlementCursor::<T>::new(batch_id, asset),
+                            None => break,
+                        }
+                    },
+                };
+                used = used.saturating_add(per_batch);
+
+                let mut rewards = match &cursor.last_key {
+                    Some(key) => BatchRewards::<T>::iter_prefix_from(&cursor.batch_id, key.to_vec()),
+                    None => BatchRewards::<T>::iter_prefix(&cursor.batch_id),
+                };
+
+                let mut exhausted = true;
+                loop {
+                    if used.saturating_add(per_reward).any_gt(limit) {
+                        exhausted = false;
+                        break;
+                    }
+                    let Some((provider, reward)) = rewards.next() else { break };
+                    used = used.saturating_add(per_reward);
+
+                    Self::settle_or_record(&mut cursor, provider, &reward);
+                }
+
+                if !exhausted {
+                    cursor.last_key = BoundedVec::try_from(rewards.last_raw_key().to_vec()).ok();
+                    Self::deposit_event(Event::SettlementProgress {
+                        batch_id: cursor.batch_id,
+                        settled: cursor.settled,
+                        failed: cursor.failed,
+                    });
+                    CurrentSettlement::<T>::put(cursor);
+                    break;
+                }
+
+                Self::finish_settlement(cursor);
+
+                if used.saturating_add(per_batch).any_gt(limit) {
+                    break;
+                }
+            }
+
+            used
+        }
+
+        /// Return part of a batch's bounty from escrow to the task creator
+        fn refund_bounty(batch_id: T::Hash, batch: &RewardBatch<T>, amount: BalanceOf<T>) -> DispatchResult {
+            let creator = T::Tasks::creator(&batch.task_id).ok_or(Error::<T>::TaskNotPayable)?;
+            T::Assets::transfer(batch.asset.clone(), &Self::account_id(), &creator, amount, Preservation::Expendable)?;
+            RefundedBounty::<T>::mutate(&batch_id, |refunded| *refunded = refunded.saturating_add(amount));
+
+            Self::deposit_event(Event::BountyRefunded { batch_id, creator, amount });
+
+            Ok(())
+        }
+
+        /// Settle one reward, recording it for retry if it fails
+        fn settle_or_record(cursor: &mut SettlementCursor<T>, provider: T::AccountId, reward: &ProviderReward<T>) {
+            match Self::settle_reward(cursor.batch_id, &cursor.asset, &provider, reward) {
+                Ok(fee) => {
+                    cursor.settled = cursor.settled.saturating_add(1);
+                    cursor.total_fee = cursor.total_fee.saturating_add(fee);
+                },
+                Err(error) => {
+                    cursor.failed = cursor.failed.saturating_add(1);
+                    FailedSettlements::<T>::insert(&cursor.batch_id, &provider, error);
+                    Self::deposit_event(Event::SettlementFailed { batch_id: cursor.batch_id, provider, error });
+                },
+            }
+        }
+
+        /// Mark a fully processed batch settled, refund what it did not allocate and report
+        /// the outcome
+        fn finish_settlement(cursor: SettlementCursor<T>) {
+            let batch_id = cursor.batch_id;
+
+            let settled = RewardBatches::<T>::mutate(&batch_id, |maybe_batch| {
+                maybe_batch.as_mut().map(|batch| {
+                    batch.settled = true;
+                    batch.clone()
+                })
+            });
+            // Merkle batches allocate nothing up front; their unclaimed rest is refunded once
+            // their claim period is over
+            if let Some(batch) = settled {
+                let allocated = BatchAllocated::<T>::get(&batch_id);
+                let unallocated = batch.total_bounty.saturating_sub(allocated);
+                if !allocated.is_zero() && !unallocated.is_zero() {
+                    if let Err(error) = Self::refund_bounty(batch_id, &batch, unallocated) {
+                        log::warn!(target: "reward-distribution", "failed to refund batch {:?}: {:?}", batch_id, error);
+                    }
+                }
+            }
+            SettlementQueue::<T>::mutate(|queue| queue.retain(|queued| *queued != batch_id));
+            CurrentSettlement::<T>::kill();
+            if FailedSettlements::<T>::iter_prefix(&batch_id).next().is_none() {
+                Self::release_coordinator_bond(batch_id);
+            }
+            LastSettlementBlock::<T>::put(frame_system::Pallet::<T>::block_number());
+
+            Self::deposit_event(Event::BatchSettled {
+                batch_id,
+                settled: cursor.settled,
+                failed: cursor.failed,
+                total_fee: cursor.total_fee,
+            });
+
+            // Fees that cannot be routed now stay in escrow for `route_unrouted_fees`
+            match with_storage_layer(|| Self::route_fee(&cursor.asset, cursor.total_fee)) {
+                Ok(()) => Self::collect_fee(cursor.asset, cursor.total_fee),
+                Err(error) => {
+                    log::warn!(target: "reward-distribution", "failed to route fees of batch {:?}: {:?}", batch_id, error);
+                    UnroutedFees::<T>::mutate(&cursor.asset, |unrouted| *unrouted = unrouted.saturating_add(cursor.total_fee));
+                    Self::deposit_event(Event::FeeRoutingFailed { asset: cursor.asset, amount: cursor.total_fee, error });
+                },
+            }
+        }
+
+        /// Account for platform fees that were routed
+        fn collect_fee(asset: T::AssetKind, amount: BalanceOf<T>) {
+            if amount.is_zero() {
+                return;
+            }
+            PlatformFeesCollected::<T>::mutate(&asset, |total| *total = total.saturating_add(amount));
+            Self::deposit_event(Event::PlatformFeeCollected { asset, amount });
+        }
+
+        /// Move one allocated reward into the provider's claimable balance, returning the fee taken.
+        ///
+        /// Runs in its own storage layer so a failure leaves no partial changes behind.
+        fn settle_reward(
+            batch_id: T::Hash,
+            asset: &T::AssetKind,
+            provider: &T::AccountId,
+            reward: &ProviderReward<T>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            with_storage_layer(|| {
+                let task_id = RewardBatches::<T>::get(&batch_id).ok_or(Error::<T>::BatchNotFound)?.task_id;
+                let fee = PlatformFee::<T>::get() * reward.amount;
+                let net_reward = reward.amount.saturating_sub(fee);
+
+                // Allocations paid out before settlement by the old claim path are gone
+                PendingRewards::<T>::try_mutate(provider, asset, |pending| {
+                    ensure!(*pending >= reward.amount, Error::<T>::AllocationMissing);
+                    *pending = pending.saturating_sub(reward.amount);
+                    Ok::<(), DispatchError>(())
+                })?;
+
+                let commission = Self::split_commission(provider, asset, net_reward);
+                Self::credit_reward(provider, asset, commission)?;
+                T::Stats::on_reward_settled(
+                    provider,
+                    &task_id,
+                    reward.gradients_contributed,
+                    Self::native_amount(asset, reward.amount),
+                );
+
+                Self::deposit_event(Event::RewardSettled {
+                    batch_id,
+                    provider: provider.clone(),
+                    amount: commission,
+                });
+
+                Ok(fee)
+            })
+        }
+
+        /// Send collected platform fees out of escrow to the configured destination
+        fn route_fee(asset: &T::AssetKind, amount: BalanceOf<T>) -> DispatchResult {
+            if amount.is_zero() {
+                return Ok(());
+            }
+
+            let escrow_account = Self::account_id();
+            let send = |dest: &T::AccountId, share: BalanceOf<T>| -> DispatchResult {
+                if !share.is_zero() {
+                    T::Assets::transfer(asset.clone(), &escrow_account, dest, share, Preservation::Expendable)?;
+                }
+                Ok(())
+            };
+
+            match FeeRouting::<T>::get() {
+                FeeDestination::Treasury => send(&T::TreasuryAccount::get(), amount),
+                FeeDestination::Burn => {
+                    T::Assets::burn_from(
+                        asset.clone(),
+                        &escrow_account,
+                        amount,
+                        Preservation::Expendable,
+                        Precision::Exact,
+                        Fortitude::Polite,
+                    )?;
+                    Ok(())
+                },
+                FeeDestination::Split { validators, stakers } => {
+                    let to_validators = validators * amount;
+                    let to_stakers = stakers * amount;
+                    send(&T::ValidatorFeePot::get(), to_validators)?;
+                    send(&T::StakerFeePot::get(), to_stakers)?;
+                    send(&T::TreasuryAccount::get(), amount.saturating_sub(to_validators).saturating_sub(to_stakers))
+                },
+            }
+        }
+
+        /// Set the delegator share of a net reward aside and return the provider's commission
+        fn split_commission(provider: &T::AccountId, asset: &T::AssetKind, net_reward: BalanceOf<T>) -> BalanceOf<T> {
+            let commission = T::Providers::commission(provider) * net_reward;
+            let delegator_share = net_reward.saturating_sub(commission);
+
+            if !delegator_share.is_zero() {
+                DelegatorRewards::<T>::mutate(provider, asset, |owed| *owed = owed.saturating_add(delegator_share));
+                Self::deposit_event(Event::DelegatorRewardAccrued {
+                    provider: provider.clone(),
+                    amount: delegator_share,
+                });
+            }
+
+            commission
+        }
+
+        /// Credit a provider's net reward to its stake if it auto-compounds, or make it claimable
+        fn credit_reward(provider: &T::AccountId, asset: &T::AssetKind, amount: BalanceOf<T>) -> DispatchResult {
+            if Self::try_compound(provider, asset, amount) {
+                return Ok(());
+            }
+
+            ClaimableRewards::<T>::try_mutate(provider, asset, |claimable| {
+                *claimable = claimable.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+                Ok::<(), DispatchError>(())
+            })
+        }
+
+        /// Move a native reward from escrow into the stake of an auto-compounding provider,
+        /// returning whether it was compounded.
+        ///
+        /// A provider that cannot take more stake (e.g. unbonding) keeps the reward to claim.
+        fn try_compound(provider: &T::AccountId, asset: &T::AssetKind, amount: BalanceOf<T>) -> bool {
+            if amount.is_zero() || !Self::is_native(asset) || !T::Providers::auto_compound(provider) {
+                return false;
+            }
+
+            let compounded =
+                with_storage_layer(|| T::Providers::compound(provider, &Self::account_id(), amount)).is_ok();
+            if compounded {
+                TotalRewardsDistributed::<T>::mutate(asset, |total| *total = total.saturating_add(amount));
+            }
+            compounded
+        }
+
+        /// Pay out a claimed reward, putting all but the immediate share of large native rewards
+        /// on a vesting schedule
+        fn pay_out_vesting(provider: &T::AccountId, asset: &T::AssetKind, amount: BalanceOf<T>) -> DispatchResult {
+            if !Self::is_native(asset) || amount < T::VestingThreshold::get() {
+                return Self::pay_out(provider, asset, amount);
+            }
+
+            // Release what has vested first, freeing slots of finished schedules
+            Self::release_vested(provider)?;
+
+            let immediate = T::ImmediateRelease::get() * amount;
+            let locked = amount.saturating_sub(immediate);
+            if !locked.is_zero() {
+                Vesting::<T>::try_mutate(provider, |schedules| {
+                    schedules.try_push(VestingSchedule {
+                        locked,
+                        released: Zero::zero(),
+                        starting_block: frame_system::Pallet::<T>::block_number(),
+                        duration: T::VestingPeriod::get(),
+                    })
+                })
+                .map_err(|_| Error::<T>::TooManyVestingSchedules)?;
+            }
+            Self::pay_out(provider, asset, immediate)?;
+
+            Self::deposit_event(Event::RewardVesting { provider: provider.clone(), immediate, locked });
+
+            Ok(())
+        }
+
+        /// Pay out everything vested so far and drop finished schedules
+        fn release_vested(provider: &T::AccountId) -> Result<BalanceOf<T>, DispatchError> {
+            let now = frame_system::Pallet::<T>::block_number();
+            let mut schedules = Vesting::<T>::get(provider);
+
+            let mut released: BalanceOf<T> = Zero::zero();
+            for schedule in schedules.iter_mut() {
+                let amount = schedule.releasable(now);
+                schedule.released = schedule.released.saturating_add(amount);
+                released = released.saturating_add(amount);
+            }
+            schedules.retain(|schedule| !schedule.remaining().is_zero());
+
+            if schedules.is_empty() {
+                Vesting::<T>::remove(provider);
+            } else {
+                Vesting::<T>::insert(provider, schedules);
+            }
+
+            if !released.is_zero() {
+                Self::pay_out(provider, &T::NativeAsset::get(), released)?;
+                Self::deposit_event(Event::Vested { provider: provider.clone(), amount: released });
+            }
+
+            Ok(released)
+        }
+
+        /// Transfer a provider's reward from escrow to its payout destination
+        fn pay_out(provider: &T::AccountId, asset: &T::AssetKind, amount: BalanceOf<T>) -> DispatchResult {
+            if amount.is_zero() {
+                return Ok(());
+            }
+
+            // Escrow may pay out the last of a non-native asset; the native existential deposit it
+            // was endowed with is never owed to anyone, so it stays alive
+            let escrow_account = Self::account_id();
+            let transfer = |dest: &T::AccountId| {
+                T::Assets::transfer(asset.clone(), &escrow_account, dest, amount, Preservation::Expendable).map(|_| ())
+            };
+            match T::Providers::payee(provider) {
+                RewardDestination::Stash => transfer(provider),
+                RewardDestination::Account(dest) => transfer(&dest),
+                RewardDestination::Staked => {
+                    transfer(provider)?;
+                    // Only native rewards can be staked. A provider that cannot take more stake
+                    // (e.g. unbonding) keeps the reward free
+                    if Self::is_native(asset) {
+                        if let Err(error) = with_storage_layer(|| T::Providers::restake(provider, amount)) {
+                            log::warn!(target: "reward-distribution", "failed to restake reward of {:?}: {:?}", provider, error);
+                            Self::deposit_event(Event::RestakeFailed { provider: provider.clone(), amount, error });
+                        }
+                    }
+                    Ok(())
+                },
+            }
+        }
+
+        /// Check the reward state machine: allocations stay within each batch's bounty, settled
+        /// batches refunded what they did not allocate, unsettled allocations match
+        /// `PendingRewards`, and escrow covers every locked or settled reward that has not been
+        /// claimed yet.
+        #[cfg(feature = "try-runtime")]
+        pub fn do_try_state() -> Result<(), sp_runtime::TryRuntimeError> {
+            use sp_std::collections::btree_map::BTreeMap;
+
+            let mut unsettled: BTreeMap<(T::AccountId, T::AssetKind), BalanceOf<T>> = BTreeMap::new();
+            let mut liabilities: BTreeMap<T::AssetKind, BalanceOf<T>> = BTreeMap::new();
+            let mut owe = |asset: T::AssetKind, amount: BalanceOf<T>| {
+                let owed = liabilities.entry(asset).or_insert_with(Zero::zero);
+                *owed = owed.saturating_add(amount);
+            };
+
+            for (batch_id, batch) in RewardBatches::<T>::iter() {
+                let mut allocated: BalanceOf<T> = Zero::zero();
+                let mut locked: BalanceOf<T> = Zero::zero();
+                for (provider, reward) in BatchRewards::<T>::iter_prefix(&batch_id) {
+                    allocated = allocated.saturating_add(reward.amount);
+                    if !batch.settled || FailedSettlements::<T>::contains_key(&batch_id, &provider) {
+                        locked = locked.saturating_add(reward.amount);
+                        let owed = unsettled.entry((provider, batch.asset.clone())).or_insert_with(Zero::zero);
+                        *owed = owed.saturating_add(reward.amount);
+                    }
+                }
+
+                ensure!(allocated <= batch.total_bounty, "batch allocations exceed its bounty");
+                ensure!(
+                    BatchAllocated::<T>::get(&batch_id) <= batch.total_bounty,
+                    "batch allocated total exceeds its bounty"
+                );
+                let refunded = RefundedBounty::<T>::get(&batch_id);
+                ensure!(
+                    ClaimedByProof::<T>::get(&batch_id).saturating_add(refunded) <= batch.total_bounty,
+                    "proof claims and refunds exceed the batch bounty"
+                );
+
+                // Itemised batches owe their allocations until settled and refund the rest; merkle
+                // batches owe their bounty until claimed or refunded
+                if BatchAllocated::<T>::get(&batch_id).is_zero() {
+                    locked = batch
+                        .total_bounty
+                        .saturating_sub(ClaimedByProof::<T>::get(&batch_id))
+                        .saturating_sub(refunded);
+                } else if batch.settled {
+                    ensure!(
+                        BatchAllocated::<T>::get(&batch_id).saturating_add(refunded) == batch.total_bounty,
+                        "unallocated bounty of a settled batch was not refunded"
+                    );
+                }
+                owe(batch.asset, locked);
+            }
+
+            for (provider, asset, pending) in PendingRewards::<T>::iter() {
+                ensure!(
+                    unsettled.get(&(provider, asset)).copied().unwrap_or_else(Zero::zero) == pending,
+                    "pending rewards do not match unsettled allocations"
+                );
+            }
+            for ((provider, asset), owed) in unsettled {
+                ensure!(
+                    PendingRewards::<T>::get(&provider, &asset) == owed,
+                    "unsettled allocation missing from pending rewards"
+                );
+            }
+
+            for (_, asset, owed) in ClaimableRewards::<T>::iter().chain(DelegatorRewards::<T>::iter()) {
+                owe(asset, owed);
+            }
+            for schedule in Vesting::<T>::iter_values().flat_map(|schedules| schedules.into_iter()) {
+                owe(T::NativeAsset::get(), schedule.remaining());
+            }
+            for stream in PaymentStreams::<T>::iter_values() {
+                owe(stream.asset, stream.remaining);
+            }
+            for (asset, unrouted) in UnroutedFees::<T>::iter() {
+                owe(asset, unrouted);
+            }
+
+            for (asset, owed) in liabilities {
+                ensure!(
+                    T::Assets::balance(asset, &Self::account_id()) >= owed,
+                    "escrow cannot cover locked, settled, vesting and streamed rewards and unrouted fees"
+                );
+            }
+
+            Ok(())
+        }
+
+        /// Calculate reward for a provider based on contribution
+        pub fn calculate_reward(
+            base_bounty: BalanceOf<T>,
+            gradients: u64,
+            total_gradients: u64,
+            quality_score: u32,
+            hardware_multiplier: u32,
+        ) -> BalanceOf<T> {
+            if total_gradients == 0 {
+                return Zero::zero();
+            }
+
+            // Base reward proportional to contribution
+            let contribution_ratio = Permill::from_rational(gradients, total_gradients);
+            let base_reward = contribution_ratio * base_bounty;
+
+            // Apply quality multiplier (0-1000 score maps to 0-100%)
+            let quality_multiplier = Permill::from_rational(quality_score, 1000u32);
+            let quality_adjusted = quality_multiplier * base_reward;
+
+            // Apply hardware multiplier (stored as u32, 100 = 1.0x)
+            let hardware_adjusted = quality_adjusted
+                .saturating_mul(hardware_multiplier.into())
+                / 100u32.into();
+
+            hardware_adjusted
+        }
+    }
+
+    impl<T: Config> TaskHooks<T::AccountId, T::Hash> for Pallet<T> {
+        fn on_provider_left(task_id: &T::Hash, who: &T::AccountId) -> DispatchResult {
+            match PaymentStreams::<T>::get(task_id, who) {
+                Some(stream) => Self::end_stream(*task_id, who.clone(), stream),
+                None => Ok(()),
+            }
+        }
+
+        fn on_task_completed(task_id: &T::Hash) -> DispatchResult {
+            // Bounded by `MaxStreamsPerTask`
+            let streams = PaymentStreams::<T>::iter_prefix(task_id).collect::<Vec<_>>();
+            for (provider, stream) in streams {
+                Self::end_stream(*task_id, provider, stream)?;
+            }
+            LastClosedRound::<T>::remove(task_id);
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> OnProviderSlashed<T::AccountId> for Pallet<T> {
+        fn on_provider_slashed(who: &T::AccountId) {
+            // Bounded by `MaxStreamsPerProvider`
+            for task_id in ProviderStreams::<T>::get(who) {
+                let Some(stream) = PaymentStreams::<T>::get(&task_id, who) else { continue };
+                // A stream that cannot be refunded now is stopped by the next `close_round`
+                if let Err(e) = with_storage_layer(|| Self::end_stream(task_id, who.clone(), stream)) {
+                    log::warn!(target: "reward-distribution", "failed to stop stream of slashed provider on task {:?}: {:?}", task_id, e);
+                }
+            }
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+//! Reward merkle tree shared by `claim_with_proof` and off-chain batch builders.
+//!
+//! A coordinator builds the tree off-chain from the batch's rewards in a fixed order:
+//!
+//! 1. Leaf `i` is `H::hash_of(&(i as u32, account, amount))`, SCALE-encoding the tuple.
+//! 2. Each level pairs nodes left to right into `H::hash_of(&(left, right))`. The last node
+//!    of a level with an odd number of nodes is paired with itself.
+//! 3. The root of a single leaf is the leaf itself.
+//!
+//! A proof lists the sibling of the node at every level, from the leaf up to the root. Bit
+//! `n` of the leaf index tells whether the node at level `n` is a left (0) or right (1)
+//! child. [`root`] and [`proof`] are the reference builder and run in `no_std` as well.
+
+use codec::Encode;
+use scale_info::prelude::vec::Vec;
+use sp_runtime::traits::Hash;
+
+/// Leaf committing to the reward of `account` at position `index`
+pub fn leaf<H: Hash, AccountId: Encode, Balance: Encode>(
+    index: u32,
+    account: &AccountId,
+    amount: &Balance,
+) -> H::Output {
+    H::hash_of(&(index, account, amount))
+}
+
+fn parent<H: Hash>(left: &H::Output, right: &H::Output) -> H::Output {
+    H::hash_of(&(left, right))
+}
+
+fn next_level<H: Hash>(level: &[H::Output]) -> Vec<H::Output> {
+    level
+        .chunks(2)
+        .map(|pair| parent::<H>(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+        .collect()
+}
+
+/// Root of the tree over `leaves`; the default hash for an empty tree
+pub fn root<H: Hash>(leaves: &[H::Output]) -> H::Output {
+    if leaves.is_empty() {
+        return Default::default();
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level::<H>(&level);
+    }
+    level[0]
+}
+
+/// Siblings proving the leaf at `index`, or `None` if the index is out of range
+pub fn proof<H: Hash>(leaves: &[H::Output], index: u32) -> Option<Vec<H::Output>> {
+    let mut position = index as usize;
+    if position >= leaves.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        siblings.push(*level.get(sibling).unwrap_or(&level[position]));
+        level = next_level::<H>(&level);
+        position /= 2;
+    }
+    Some(siblings)
+}
+
+/// Check that `leaf` sits at `index` in the tree with the given root
+pub fn verify<H: Hash>(root: &H::Output, leaf: H::Output, index: u32, siblings: &[H::Output]) -> bool {
+    // Index bits above the proof depth would let one leaf be claimed under several indices
+    if siblings.len() < 32 && index >> siblings.len() != 0 {
+        return false;
+    }
+
+    let computed = siblings.iter().enumerate().fold(leaf, |node, (depth, sibling)| {
+        if (index >> depth) & 1 == 0 {
+            parent::<H>(&node, sibling)
+        } else {
+            parent::<H>(sibling, &node)
+        }
+    });
+
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_runtime::traits::BlakeTwo256;
+
+    type H = BlakeTwo256;
+
+    fn leaves(count: u32) -> Vec<<H as Hash>::Output> {
+        (0..count).map(|i| leaf::<H, _, _>(i, &(100u64 + i as u64), &(1_000u128 * (i as u128 + 1)))).collect()
+    }
+
+    fn round_trips(count: u32) {
+        let leaves = leaves(count);
+        let root = root::<H>(&leaves);
+        for index in 0..count {
+            let siblings = proof::<H>(&leaves, index).unwrap();
+            assert!(verify::<H>(&root, leaves[index as usize], index, &siblings), "leaf {} of {}", index, count);
+        }
+    }
+
+    #[test]
+    fn single_leaf_is_its_own_root() {
+        let leaves = leaves(1);
+        assert_eq!(root::<H>(&leaves), leaves[0]);
+        assert_eq!(proof::<H>(&leaves, 0), Some(Vec::new()));
+        assert!(verify::<H>(&leaves[0], leaves[0], 0, &[]));
+    }
+
+    #[test]
+    fn every_leaf_round_trips() {
+        for count in 1..=17 {
+            round_trips(count);
+        }
+    }
+
+    #[test]
+    fn odd_leaf_counts_pair_the_last_node_with_itself() {
+        let leaves = leaves(3);
+        let left = parent::<H>(&leaves[0], &leaves[1]);
+        let right = parent::<H>(&leaves[2], &leaves[2]);
+        assert_eq!(root::<H>(&leaves), parent::<H>(&left, &right));
+        assert_eq!(proof::<H>(&leaves, 2), Some(vec![leaves[2], left]));
+    }
+
+    #[test]
+    fn wrong_index_is_rejected() {
+        let leaves = leaves(5);
+        let root = root::<H>(&leaves);
+        let siblings = proof::<H>(&leaves, 1).unwrap();
+
+        assert!(!verify::<H>(&root, leaves[1], 0, &siblings));
+        assert!(!verify::<H>(&root, leaves[1], 3, &siblings));
+        // Same path bits, but above the proof depth
+        assert!(!verify::<H>(&root, leaves[1], 1 | (1 << siblings.len()), &siblings));
+        assert_eq!(proof::<H>(&leaves, 5), None);
+    }
+
+    #[test]
+    fn wrong_amount_or_account_is_rejected() {
+        let leaves = leaves(4);
+        let root = root::<H>(&leaves);
+        let siblings = proof::<H>(&leaves, 2).unwrap();
+
+        let inflated = leaf::<H, _, _>(2, &102u64, &3_001u128);
+        assert!(!verify::<H>(&root, inflated, 2, &siblings));
+        let other_account = leaf::<H, _, _>(2, &999u64, &3_000u128);
+        assert!(!verify::<H>(&root, other_account, 2, &siblings));
+        assert!(verify::<H>(&root, leaf::<H, _, _>(2, &102u64, &3_000u128), 2, &siblings));
+    }
+}
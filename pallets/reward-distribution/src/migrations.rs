@@ -0,0 +1,376 @@
+//! Storage migrations for the reward distribution pallet.
+
+use super::*;
+use frame_support::{storage_alias, traits::UncheckedOnRuntimeUpgrade};
+
+pub mod v1 {
+    use super::*;
+
+    /// `RewardBatch` as of storage versions 0 and 1
+    #[derive(Encode, Decode, Clone, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct OldRewardBatch<T: Config> {
+        pub task_id: T::Hash,
+        pub total_bounty: BalanceOf<T>,
+        pub coordinator: T::AccountId,
+        pub created_at: BlockNumberFor<T>,
+        pub settled: bool,
+        pub merkle_root: T::Hash,
+    }
+
+    /// Batches as stored before they named their asset
+    #[storage_alias]
+    pub type RewardBatches<T: Config> = StorageMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        <T as frame_system::Config>::Hash,
+        OldRewardBatch<T>,
+    >;
+
+    /// Queues every unsettled batch for the settlement engine, oldest first.
+    pub struct InnerMigrateToV1<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut reads = 0u64;
+            let mut unsettled: Vec<(BlockNumberFor<T>, T::Hash)> = Vec::new();
+
+            for (batch_id, batch) in RewardBatches::<T>::iter() {
+                reads += 1;
+                if !batch.settled {
+                    unsettled.push((batch.created_at, batch_id));
+                }
+            }
+            unsettled.sort();
+
+            let mut queue = BoundedVec::<T::Hash, T::MaxQueuedBatches>::default();
+            for (_, batch_id) in unsettled {
+                if queue.try_push(batch_id).is_err() {
+                    log::error!(target: "reward-distribution", "settlement queue full, {:?} left unqueued", batch_id);
+                }
+            }
+            let queued = queue.len();
+            SettlementQueue::<T>::put(queue);
+
+            log::info!(target: "reward-distribution", "queued {} unsettled batches", queued);
+
+            T::DbWeight::get().reads_writes(reads, 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            let unsettled = RewardBatches::<T>::iter_values().filter(|batch| !batch.settled).count() as u32;
+            Ok(unsettled.min(T::MaxQueuedBatches::get()).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let expected = u32::decode(&mut &state[..]).map_err(|_| "v1: invalid pre-upgrade state")?;
+            let queue = SettlementQueue::<T>::get();
+            ensure!(queue.len() as u32 == expected, "v1: not every unsettled batch was queued");
+
+            let mut last_created = None;
+            for batch_id in queue.iter() {
+                let batch = RewardBatches::<T>::get(batch_id).ok_or("v1: queued batch does not exist")?;
+                ensure!(!batch.settled, "v1: settled batch queued");
+                ensure!(last_created.map_or(true, |last| last <= batch.created_at), "v1: queue out of creation order");
+                last_created = Some(batch.created_at);
+            }
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV1<T> = frame_support::migrations::VersionedMigration<
+        0,
+        1,
+        InnerMigrateToV1<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v2 {
+    use super::*;
+
+    /// `SettlementCursor` as of storage version 1
+    #[derive(Encode, Decode, Clone, TypeInfo, PartialEq, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct OldSettlementCursor<T: Config> {
+        pub batch_id: T::Hash,
+        pub last_key: Option<BoundedVec<u8, ConstU32<256>>>,
+        pub settled: u32,
+        pub failed: u32,
+        pub total_fee: BalanceOf<T>,
+    }
+
+    /// Provider ledgers as of storage version 1, all in the native asset
+    pub mod old {
+        use super::*;
+
+        #[storage_alias]
+        pub type PendingRewards<T: Config> = StorageMap<
+            Pallet<T>,
+            Blake2_128Concat,
+            <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
+            ValueQuery,
+        >;
+
+        #[storage_alias]
+        pub type DelegatorRewards<T: Config> = StorageMap<
+            Pallet<T>,
+            Blake2_128Concat,
+            <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
+            ValueQuery,
+        >;
+
+        #[storage_alias]
+        pub type ClaimableRewards<T: Config> = StorageMap<
+            Pallet<T>,
+            Blake2_128Concat,
+            <T as frame_system::Config>::AccountId,
+            BalanceOf<T>,
+            ValueQuery,
+        >;
+
+        #[storage_alias]
+        pub type TotalRewardsDistributed<T: Config> = StorageValue<Pallet<T>, BalanceOf<T>, ValueQuery>;
+
+        #[storage_alias]
+        pub type PlatformFeesCollected<T: Config> = StorageValue<Pallet<T>, BalanceOf<T>, ValueQuery>;
+    }
+
+    /// Records that every existing batch, ledger entry and total is in the native asset.
+    ///
+    /// The per-provider ledgers and totals move under the native asset key. Old entries are
+    /// drained before the new ones are written, since both share a storage prefix.
+    pub struct InnerMigrateToV2<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV2<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let native = T::NativeAsset::get();
+            let mut reads = 0u64;
+            let mut writes = 0u64;
+
+            RewardBatches::<T>::translate::<v1::OldRewardBatch<T>, _>(|_, old| {
+                reads += 1;
+                writes += 1;
+                Some(RewardBatch {
+                    task_id: old.task_id,
+                    asset: native.clone(),
+                    total_bounty: old.total_bounty,
+                    coordinator: old.coordinator,
+                    created_at: old.created_at,
+                    settled: old.settled,
+                    merkle_root: old.merkle_root,
+                })
+            });
+
+            let _ = CurrentSettlement::<T>::translate::<OldSettlementCursor<T>, _>(|old| {
+                reads += 1;
+                writes += 1;
+                old.map(|old| SettlementCursor {
+                    batch_id: old.batch_id,
+                    asset: native.clone(),
+                    last_key: old.last_key,
+                    settled: old.settled,
+                    failed: old.failed,
+                    total_fee: old.total_fee,
+                })
+            });
+
+            let pending = old::PendingRewards::<T>::drain().collect::<Vec<_>>();
+            let delegator = old::DelegatorRewards::<T>::drain().collect::<Vec<_>>();
+            let claimable = old::ClaimableRewards::<T>::drain().collect::<Vec<_>>();
+            let moved = (pending.len() + delegator.len() + claimable.len()) as u64;
+
+            for (provider, amount) in pending {
+                PendingRewards::<T>::insert(&provider, &native, amount);
+            }
+            for (provider, amount) in delegator {
+                DelegatorRewards::<T>::insert(&provider, &native, amount);
+            }
+            for (provider, amount) in claimable {
+                ClaimableRewards::<T>::insert(&provider, &native, amount);
+            }
+            reads += moved;
+            writes += moved.saturating_mul(2);
+
+            TotalRewardsDistributed::<T>::insert(&native, old::TotalRewardsDistributed::<T>::take());
+            PlatformFeesCollected::<T>::insert(&native, old::PlatformFeesCollected::<T>::take());
+            reads += 2;
+            writes += 4;
+
+            log::info!(target: "reward-distribution", "moved {} provider ledger entries to the native asset", moved);
+
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            let sum = |entries: Vec<BalanceOf<T>>| {
+                entries.into_iter().fold(BalanceOf::<T>::zero(), |total, amount| total.saturating_add(amount))
+            };
+            let batches = v1::RewardBatches::<T>::iter_keys().count() as u32;
+            let pending = sum(old::PendingRewards::<T>::iter_values().collect());
+            let delegator = sum(old::DelegatorRewards::<T>::iter_values().collect());
+            let claimable = sum(old::ClaimableRewards::<T>::iter_values().collect());
+            let distributed = old::TotalRewardsDistributed::<T>::get();
+            let fees = old::PlatformFeesCollected::<T>::get();
+            Ok((batches, pending, delegator, claimable, distributed, fees).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let (batches, pending, delegator, claimable, distributed, fees) =
+                <(u32, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>)>::decode(&mut &state[..])
+                    .map_err(|_| "v2: invalid pre-upgrade state")?;
+            let native = T::NativeAsset::get();
+            let sum = |entries: Vec<(T::AccountId, T::AssetKind, BalanceOf<T>)>| {
+                entries.into_iter().fold(BalanceOf::<T>::zero(), |total, (_, asset, amount)| {
+                    if asset == native { total.saturating_add(amount) } else { total }
+                })
+            };
+
+            ensure!(RewardBatches::<T>::iter_values().count() as u32 == batches, "v2: batches lost");
+            ensure!(RewardBatches::<T>::iter_values().all(|batch| batch.asset == native), "v2: batch not in native asset");
+            ensure!(sum(PendingRewards::<T>::iter().collect()) == pending, "v2: pending rewards changed");
+            ensure!(sum(DelegatorRewards::<T>::iter().collect()) == delegator, "v2: delegator rewards changed");
+            ensure!(sum(ClaimableRewards::<T>::iter().collect()) == claimable, "v2: claimable rewards changed");
+            ensure!(TotalRewardsDistributed::<T>::get(&native) == distributed, "v2: total distributed changed");
+            ensure!(PlatformFeesCollected::<T>::get(&native) == fees, "v2: fees collected changed");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV2<T> = frame_support::migrations::VersionedMigration<
+        1,
+        2,
+        InnerMigrateToV2<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v3 {
+    use super::*;
+
+    /// Indexes every open payment stream under its provider.
+    ///
+    /// Streams beyond `MaxStreamsPerProvider` stay unindexed; they still stop lazily in
+    /// `close_round` when their provider is slashed.
+    pub struct InnerMigrateToV3<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV3<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut indexed = 0u64;
+            let mut skipped = 0u64;
+
+            for (task_id, provider, _) in PaymentStreams::<T>::iter() {
+                if ProviderStreams::<T>::try_append(&provider, task_id).is_ok() {
+                    indexed += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+
+            log::info!(
+                target: "reward-distribution",
+                "indexed {} payment streams by provider, {} over the per-provider limit",
+                indexed,
+                skipped,
+            );
+
+            let streams = indexed.saturating_add(skipped);
+            T::DbWeight::get().reads_writes(streams.saturating_mul(2), indexed)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            ensure!(ProviderStreams::<T>::iter_keys().next().is_none(), "v3: streams already indexed");
+            Ok((PaymentStreams::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let streams = u32::decode(&mut &state[..]).map_err(|_| "v3: invalid pre-upgrade state")?;
+            let max = T::MaxStreamsPerProvider::get() as usize;
+
+            let mut indexed = 0u32;
+            for (provider, tasks) in ProviderStreams::<T>::iter() {
+                for task_id in tasks.iter() {
+                    ensure!(PaymentStreams::<T>::contains_key(task_id, &provider), "v3: index points at no stream");
+                }
+                indexed = indexed.saturating_add(tasks.len() as u32);
+            }
+            for (task_id, provider) in PaymentStreams::<T>::iter_keys() {
+                let tasks = ProviderStreams::<T>::get(&provider);
+                ensure!(tasks.contains(&task_id) || tasks.len() == max, "v3: stream left unindexed");
+            }
+            ensure!(indexed <= streams, "v3: more index entries than streams");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV3<T> = frame_support::migrations::VersionedMigration<
+        2,
+        3,
+        InnerMigrateToV3<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v4 {
+    use super::*;
+
+    /// Re-bases every open payment stream on its provider's applied slash count.
+    ///
+    /// Streams used to compare against a slash count that included cancelled slashes. Applied
+    /// slashes already end indexed streams eagerly, so open streams carry none since they opened.
+    pub struct InnerMigrateToV4<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV4<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated = 0u64;
+
+            PaymentStreams::<T>::translate::<PaymentStream<T>, _>(|_, provider, stream| {
+                translated += 1;
+                Some(PaymentStream { slashes_at_open: T::Contributions::applied_slash_count(&provider), ..stream })
+            });
+
+            log::info!(target: "reward-distribution", "re-based {} payment streams on applied slashes", translated);
+
+            T::DbWeight::get().reads_writes(translated.saturating_mul(2), translated)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((PaymentStreams::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let streams = u32::decode(&mut &state[..]).map_err(|_| "v4: invalid pre-upgrade state")?;
+            let mut rebased = 0u32;
+            for (_, provider, stream) in PaymentStreams::<T>::iter() {
+                ensure!(
+                    stream.slashes_at_open == T::Contributions::applied_slash_count(&provider),
+                    "v4: stream not re-based on applied slashes"
+                );
+                rebased += 1;
+            }
+            ensure!(rebased == streams, "v4: streams lost in migration");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV4<T> = frame_support::migrations::VersionedMigration<
+        3,
+        4,
+        InnerMigrateToV4<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
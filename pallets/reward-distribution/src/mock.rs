@@ -0,0 +1,404 @@
+//! Test runtime for the reward distribution pallet.
+//!
+//! Tasks, provider settings and contributions come from in-memory mocks so each test can set
+//! up exactly the state it needs. Task bounties are paid out of `TASK_POT`.
+
+use crate as pallet_reward_distribution;
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{
+        fungible::{self, NativeFromLeft, NativeOrWithId, UnionOf},
+        fungibles,
+        tokens::Preservation,
+        AsEnsureOriginWithArg, Hooks,
+    },
+    weights::{constants::RocksDbWeight, Weight},
+    PalletId,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use pallet_provider_staking::{ContributionInspect, GpuTier, ProviderStats, RewardDestination, RewardSettings, SlashIndex};
+use pallet_task_registry::TaskEscrow;
+use sp_core::H256;
+use sp_runtime::{
+    traits::Convert,
+    BuildStorage, DispatchError, DispatchResult, Perbill, Permill,
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = u64;
+pub type Balance = u64;
+pub type AssetKind = NativeOrWithId<u32>;
+
+pub const CREATOR: AccountId = 1;
+pub const ALICE: AccountId = 2;
+pub const BOB: AccountId = 3;
+pub const CHARLIE: AccountId = 4;
+pub const COORDINATOR: AccountId = 5;
+pub const TASK_POT: AccountId = 99;
+pub const TREASURY: AccountId = 90;
+pub const VALIDATOR_POT: AccountId = 91;
+pub const STAKER_POT: AccountId = 92;
+
+pub const INITIAL_BALANCE: Balance = 1_000_000;
+/// Asset some tasks are funded in, with a minimum balance above the native one
+pub const ASSET: u32 = 1;
+pub const ASSET_MIN_BALANCE: Balance = 10;
+pub const SETTLEMENT_PERIOD: u64 = 10;
+pub const PROOF_CLAIM_PERIOD: u64 = 20;
+
+frame_support::construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        Balances: pallet_balances,
+        Assets: pallet_assets,
+        RewardDistribution: pallet_reward_distribution,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type DbWeight = RocksDbWeight;
+    type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+#[derive_impl(pallet_assets::config_preludes::TestDefaultConfig)]
+impl pallet_assets::Config for Test {
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type Freezer = ();
+    type Holder = ();
+}
+
+pub type NativeAndAssets = UnionOf<Balances, Assets, NativeFromLeft, AssetKind, AccountId>;
+
+parameter_types! {
+    pub const NativeAsset: AssetKind = NativeOrWithId::Native;
+    pub const RewardPalletId: PalletId = PalletId(*b"py/rewrd");
+    pub const SettlementPeriod: u64 = SETTLEMENT_PERIOD;
+    pub const DefaultPlatformFee: Permill = Permill::from_percent(10);
+    pub const MaxPlatformFee: Permill = Permill::from_percent(20);
+    pub const TreasuryAccount: AccountId = TREASURY;
+    pub const ValidatorFeePot: AccountId = VALIDATOR_POT;
+    pub const StakerFeePot: AccountId = STAKER_POT;
+    pub const CoordinatorBondRatio: Permill = Permill::from_percent(5);
+    pub const ImmediateRelease: Permill = Permill::from_percent(25);
+    pub const VestingPeriod: u64 = 100;
+    pub static VestingThreshold: Balance = Balance::MAX;
+}
+
+impl pallet_reward_distribution::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type Currency = Balances;
+    type AssetKind = AssetKind;
+    type Assets = NativeAndAssets;
+    type NativeAsset = NativeAsset;
+    type MaxProvidersPerBatch = frame_support::traits::ConstU32<16>;
+    type MinimumReward = frame_support::traits::ConstU64<10>;
+    type PalletId = RewardPalletId;
+    type SettlementPeriod = SettlementPeriod;
+    type ProofClaimPeriod = frame_support::traits::ConstU64<PROOF_CLAIM_PERIOD>;
+    type DefaultPlatformFee = DefaultPlatformFee;
+    type MaxPlatformFee = MaxPlatformFee;
+    type FeeOrigin = EnsureRoot<AccountId>;
+    type TreasuryAccount = TreasuryAccount;
+    type ValidatorFeePot = ValidatorFeePot;
+    type StakerFeePot = StakerFeePot;
+    type Providers = MockProviders;
+    type Tasks = MockTasks;
+    type TierMultiplier = UnitMultiplier;
+    type MaxQueuedBatches = frame_support::traits::ConstU32<8>;
+    type CoordinatorBondRatio = CoordinatorBondRatio;
+    type MinCoordinatorBond = frame_support::traits::ConstU64<10>;
+    type BatchChallengeBond = frame_support::traits::ConstU64<50>;
+    type ArbiterOrigin = EnsureRoot<AccountId>;
+    type ImmediateRelease = ImmediateRelease;
+    type VestingPeriod = VestingPeriod;
+    type VestingThreshold = VestingThreshold;
+    type MaxVestingSchedules = frame_support::traits::ConstU32<4>;
+    type Contributions = MockProviders;
+    type MaxStreamsPerTask = frame_support::traits::ConstU32<4>;
+    type MaxStreamsPerProvider = frame_support::traits::ConstU32<4>;
+    type Stats = MockProviders;
+}
+
+/// Every GPU tier earns the base reward
+pub struct UnitMultiplier;
+
+impl Convert<GpuTier, u32> for UnitMultiplier {
+    fn convert(_tier: GpuTier) -> u32 {
+        100
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockTask {
+    pub creator: AccountId,
+    pub providers: BTreeSet<AccountId>,
+    pub asset: AssetKind,
+    pub bounty: Balance,
+    pub running: bool,
+    pub escrowed: Option<Balance>,
+}
+
+thread_local! {
+    static TASKS: RefCell<BTreeMap<H256, MockTask>> = RefCell::new(BTreeMap::new());
+    static PAYEES: RefCell<BTreeMap<AccountId, RewardDestination<AccountId>>> = RefCell::new(BTreeMap::new());
+    static COMMISSIONS: RefCell<BTreeMap<AccountId, Perbill>> = RefCell::new(BTreeMap::new());
+    static AUTO_COMPOUND: RefCell<BTreeSet<AccountId>> = RefCell::new(BTreeSet::new());
+    static STAKED: RefCell<BTreeMap<AccountId, Balance>> = RefCell::new(BTreeMap::new());
+    static RESTAKE_FAILS: RefCell<bool> = RefCell::new(false);
+    static CONTRIBUTIONS: RefCell<BTreeSet<(H256, AccountId, u32)>> = RefCell::new(BTreeSet::new());
+    static SLASHES: RefCell<BTreeMap<AccountId, SlashIndex>> = RefCell::new(BTreeMap::new());
+    static SETTLED: RefCell<Vec<(AccountId, H256, u64, Balance)>> = RefCell::new(Vec::new());
+}
+
+pub fn task_id(n: u8) -> H256 {
+    H256::repeat_byte(n)
+}
+
+/// Add a running task whose providers have joined
+pub fn add_task(task: H256, creator: AccountId, bounty: Balance, providers: &[AccountId]) {
+    TASKS.with(|tasks| {
+        tasks.borrow_mut().insert(task, MockTask {
+            creator,
+            providers: providers.iter().copied().collect(),
+            asset: NativeOrWithId::Native,
+            bounty,
+            running: true,
+            escrowed: None,
+        })
+    });
+}
+
+/// Fund a task in `asset` instead of the native currency
+pub fn set_task_asset(task: H256, asset: AssetKind) {
+    TASKS.with(|tasks| tasks.borrow_mut().get_mut(&task).expect("task exists").asset = asset);
+}
+
+/// Complete a task, escrowing what is left of its bounty
+pub fn complete_task(task: H256) {
+    TASKS.with(|tasks| {
+        let mut tasks = tasks.borrow_mut();
+        let task = tasks.get_mut(&task).expect("task exists");
+        task.running = false;
+        task.escrowed = Some(task.bounty);
+    });
+}
+
+pub fn remove_provider(task: H256, who: AccountId) {
+    TASKS.with(|tasks| {
+        tasks.borrow_mut().get_mut(&task).expect("task exists").providers.remove(&who);
+    });
+}
+
+pub fn mock_task(task: H256) -> Option<MockTask> {
+    TASKS.with(|tasks| tasks.borrow().get(&task).cloned())
+}
+
+pub fn set_payee(who: AccountId, payee: RewardDestination<AccountId>) {
+    PAYEES.with(|payees| payees.borrow_mut().insert(who, payee));
+}
+
+pub fn set_commission(who: AccountId, commission: Perbill) {
+    COMMISSIONS.with(|commissions| commissions.borrow_mut().insert(who, commission));
+}
+
+pub fn set_auto_compound(who: AccountId) {
+    AUTO_COMPOUND.with(|set| set.borrow_mut().insert(who));
+}
+
+pub fn set_restake_fails(fails: bool) {
+    RESTAKE_FAILS.with(|flag| *flag.borrow_mut() = fails);
+}
+
+pub fn staked(who: AccountId) -> Balance {
+    STAKED.with(|staked| staked.borrow().get(&who).copied().unwrap_or(0))
+}
+
+pub fn contribute(task: H256, who: AccountId, round: u32) {
+    CONTRIBUTIONS.with(|set| set.borrow_mut().insert((task, who, round)));
+}
+
+pub fn slash(who: AccountId) {
+    SLASHES.with(|slashes| *slashes.borrow_mut().entry(who).or_insert(0) += 1);
+}
+
+pub fn settled_stats() -> Vec<(AccountId, H256, u64, Balance)> {
+    SETTLED.with(|settled| settled.borrow().clone())
+}
+
+fn stake(who: &AccountId, amount: Balance) {
+    STAKED.with(|staked| *staked.borrow_mut().entry(*who).or_insert(0) += amount);
+}
+
+/// Task registry backed by `TASKS`, paying bounties out of `TASK_POT`
+pub struct MockTasks;
+
+impl TaskEscrow<AccountId, H256, AssetKind, Balance> for MockTasks {
+    fn creator(task_id: &H256) -> Option<AccountId> {
+        mock_task(*task_id).map(|task| task.creator)
+    }
+
+    fn is_provider(task_id: &H256, who: &AccountId) -> bool {
+        mock_task(*task_id).is_some_and(|task| task.providers.contains(who))
+    }
+
+    fn is_running(task_id: &H256) -> bool {
+        mock_task(*task_id).is_some_and(|task| task.running)
+    }
+
+    fn escrowed(task_id: &H256) -> Option<(AssetKind, Balance)> {
+        mock_task(*task_id).and_then(|task| Some((task.asset, task.escrowed?)))
+    }
+
+    fn release(task_id: &H256, dest: &AccountId) -> Result<Balance, DispatchError> {
+        let (asset, amount) = TASKS
+            .with(|tasks| {
+                let mut tasks = tasks.borrow_mut();
+                let task = tasks.get_mut(task_id)?;
+                Some((task.asset.clone(), task.escrowed.take()?))
+            })
+            .ok_or(DispatchError::Other("nothing escrowed"))?;
+        <NativeAndAssets as fungibles::Mutate<AccountId>>::transfer(asset, &TASK_POT, dest, amount, Preservation::Preserve)?;
+        Ok(amount)
+    }
+
+    fn draw(task_id: &H256, dest: &AccountId, amount: Balance) -> Result<AssetKind, DispatchError> {
+        let task = mock_task(*task_id).ok_or(DispatchError::Other("unknown task"))?;
+        if !task.running || amount > task.bounty {
+            return Err(DispatchError::Other("cannot draw"));
+        }
+        <NativeAndAssets as fungibles::Mutate<AccountId>>::transfer(
+            task.asset.clone(),
+            &TASK_POT,
+            dest,
+            amount,
+            Preservation::Preserve,
+        )?;
+        TASKS.with(|tasks| tasks.borrow_mut().get_mut(task_id).expect("task exists").bounty -= amount);
+        Ok(task.asset)
+    }
+}
+
+/// Provider settings, contributions and stats backed by thread-local state
+pub struct MockProviders;
+
+impl RewardSettings<AccountId, Balance> for MockProviders {
+    fn payee(who: &AccountId) -> RewardDestination<AccountId> {
+        PAYEES.with(|payees| payees.borrow().get(who).cloned()).unwrap_or(RewardDestination::Stash)
+    }
+
+    fn commission(who: &AccountId) -> Perbill {
+        COMMISSIONS.with(|commissions| commissions.borrow().get(who).copied()).unwrap_or(Perbill::one())
+    }
+
+    fn restake(who: &AccountId, amount: Balance) -> DispatchResult {
+        if RESTAKE_FAILS.with(|flag| *flag.borrow()) {
+            return Err(DispatchError::Other("cannot restake"));
+        }
+        stake(who, amount);
+        Ok(())
+    }
+
+    fn auto_compound(who: &AccountId) -> bool {
+        AUTO_COMPOUND.with(|set| set.borrow().contains(who))
+    }
+
+    fn compound(who: &AccountId, source: &AccountId, amount: Balance) -> DispatchResult {
+        <Balances as fungible::Mutate<AccountId>>::transfer(source, who, amount, Preservation::Preserve)?;
+        stake(who, amount);
+        Ok(())
+    }
+}
+
+impl ContributionInspect<AccountId, H256> for MockProviders {
+    fn contributed(task_id: &H256, who: &AccountId, round: u32) -> bool {
+        CONTRIBUTIONS.with(|set| set.borrow().contains(&(*task_id, *who, round)))
+    }
+
+    fn applied_slash_count(who: &AccountId) -> SlashIndex {
+        SLASHES.with(|slashes| slashes.borrow().get(who).copied().unwrap_or(0))
+    }
+}
+
+impl ProviderStats<AccountId, H256, Balance> for MockProviders {
+    fn on_reward_settled(who: &AccountId, task_id: &H256, gradients: u64, earned: Balance) {
+        SETTLED.with(|settled| settled.borrow_mut().push((*who, *task_id, gradients, earned)));
+    }
+
+    fn on_stream_paid(who: &AccountId, task_id: &H256, earned: Balance) {
+        SETTLED.with(|settled| settled.borrow_mut().push((*who, *task_id, 0, earned)));
+    }
+}
+
+pub fn escrow_account() -> AccountId {
+    RewardDistribution::account_id()
+}
+
+pub fn balance(who: AccountId) -> Balance {
+    <Balances as fungible::Inspect<AccountId>>::balance(&who)
+}
+
+pub fn asset_balance(who: AccountId) -> Balance {
+    <Assets as fungibles::Inspect<AccountId>>::balance(ASSET, &who)
+}
+
+pub fn held(who: AccountId) -> Balance {
+    <Balances as fungible::InspectHold<AccountId>>::total_balance_on_hold(&who)
+}
+
+/// Move to block `n`, running the settlement engine with unlimited weight on the way
+pub fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        System::set_block_number(System::block_number() + 1);
+        RewardDistribution::on_idle(System::block_number(), Weight::MAX);
+    }
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![
+            (CREATOR, INITIAL_BALANCE),
+            (ALICE, INITIAL_BALANCE),
+            (BOB, INITIAL_BALANCE),
+            (CHARLIE, INITIAL_BALANCE),
+            (COORDINATOR, INITIAL_BALANCE),
+            (TASK_POT, INITIAL_BALANCE),
+            (TREASURY, 1),
+            (VALIDATOR_POT, 1),
+            (STAKER_POT, 1),
+            // The escrow account keeps its existential deposit while paying out
+            (RewardDistribution::account_id(), 1),
+        ],
+        ..Default::default()
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    pallet_assets::GenesisConfig::<Test> {
+        assets: vec![(ASSET, TASK_POT, true, ASSET_MIN_BALANCE)],
+        accounts: vec![(ASSET, TASK_POT, INITIAL_BALANCE)],
+        ..Default::default()
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,922 @@
+use crate::{
+    merkle, migrations, mock::*, ContributionMetrics, Error, Event, FeeDestination, MerkleProof, PaymentStream,
+    PaymentStreams, ProviderReward,
+};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{
+        fungible::{self, NativeOrWithId},
+        fungibles, Get, Hooks, OnRuntimeUpgrade, StorageVersion,
+    },
+    weights::Weight,
+    BoundedVec,
+};
+use pallet_provider_staking::{GpuTier, OnProviderSlashed, RewardDestination};
+use pallet_task_registry::TaskHooks;
+use sp_core::H256;
+use sp_runtime::{traits::BlakeTwo256, DispatchError, Perbill, Permill};
+
+const NATIVE: AssetKind = NativeOrWithId::Native;
+
+fn reward(provider: AccountId, amount: Balance) -> ProviderReward<Test> {
+    ProviderReward { provider, amount, gradients_contributed: 1, quality_score: 1000, hardware_multiplier: 100 }
+}
+
+/// Complete a task funded with `bounty` and let its creator open the reward batch
+fn create_batch(n: u8, bounty: Balance, merkle_root: H256) -> H256 {
+    let task = task_id(n);
+    add_task(task, CREATOR, bounty, &[ALICE, BOB, CHARLIE]);
+    complete_task(task);
+    assert_ok!(RewardDistribution::create_batch(RuntimeOrigin::signed(CREATOR), task, merkle_root));
+    RewardDistribution::task_batches(task).expect("batch created")
+}
+
+/// Batch of a task with `rewards` allocated, settled by the engine
+fn settled_batch(n: u8, bounty: Balance, rewards: Vec<ProviderReward<Test>>) -> H256 {
+    let batch_id = create_batch(n, bounty, H256::zero());
+    assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, rewards));
+    run_to_block(System::block_number() + SETTLEMENT_PERIOD);
+    assert!(RewardDistribution::reward_batches(batch_id).unwrap().settled);
+    batch_id
+}
+
+fn metrics(provider: AccountId, gradients_contributed: u64, quality_score: u32) -> ContributionMetrics<AccountId> {
+    ContributionMetrics { provider, gradients_contributed, quality_score, gpu_tier: GpuTier::Consumer }
+}
+
+/// Run a migration, with its try-runtime checks when they are compiled in
+fn run_migration<M: OnRuntimeUpgrade>() {
+    #[cfg(feature = "try-runtime")]
+    let state = M::pre_upgrade().expect("pre-upgrade checks pass");
+    M::on_runtime_upgrade();
+    #[cfg(feature = "try-runtime")]
+    M::post_upgrade(state).expect("post-upgrade checks pass");
+}
+
+fn merkle_proof(leaves: &[H256], leaf_index: u32) -> MerkleProof<H256> {
+    let siblings = merkle::proof::<BlakeTwo256>(leaves, leaf_index).expect("leaf exists");
+    MerkleProof { leaf_index, siblings: BoundedVec::truncate_from(siblings) }
+}
+
+#[test]
+fn allocate_settle_claim_pays_out_exactly_the_bounty() {
+    new_test_ext().execute_with(|| {
+        let batch_id = create_batch(1, 1000, H256::zero());
+        assert_eq!(held(CREATOR), 50);
+        assert_eq!(balance(escrow_account()), 1001);
+
+        assert_ok!(RewardDistribution::submit_rewards(
+            RuntimeOrigin::signed(CREATOR),
+            batch_id,
+            vec![reward(ALICE, 600), reward(BOB, 400)],
+        ));
+        assert_eq!(RewardDistribution::pending_rewards(ALICE, NATIVE), 600);
+        assert_eq!(RewardDistribution::batch_allocated(batch_id), 1000);
+
+        // Allocations are not claimable before their batch settles
+        assert_noop!(
+            RewardDistribution::claim_rewards(RuntimeOrigin::signed(ALICE), NATIVE),
+            Error::<Test>::NoRewardsToClaim
+        );
+        assert_noop!(
+            RewardDistribution::settle_batch(RuntimeOrigin::root(), batch_id),
+            Error::<Test>::SettlementTooEarly
+        );
+
+        run_to_block(1 + SETTLEMENT_PERIOD);
+
+        assert!(RewardDistribution::reward_batches(batch_id).unwrap().settled);
+        assert_eq!(RewardDistribution::pending_rewards(ALICE, NATIVE), 0);
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, NATIVE), 540);
+        assert_eq!(RewardDistribution::claimable_rewards(BOB, NATIVE), 360);
+        assert_eq!(RewardDistribution::platform_fees_collected(NATIVE), 100);
+        assert_eq!(balance(TREASURY), 1 + 100);
+        assert_eq!(held(CREATOR), 0);
+        System::assert_has_event(Event::BatchSettled { batch_id, settled: 2, failed: 0, total_fee: 100 }.into());
+
+        assert_ok!(RewardDistribution::claim_rewards(RuntimeOrigin::signed(ALICE), NATIVE));
+        assert_ok!(RewardDistribution::claim_rewards(RuntimeOrigin::signed(BOB), NATIVE));
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 540);
+        assert_eq!(balance(BOB), INITIAL_BALANCE + 360);
+        System::assert_has_event(Event::RewardsClaimed { provider: ALICE, asset: NATIVE, amount: 540 }.into());
+        assert_noop!(
+            RewardDistribution::claim_rewards(RuntimeOrigin::signed(ALICE), NATIVE),
+            Error::<Test>::NoRewardsToClaim
+        );
+
+        // Everything funded was paid out, down to the existential deposit
+        assert_eq!(balance(escrow_account()), 1);
+        assert_eq!(RewardDistribution::total_rewards_distributed(NATIVE), 900);
+    });
+}
+
+#[test]
+fn allocations_cannot_exceed_the_bounty() {
+    new_test_ext().execute_with(|| {
+        let batch_id = create_batch(1, 1000, H256::zero());
+
+        assert_noop!(
+            RewardDistribution::submit_rewards(
+                RuntimeOrigin::signed(CREATOR),
+                batch_id,
+                vec![reward(ALICE, 600), reward(BOB, 401)],
+            ),
+            Error::<Test>::InvalidRewardAmount
+        );
+        assert_noop!(
+            RewardDistribution::submit_rewards(RuntimeOrigin::signed(ALICE), batch_id, vec![reward(ALICE, 600)]),
+            Error::<Test>::NotAuthorized
+        );
+
+        // The cap holds across submissions too
+        assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(ALICE, 600)]));
+        assert_noop!(
+            RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(BOB, 401)]),
+            Error::<Test>::InvalidRewardAmount
+        );
+        assert_noop!(
+            RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(ALICE, 100)]),
+            Error::<Test>::DuplicateReward
+        );
+        assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(BOB, 400)]));
+        assert_noop!(
+            RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(CHARLIE, 10)]),
+            Error::<Test>::InvalidRewardAmount
+        );
+        assert_eq!(RewardDistribution::batch_allocated(batch_id), 1000);
+    });
+}
+
+#[test]
+fn proof_claims_are_capped_by_the_bounty() {
+    new_test_ext().execute_with(|| {
+        // A bad root committing more than the bounty
+        let leaves = vec![
+            merkle::leaf::<BlakeTwo256, _, _>(0, &ALICE, &700u64),
+            merkle::leaf::<BlakeTwo256, _, _>(1, &BOB, &400u64),
+        ];
+        let batch_id = create_batch(1, 1000, merkle::root::<BlakeTwo256>(&leaves));
+
+        assert_noop!(
+            RewardDistribution::claim_with_proof(RuntimeOrigin::signed(ALICE), batch_id, 700, merkle_proof(&leaves, 0)),
+            Error::<Test>::SettlementTooEarly
+        );
+        System::set_block_number(1 + SETTLEMENT_PERIOD);
+
+        assert_ok!(RewardDistribution::claim_with_proof(
+            RuntimeOrigin::signed(ALICE),
+            batch_id,
+            700,
+            merkle_proof(&leaves, 0),
+        ));
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 630);
+        assert_eq!(balance(TREASURY), 1 + 70);
+        assert_eq!(RewardDistribution::claimed_by_proof(batch_id), 700);
+        System::assert_has_event(
+            Event::RewardClaimedWithProof { batch_id, provider: ALICE, leaf_index: 0, amount: 700 }.into(),
+        );
+
+        assert_noop!(
+            RewardDistribution::claim_with_proof(RuntimeOrigin::signed(ALICE), batch_id, 700, merkle_proof(&leaves, 0)),
+            Error::<Test>::AlreadyClaimed
+        );
+        assert_noop!(
+            RewardDistribution::claim_with_proof(RuntimeOrigin::signed(BOB), batch_id, 300, merkle_proof(&leaves, 1)),
+            Error::<Test>::InvalidProof
+        );
+        assert_noop!(
+            RewardDistribution::claim_with_proof(RuntimeOrigin::signed(BOB), batch_id, 400, merkle_proof(&leaves, 1)),
+            Error::<Test>::InvalidRewardAmount
+        );
+        assert_noop!(
+            RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(BOB, 300)]),
+            Error::<Test>::ProofClaimsStarted
+        );
+
+        // Escrow keeps what no valid claim can take until the claim period is over
+        assert_eq!(balance(escrow_account()), 1 + 300);
+        assert_noop!(
+            RewardDistribution::refund_unclaimed(RuntimeOrigin::signed(BOB), batch_id),
+            Error::<Test>::SettlementTooEarly
+        );
+        run_to_block(2 + SETTLEMENT_PERIOD);
+        assert_noop!(
+            RewardDistribution::refund_unclaimed(RuntimeOrigin::signed(BOB), batch_id),
+            Error::<Test>::ClaimPeriodNotOver
+        );
+
+        run_to_block(1 + SETTLEMENT_PERIOD + PROOF_CLAIM_PERIOD);
+        assert_noop!(
+            RewardDistribution::claim_with_proof(RuntimeOrigin::signed(BOB), batch_id, 400, merkle_proof(&leaves, 1)),
+            Error::<Test>::ClaimPeriodOver
+        );
+        let creator_balance = balance(CREATOR);
+        assert_ok!(RewardDistribution::refund_unclaimed(RuntimeOrigin::signed(BOB), batch_id));
+        System::assert_last_event(Event::BountyRefunded { batch_id, creator: CREATOR, amount: 300 }.into());
+        assert_eq!(balance(CREATOR), creator_balance + 300);
+        assert_eq!(balance(escrow_account()), 1);
+        assert_noop!(
+            RewardDistribution::refund_unclaimed(RuntimeOrigin::signed(BOB), batch_id),
+            Error::<Test>::NothingToRefund
+        );
+    });
+}
+
+#[test]
+fn unallocated_bounty_is_refunded_at_settlement() {
+    new_test_ext().execute_with(|| {
+        let batch_id = create_batch(1, 1000, H256::zero());
+        assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(ALICE, 600)]));
+        let creator_balance = balance(CREATOR);
+
+        run_to_block(1 + SETTLEMENT_PERIOD);
+
+        System::assert_has_event(Event::BountyRefunded { batch_id, creator: CREATOR, amount: 400 }.into());
+        assert_eq!(RewardDistribution::refunded_bounty(batch_id), 400);
+        // The coordinator bond is released as well
+        assert_eq!(held(CREATOR), 0);
+        assert_eq!(balance(CREATOR), creator_balance + 50 + 400);
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, NATIVE), 540);
+        assert_eq!(balance(escrow_account()), 1 + 540);
+        assert_noop!(
+            RewardDistribution::refund_unclaimed(RuntimeOrigin::signed(CREATOR), batch_id),
+            Error::<Test>::NotAMerkleBatch
+        );
+    });
+}
+
+#[test]
+fn delegator_share_is_paid_to_the_payee() {
+    new_test_ext().execute_with(|| {
+        set_commission(ALICE, Perbill::from_percent(50));
+        set_payee(ALICE, RewardDestination::Account(CHARLIE));
+        settled_batch(1, 1000, vec![reward(ALICE, 1000)]);
+
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, NATIVE), 450);
+        assert_eq!(RewardDistribution::delegator_rewards(ALICE, NATIVE), 450);
+
+        assert_ok!(RewardDistribution::claim_delegator_rewards(RuntimeOrigin::signed(ALICE), NATIVE));
+        System::assert_has_event(Event::DelegatorRewardsClaimed { provider: ALICE, asset: NATIVE, amount: 450 }.into());
+        assert_eq!(balance(CHARLIE), INITIAL_BALANCE + 450);
+        assert_eq!(balance(ALICE), INITIAL_BALANCE);
+        assert_eq!(RewardDistribution::total_rewards_distributed(NATIVE), 450);
+        assert_noop!(
+            RewardDistribution::claim_delegator_rewards(RuntimeOrigin::signed(ALICE), NATIVE),
+            Error::<Test>::NoRewardsToClaim
+        );
+    });
+}
+
+#[test]
+fn delegator_share_vests_and_compounds_like_the_commission() {
+    new_test_ext().execute_with(|| {
+        VestingThreshold::set(300);
+        set_commission(ALICE, Perbill::from_percent(50));
+        set_commission(BOB, Perbill::from_percent(50));
+        set_auto_compound(BOB);
+        settled_batch(1, 1000, vec![reward(ALICE, 800), reward(BOB, 200)]);
+        assert_eq!(staked(BOB), 90);
+
+        assert_ok!(RewardDistribution::claim_delegator_rewards(RuntimeOrigin::signed(ALICE), NATIVE));
+        System::assert_has_event(Event::RewardVesting { provider: ALICE, immediate: 90, locked: 270 }.into());
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 90);
+        assert_eq!(RewardDistribution::vesting(ALICE).len(), 1);
+
+        assert_ok!(RewardDistribution::claim_delegator_rewards(RuntimeOrigin::signed(BOB), NATIVE));
+        assert_eq!(staked(BOB), 180);
+        assert_eq!(balance(BOB), INITIAL_BALANCE + 180);
+    });
+}
+
+#[test]
+fn computed_rewards_add_up_to_the_bounty() {
+    new_test_ext().execute_with(|| {
+        let even = vec![metrics(ALICE, 1, 1000), metrics(BOB, 1, 1000), metrics(CHARLIE, 1, 1000)];
+        assert_eq!(RewardDistribution::compute_rewards(1000, 10, &even), Ok(vec![334, 333, 333]));
+
+        // On a tie the rounding dust goes to the first provider listed, here CHARLIE
+        let reversed: Vec<_> = even.into_iter().rev().collect();
+        assert_eq!(RewardDistribution::compute_rewards(1000, 10, &reversed), Ok(vec![334, 333, 333]));
+
+        let uneven = vec![metrics(ALICE, 3, 1000), metrics(BOB, 5, 700), metrics(CHARLIE, 2, 1000)];
+        let amounts = RewardDistribution::compute_rewards(997, 10, &uneven).unwrap();
+        assert_eq!(amounts.iter().sum::<Balance>(), 997);
+        assert!(amounts[1] > amounts[0] && amounts[0] > amounts[2]);
+        assert_eq!(RewardDistribution::compute_rewards(997, 10, &uneven), Ok(amounts));
+
+        assert_noop!(
+            RewardDistribution::compute_rewards(1000, 10, &[metrics(ALICE, 0, 1000)]),
+            Error::<Test>::NoContribution
+        );
+    });
+}
+
+#[test]
+fn rewards_below_the_minimum_go_to_the_largest_contributor() {
+    new_test_ext().execute_with(|| {
+        let batch_metrics = vec![metrics(ALICE, 99, 1000), metrics(BOB, 1, 500)];
+        assert_eq!(RewardDistribution::compute_rewards(1000, 10, &batch_metrics), Ok(vec![1000, 0]));
+        assert_noop!(
+            RewardDistribution::compute_rewards(5, 10, &batch_metrics),
+            Error::<Test>::InvalidRewardAmount
+        );
+
+        let batch_id = create_batch(1, 1000, H256::zero());
+        assert_ok!(RewardDistribution::submit_metrics(RuntimeOrigin::signed(CREATOR), batch_id, batch_metrics));
+        assert_eq!(RewardDistribution::pending_rewards(ALICE, NATIVE), 1000);
+        assert_eq!(RewardDistribution::pending_rewards(BOB, NATIVE), 0);
+        assert_eq!(RewardDistribution::batch_allocated(batch_id), 1000);
+        let recorded = RewardDistribution::batch_metrics(batch_id).expect("metrics recorded");
+        assert_eq!((recorded.total_gradients, recorded.avg_quality_score, recorded.participants), (100, 750, 2));
+        assert_noop!(
+            RewardDistribution::submit_metrics(RuntimeOrigin::signed(CREATOR), batch_id, vec![metrics(BOB, 1, 1000)]),
+            Error::<Test>::RewardsAlreadySubmitted
+        );
+    });
+}
+
+#[test]
+fn settlement_engine_resumes_from_its_cursor() {
+    new_test_ext().execute_with(|| {
+        let batch_id = create_batch(1, 1000, H256::zero());
+        assert_ok!(RewardDistribution::submit_rewards(
+            RuntimeOrigin::signed(CREATOR),
+            batch_id,
+            vec![reward(ALICE, 300), reward(BOB, 300), reward(CHARLIE, 300)],
+        ));
+        System::set_block_number(1 + SETTLEMENT_PERIOD);
+        let now = System::block_number();
+        let db = <Test as frame_system::Config>::DbWeight::get();
+
+        // Too little weight for a batch leaves it alone
+        assert_eq!(RewardDistribution::on_idle(now, db.reads(2)), Weight::zero());
+        assert!(RewardDistribution::current_settlement().is_none());
+
+        // Enough weight for one reward per block
+        let one_reward = db.reads_writes(33, 25);
+        RewardDistribution::on_idle(now, one_reward);
+        let cursor = RewardDistribution::current_settlement().expect("batch in progress");
+        assert_eq!((cursor.batch_id, cursor.settled, cursor.failed), (batch_id, 1, 0));
+        System::assert_has_event(Event::SettlementProgress { batch_id, settled: 1, failed: 0 }.into());
+        assert_noop!(
+            RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(COORDINATOR, 10)]),
+            Error::<Test>::SettlementInProgress
+        );
+
+        RewardDistribution::on_idle(now, one_reward);
+        assert_eq!(RewardDistribution::current_settlement().expect("batch in progress").settled, 2);
+
+        run_to_block(now + 1);
+        assert!(RewardDistribution::current_settlement().is_none());
+        assert!(RewardDistribution::settlement_queue().is_empty());
+        System::assert_has_event(Event::BatchSettled { batch_id, settled: 3, failed: 0, total_fee: 90 }.into());
+        for provider in [ALICE, BOB, CHARLIE] {
+            assert_eq!(RewardDistribution::claimable_rewards(provider, NATIVE), 270);
+        }
+    });
+}
+
+#[test]
+fn upheld_challenge_refunds_the_bounty_and_awards_the_coordinator_bond() {
+    new_test_ext().execute_with(|| {
+        let batch_id = create_batch(1, 1000, H256::zero());
+        assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(ALICE, 600)]));
+
+        assert_noop!(
+            RewardDistribution::challenge_batch(RuntimeOrigin::signed(COORDINATOR), batch_id, b"cid".to_vec()),
+            Error::<Test>::NotAParticipant
+        );
+        assert_ok!(RewardDistribution::challenge_batch(RuntimeOrigin::signed(BOB), batch_id, b"cid".to_vec()));
+        assert_eq!(held(BOB), 50);
+        assert_noop!(
+            RewardDistribution::challenge_batch(RuntimeOrigin::signed(CHARLIE), batch_id, b"cid".to_vec()),
+            Error::<Test>::AlreadyChallenged
+        );
+
+        // The engine leaves a challenged batch alone
+        run_to_block(2 + SETTLEMENT_PERIOD);
+        assert!(!RewardDistribution::reward_batches(batch_id).unwrap().settled);
+
+        assert_ok!(RewardDistribution::resolve_challenge(RuntimeOrigin::root(), batch_id, true));
+        System::assert_has_event(Event::ChallengeUpheld { batch_id, refunded_to: CREATOR, amount: 1000 }.into());
+
+        assert!(RewardDistribution::reward_batches(batch_id).is_none());
+        assert_eq!(RewardDistribution::pending_rewards(ALICE, NATIVE), 0);
+        assert_eq!(held(BOB), 0);
+        assert_eq!(balance(BOB), INITIAL_BALANCE + 50);
+        assert_eq!(held(CREATOR), 0);
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE - 50 + 1000);
+        assert_eq!(balance(escrow_account()), 1);
+    });
+}
+
+#[test]
+fn rejected_challenge_pays_the_challenge_bond_to_the_coordinator() {
+    new_test_ext().execute_with(|| {
+        let batch_id = create_batch(1, 1000, H256::zero());
+        assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(ALICE, 600)]));
+        assert_ok!(RewardDistribution::challenge_batch(RuntimeOrigin::signed(CHARLIE), batch_id, b"cid".to_vec()));
+
+        assert_ok!(RewardDistribution::resolve_challenge(RuntimeOrigin::root(), batch_id, false));
+        System::assert_has_event(Event::ChallengeRejected { batch_id }.into());
+        assert_eq!(balance(CHARLIE), INITIAL_BALANCE - 50);
+        assert_eq!(held(CHARLIE), 0);
+
+        // Settlement resumes; the coordinator gets its own bond back and the unallocated bounty
+        run_to_block(1 + SETTLEMENT_PERIOD);
+        assert!(RewardDistribution::reward_batches(batch_id).unwrap().settled);
+        assert_eq!(held(CREATOR), 0);
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE + 50 + 400);
+        assert_noop!(
+            RewardDistribution::challenge_batch(RuntimeOrigin::signed(BOB), batch_id, b"cid".to_vec()),
+            Error::<Test>::ChallengeWindowClosed
+        );
+    });
+}
+
+#[test]
+fn only_the_creator_or_its_delegate_coordinates_a_batch() {
+    new_test_ext().execute_with(|| {
+        let task = task_id(1);
+        add_task(task, CREATOR, 1000, &[ALICE, BOB, CHARLIE]);
+        complete_task(task);
+
+        assert_noop!(
+            RewardDistribution::create_batch(RuntimeOrigin::signed(COORDINATOR), task, H256::zero()),
+            Error::<Test>::NotAuthorized
+        );
+        assert_noop!(
+            RewardDistribution::delegate_coordinator(RuntimeOrigin::signed(ALICE), task, Some(ALICE)),
+            Error::<Test>::NotAuthorized
+        );
+        assert_noop!(
+            RewardDistribution::delegate_coordinator(RuntimeOrigin::signed(CREATOR), task_id(9), Some(COORDINATOR)),
+            Error::<Test>::TaskNotFound
+        );
+
+        // A revoked delegation no longer authorises
+        assert_ok!(RewardDistribution::delegate_coordinator(RuntimeOrigin::signed(CREATOR), task, Some(COORDINATOR)));
+        assert_ok!(RewardDistribution::delegate_coordinator(RuntimeOrigin::signed(CREATOR), task, None));
+        System::assert_last_event(Event::CoordinatorDelegated { task_id: task, coordinator: None }.into());
+        assert_noop!(
+            RewardDistribution::create_batch(RuntimeOrigin::signed(COORDINATOR), task, H256::zero()),
+            Error::<Test>::NotAuthorized
+        );
+
+        assert_ok!(RewardDistribution::delegate_coordinator(RuntimeOrigin::signed(CREATOR), task, Some(COORDINATOR)));
+        System::assert_last_event(Event::CoordinatorDelegated { task_id: task, coordinator: Some(COORDINATOR) }.into());
+        assert_ok!(RewardDistribution::create_batch(RuntimeOrigin::signed(COORDINATOR), task, H256::zero()));
+        let batch_id = RewardDistribution::task_batches(task).expect("batch created");
+
+        // The delegate bonds its share of the bounty and only it can submit rewards
+        assert_eq!(held(COORDINATOR), 50);
+        assert_eq!(RewardDistribution::coordinator_bonds(batch_id), Some((COORDINATOR, 50)));
+        assert!(RewardDistribution::delegated_coordinators(task).is_none());
+        assert_noop!(
+            RewardDistribution::delegate_coordinator(RuntimeOrigin::signed(CREATOR), task, Some(ALICE)),
+            Error::<Test>::TaskNotPayable
+        );
+        assert_noop!(
+            RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(ALICE, 1000)]),
+            Error::<Test>::NotAuthorized
+        );
+        assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(COORDINATOR), batch_id, vec![reward(ALICE, 1000)]));
+
+        run_to_block(1 + SETTLEMENT_PERIOD);
+        assert!(RewardDistribution::coordinator_bonds(batch_id).is_none());
+        assert_eq!(held(COORDINATOR), 0);
+        assert_eq!(balance(COORDINATOR), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn small_bounties_bond_the_minimum() {
+    new_test_ext().execute_with(|| {
+        let batch_id = create_batch(1, 100, H256::zero());
+        assert_eq!(RewardDistribution::coordinator_bonds(batch_id), Some((CREATOR, 10)));
+        assert_eq!(held(CREATOR), 10);
+    });
+}
+
+#[test]
+fn large_claims_vest_linearly() {
+    new_test_ext().execute_with(|| {
+        VestingThreshold::set(500);
+        settled_batch(1, 1000, vec![reward(ALICE, 1000)]);
+        let claimed_at = System::block_number();
+
+        assert_ok!(RewardDistribution::claim_rewards(RuntimeOrigin::signed(ALICE), NATIVE));
+        System::assert_has_event(Event::RewardVesting { provider: ALICE, immediate: 225, locked: 675 }.into());
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 225);
+        assert_noop!(RewardDistribution::vest(RuntimeOrigin::signed(ALICE)), Error::<Test>::NothingToVest);
+
+        run_to_block(claimed_at + 50);
+        assert_ok!(RewardDistribution::vest(RuntimeOrigin::signed(ALICE)));
+        System::assert_has_event(Event::Vested { provider: ALICE, amount: 337 }.into());
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 225 + 337);
+
+        run_to_block(claimed_at + 100);
+        assert_ok!(RewardDistribution::vest(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 900);
+        assert!(RewardDistribution::vesting(ALICE).is_empty());
+        assert_eq!(balance(escrow_account()), 1);
+    });
+}
+
+#[test]
+fn split_fees_reach_every_pot() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            RewardDistribution::set_fee_destination(
+                RuntimeOrigin::root(),
+                FeeDestination::Split { validators: Permill::from_percent(60), stakers: Permill::from_percent(50) },
+            ),
+            Error::<Test>::InvalidFeeSplit
+        );
+        assert_ok!(RewardDistribution::set_fee_destination(
+            RuntimeOrigin::root(),
+            FeeDestination::Split { validators: Permill::from_percent(50), stakers: Permill::from_percent(30) },
+        ));
+
+        settled_batch(1, 1000, vec![reward(ALICE, 1000)]);
+
+        assert_eq!(balance(VALIDATOR_POT), 1 + 50);
+        assert_eq!(balance(STAKER_POT), 1 + 30);
+        assert_eq!(balance(TREASURY), 1 + 20);
+        assert_eq!(RewardDistribution::platform_fees_collected(NATIVE), 100);
+    });
+}
+
+#[test]
+fn burned_fees_leave_circulation() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(RewardDistribution::set_fee_destination(RuntimeOrigin::root(), FeeDestination::Burn));
+        let issuance = <Balances as fungible::Inspect<AccountId>>::total_issuance();
+
+        settled_batch(1, 1000, vec![reward(ALICE, 1000)]);
+
+        assert_eq!(<Balances as fungible::Inspect<AccountId>>::total_issuance(), issuance - 100);
+        assert_eq!(balance(TREASURY), 1);
+    });
+}
+
+#[test]
+fn unroutable_fees_are_kept_for_retry() {
+    new_test_ext().execute_with(|| {
+        let asset = NativeOrWithId::WithId(ASSET);
+        let task = task_id(1);
+        add_task(task, CREATOR, 50, &[ALICE]);
+        set_task_asset(task, asset.clone());
+        complete_task(task);
+        assert_ok!(RewardDistribution::create_batch(RuntimeOrigin::signed(CREATOR), task, H256::zero()));
+        let batch_id = RewardDistribution::task_batches(task).unwrap();
+        assert_ok!(RewardDistribution::submit_rewards(RuntimeOrigin::signed(CREATOR), batch_id, vec![reward(ALICE, 50)]));
+
+        // The treasury holds none of the asset and the fee is below its minimum balance
+        run_to_block(1 + SETTLEMENT_PERIOD);
+        assert!(RewardDistribution::reward_batches(batch_id).unwrap().settled);
+        assert!(System::events().iter().any(|record| matches!(
+            record.event,
+            RuntimeEvent::RewardDistribution(Event::FeeRoutingFailed { amount: 5, .. })
+        )));
+        assert_eq!(RewardDistribution::unrouted_fees(&asset), 5);
+        assert_eq!(RewardDistribution::platform_fees_collected(&asset), 0);
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, &asset), 45);
+        assert_eq!(held(CREATOR), 0);
+
+        assert!(RewardDistribution::route_unrouted_fees(RuntimeOrigin::signed(BOB), asset.clone()).is_err());
+        assert_eq!(RewardDistribution::unrouted_fees(&asset), 5);
+
+        assert_ok!(<Assets as fungibles::Mutate<AccountId>>::mint_into(ASSET, &TREASURY, ASSET_MIN_BALANCE));
+        assert_ok!(RewardDistribution::route_unrouted_fees(RuntimeOrigin::signed(BOB), asset.clone()));
+        assert_eq!(RewardDistribution::unrouted_fees(&asset), 0);
+        assert_eq!(RewardDistribution::platform_fees_collected(&asset), 5);
+        assert_eq!(asset_balance(TREASURY), ASSET_MIN_BALANCE + 5);
+        assert_noop!(
+            RewardDistribution::route_unrouted_fees(RuntimeOrigin::signed(BOB), asset),
+            Error::<Test>::NoUnroutedFees
+        );
+    });
+}
+
+#[test]
+fn asset_batches_settle_and_pay_out_in_their_asset() {
+    new_test_ext().execute_with(|| {
+        let asset = NativeOrWithId::WithId(ASSET);
+        let task = task_id(1);
+        add_task(task, CREATOR, 1000, &[ALICE, BOB]);
+        set_task_asset(task, asset.clone());
+        complete_task(task);
+        assert_ok!(RewardDistribution::create_batch(RuntimeOrigin::signed(CREATOR), task, H256::zero()));
+        let batch_id = RewardDistribution::task_batches(task).unwrap();
+
+        // Bonds are native, so an asset bounty bonds the minimum
+        assert_eq!(held(CREATOR), 10);
+        assert_eq!(asset_balance(escrow_account()), 1000);
+        assert_noop!(
+            RewardDistribution::submit_rewards(
+                RuntimeOrigin::signed(CREATOR),
+                batch_id,
+                vec![reward(ALICE, ASSET_MIN_BALANCE - 1)],
+            ),
+            Error::<Test>::InvalidRewardAmount
+        );
+
+        VestingThreshold::set(100);
+        set_auto_compound(BOB);
+        assert_ok!(RewardDistribution::submit_rewards(
+            RuntimeOrigin::signed(CREATOR),
+            batch_id,
+            vec![reward(ALICE, 600), reward(BOB, 400)],
+        ));
+        run_to_block(1 + SETTLEMENT_PERIOD);
+
+        assert_eq!(asset_balance(TREASURY), 100);
+        assert_eq!(RewardDistribution::platform_fees_collected(&asset), 100);
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, &asset), 540);
+        // Only native rewards are compounded or vest
+        assert_eq!(RewardDistribution::claimable_rewards(BOB, &asset), 360);
+        assert_eq!(staked(BOB), 0);
+
+        assert_ok!(RewardDistribution::claim_rewards(RuntimeOrigin::signed(ALICE), asset.clone()));
+        assert_eq!(asset_balance(ALICE), 540);
+        assert!(RewardDistribution::vesting(ALICE).is_empty());
+
+        // The last claim may empty the escrow account of the asset
+        assert_ok!(RewardDistribution::claim_rewards(RuntimeOrigin::signed(BOB), asset.clone()));
+        assert_eq!(asset_balance(BOB), 360);
+        assert_eq!(asset_balance(escrow_account()), 0);
+        assert_eq!(RewardDistribution::total_rewards_distributed(&asset), 900);
+        assert_eq!(RewardDistribution::total_rewards_distributed(NATIVE), 0);
+
+        // Work is counted, but earnings in an asset are not
+        assert!(settled_stats().iter().all(|(_, _, gradients, earned)| (*gradients, *earned) == (1, 0)));
+    });
+}
+
+#[test]
+fn settled_rewards_and_stream_payments_update_provider_stats() {
+    new_test_ext().execute_with(|| {
+        let alice = ProviderReward { gradients_contributed: 7, ..reward(ALICE, 600) };
+        settled_batch(1, 1000, vec![alice, reward(BOB, 400)]);
+
+        let mut settled = settled_stats();
+        settled.sort();
+        assert_eq!(settled, vec![(ALICE, task_id(1), 7, 600), (BOB, task_id(1), 1, 400)]);
+
+        // Stream payments count their gross amount but no gradients
+        let task = task_id(2);
+        add_task(task, CREATOR, 1000, &[ALICE]);
+        assert_ok!(RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, ALICE, 100, 2));
+        contribute(task, ALICE, 1);
+        assert_ok!(RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 1));
+        assert_eq!(settled_stats().last(), Some(&(ALICE, task, 0, 100)));
+    });
+}
+
+#[test]
+fn streams_pay_contributed_rounds_and_refund_when_the_provider_leaves() {
+    new_test_ext().execute_with(|| {
+        let task = task_id(1);
+        add_task(task, CREATOR, 1000, &[ALICE, BOB]);
+
+        assert_noop!(
+            RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, CHARLIE, 100, 3),
+            Error::<Test>::ProviderNotInTask
+        );
+        assert_noop!(
+            RewardDistribution::open_stream(RuntimeOrigin::signed(ALICE), task, ALICE, 100, 3),
+            Error::<Test>::NotAuthorized
+        );
+        assert_ok!(RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, ALICE, 100, 3));
+        assert_eq!(balance(escrow_account()), 1 + 300);
+        assert_eq!(mock_task(task).unwrap().bounty, 700);
+        assert_eq!(RewardDistribution::provider_streams(ALICE).to_vec(), vec![task]);
+
+        contribute(task, ALICE, 1);
+        assert_noop!(
+            RewardDistribution::close_round(RuntimeOrigin::signed(ALICE), task, 1),
+            Error::<Test>::NotAuthorized
+        );
+        assert_ok!(RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 1));
+        System::assert_has_event(Event::StreamPaid { task_id: task, provider: ALICE, round: 1, amount: 90 }.into());
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, NATIVE), 90);
+        assert_eq!(balance(TREASURY), 1 + 10);
+        assert_noop!(
+            RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 1),
+            Error::<Test>::RoundAlreadyClosed
+        );
+
+        // A round without a gradient pays nothing
+        assert_ok!(RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 2));
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, NATIVE), 90);
+
+        // The creator cannot take back a live stream, but it ends when the provider leaves
+        assert_noop!(
+            RewardDistribution::stop_stream(RuntimeOrigin::signed(CREATOR), task, ALICE),
+            Error::<Test>::StreamActive
+        );
+        remove_provider(task, ALICE);
+        assert_ok!(<RewardDistribution as TaskHooks<_, _>>::on_provider_left(&task, &ALICE));
+        System::assert_has_event(Event::StreamStopped { task_id: task, provider: ALICE, refunded: 200 }.into());
+        assert!(RewardDistribution::payment_streams(task, ALICE).is_none());
+        assert!(RewardDistribution::provider_streams(ALICE).is_empty());
+        assert_eq!(RewardDistribution::stream_count(task), 0);
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE + 200);
+        assert_eq!(balance(escrow_account()), 1 + 90);
+    });
+}
+
+#[test]
+fn slashes_and_task_completion_end_streams() {
+    new_test_ext().execute_with(|| {
+        let task = task_id(1);
+        add_task(task, CREATOR, 1000, &[ALICE, BOB]);
+        assert_ok!(RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, ALICE, 100, 2));
+        assert_ok!(RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, BOB, 100, 2));
+        assert_ok!(RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 1));
+
+        slash(ALICE);
+        <RewardDistribution as OnProviderSlashed<_>>::on_provider_slashed(&ALICE);
+        assert!(RewardDistribution::payment_streams(task, ALICE).is_none());
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE + 200);
+
+        complete_task(task);
+        assert_noop!(
+            RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 2),
+            Error::<Test>::TaskNotRunning
+        );
+        assert_noop!(
+            RewardDistribution::stop_stream(RuntimeOrigin::signed(ALICE), task, BOB),
+            Error::<Test>::StreamActive
+        );
+
+        // The creator can stop streams of a task that stopped running
+        assert_ok!(RewardDistribution::stop_stream(RuntimeOrigin::signed(CREATOR), task, BOB));
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE + 400);
+
+        assert_ok!(<RewardDistribution as TaskHooks<_, _>>::on_task_completed(&task));
+        assert_eq!(RewardDistribution::stream_count(task), 0);
+        assert!(RewardDistribution::last_closed_round(task).is_none());
+        assert_eq!(balance(escrow_account()), 1);
+    });
+}
+
+#[test]
+fn task_completion_refunds_open_streams() {
+    new_test_ext().execute_with(|| {
+        let task = task_id(1);
+        add_task(task, CREATOR, 1000, &[ALICE, BOB]);
+        assert_ok!(RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, ALICE, 100, 2));
+        assert_ok!(RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, BOB, 50, 4));
+
+        complete_task(task);
+        assert_ok!(<RewardDistribution as TaskHooks<_, _>>::on_task_completed(&task));
+
+        assert_eq!(PaymentStreams::<Test>::iter_prefix(task).count(), 0);
+        assert!(RewardDistribution::provider_streams(ALICE).is_empty());
+        assert!(RewardDistribution::provider_streams(BOB).is_empty());
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE + 400);
+    });
+}
+
+#[test]
+fn auto_compounding_providers_stake_their_rewards() {
+    new_test_ext().execute_with(|| {
+        set_auto_compound(ALICE);
+        set_payee(BOB, RewardDestination::Staked);
+
+        settled_batch(1, 1000, vec![reward(ALICE, 600), reward(BOB, 400)]);
+
+        // Compounded at settlement, nothing left to claim
+        assert_eq!(staked(ALICE), 540);
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 540);
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, NATIVE), 0);
+
+        // Restaked when claimed
+        assert_ok!(RewardDistribution::claim_rewards(RuntimeOrigin::signed(BOB), NATIVE));
+        assert_eq!(staked(BOB), 360);
+        assert_eq!(RewardDistribution::total_rewards_distributed(NATIVE), 900);
+        assert_eq!(balance(escrow_account()), 1);
+    });
+}
+
+#[test]
+fn rewards_that_cannot_be_restaked_stay_free() {
+    new_test_ext().execute_with(|| {
+        set_payee(ALICE, RewardDestination::Staked);
+        set_restake_fails(true);
+        settled_batch(1, 1000, vec![reward(ALICE, 600)]);
+
+        assert_ok!(RewardDistribution::claim_rewards(RuntimeOrigin::signed(ALICE), NATIVE));
+        System::assert_has_event(
+            Event::RestakeFailed { provider: ALICE, amount: 540, error: DispatchError::Other("cannot restake") }.into(),
+        );
+        assert_eq!(balance(ALICE), INITIAL_BALANCE + 540);
+        assert_eq!(staked(ALICE), 0);
+    });
+}
+
+fn old_batch(created_at: u64, settled: bool) -> migrations::v1::OldRewardBatch<Test> {
+    migrations::v1::OldRewardBatch {
+        task_id: H256::zero(),
+        total_bounty: 100,
+        coordinator: CREATOR,
+        created_at,
+        settled,
+        merkle_root: H256::zero(),
+    }
+}
+
+#[test]
+fn migration_to_v1_queues_unsettled_batches_oldest_first() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<RewardDistribution>();
+        migrations::v1::RewardBatches::<Test>::insert(task_id(1), old_batch(5, false));
+        migrations::v1::RewardBatches::<Test>::insert(task_id(2), old_batch(2, false));
+        migrations::v1::RewardBatches::<Test>::insert(task_id(3), old_batch(1, true));
+
+        run_migration::<migrations::v1::MigrateToV1<Test>>();
+
+        assert_eq!(RewardDistribution::settlement_queue().to_vec(), vec![task_id(2), task_id(1)]);
+        assert_eq!(StorageVersion::get::<RewardDistribution>(), StorageVersion::new(1));
+    });
+}
+
+#[test]
+fn migration_to_v2_moves_ledgers_to_the_native_asset() {
+    new_test_ext().execute_with(|| {
+        use migrations::v2::old;
+
+        StorageVersion::new(1).put::<RewardDistribution>();
+        migrations::v1::RewardBatches::<Test>::insert(task_id(1), old_batch(1, false));
+        old::PendingRewards::<Test>::insert(ALICE, 100);
+        old::DelegatorRewards::<Test>::insert(BOB, 20);
+        old::ClaimableRewards::<Test>::insert(ALICE, 30);
+        old::TotalRewardsDistributed::<Test>::put(500);
+        old::PlatformFeesCollected::<Test>::put(50);
+
+        run_migration::<migrations::v2::MigrateToV2<Test>>();
+
+        let batch = RewardDistribution::reward_batches(task_id(1)).expect("batch kept");
+        assert_eq!((batch.asset, batch.total_bounty, batch.created_at), (NATIVE, 100, 1));
+        assert_eq!(RewardDistribution::pending_rewards(ALICE, NATIVE), 100);
+        assert_eq!(RewardDistribution::delegator_rewards(BOB, NATIVE), 20);
+        assert_eq!(RewardDistribution::claimable_rewards(ALICE, NATIVE), 30);
+        assert_eq!(RewardDistribution::total_rewards_distributed(NATIVE), 500);
+        assert_eq!(RewardDistribution::platform_fees_collected(NATIVE), 50);
+        assert_eq!(StorageVersion::get::<RewardDistribution>(), StorageVersion::new(2));
+    });
+}
+
+#[test]
+fn migration_to_v3_indexes_streams_by_provider() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(2).put::<RewardDistribution>();
+        let stream = || PaymentStream::<Test> { asset: NATIVE, per_round: 10, remaining: 20, slashes_at_open: 0 };
+        PaymentStreams::<Test>::insert(task_id(1), ALICE, stream());
+        PaymentStreams::<Test>::insert(task_id(2), ALICE, stream());
+        PaymentStreams::<Test>::insert(task_id(1), BOB, stream());
+
+        run_migration::<migrations::v3::MigrateToV3<Test>>();
+
+        let mut alice = RewardDistribution::provider_streams(ALICE).to_vec();
+        alice.sort();
+        assert_eq!(alice, vec![task_id(1), task_id(2)]);
+        assert_eq!(RewardDistribution::provider_streams(BOB).to_vec(), vec![task_id(1)]);
+        assert_eq!(StorageVersion::get::<RewardDistribution>(), StorageVersion::new(3));
+
+        // Already at v3, so running it again changes nothing
+        run_migration::<migrations::v3::MigrateToV3<Test>>();
+        assert_eq!(RewardDistribution::provider_streams(BOB).len(), 1);
+    });
+}
+
+#[test]
+fn migration_to_v4_rebases_streams_on_applied_slashes() {
+    new_test_ext().execute_with(|| {
+        let task = task_id(1);
+        add_task(task, CREATOR, 1000, &[ALICE]);
+        slash(ALICE);
+        assert_ok!(RewardDistribution::open_stream(RuntimeOrigin::signed(CREATOR), task, ALICE, 100, 2));
+
+        // Opened against a slash count that also held two cancelled slashes
+        StorageVersion::new(3).put::<RewardDistribution>();
+        PaymentStreams::<Test>::mutate(task, ALICE, |stream| stream.as_mut().unwrap().slashes_at_open = 3);
+
+        run_migration::<migrations::v4::MigrateToV4<Test>>();
+
+        assert_eq!(RewardDistribution::payment_streams(task, ALICE).unwrap().slashes_at_open, 1);
+        assert_eq!(StorageVersion::get::<RewardDistribution>(), StorageVersion::new(4));
+
+        contribute(task, ALICE, 1);
+        assert_ok!(RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 1));
+        System::assert_has_event(Event::StreamPaid { task_id: task, provider: ALICE, round: 1, amount: 90 }.into());
+
+        // The next applied slash stops the stream
+        slash(ALICE);
+        assert_ok!(RewardDistribution::close_round(RuntimeOrigin::signed(CREATOR), task, 2));
+        System::assert_has_event(Event::StreamStopped { task_id: task, provider: ALICE, refunded: 100 }.into());
+        assert!(RewardDistribution::payment_streams(task, ALICE).is_none());
+    });
+}
@@ -4,35 +4,111 @@ use codec::{Encode, DecodeWithMemTracking};
 use frame_support::{
     dispatch::DispatchResult,
     pallet_prelude::*,
-    traits::{Currency, ReservableCurrency, ExistenceRequirement},
+    traits::{
+        fungible::{self, MutateHold},
+        fungibles::{self, Mutate},
+        tokens::{Fortitude, Precision, Preservation, Restriction},
+    },
     PalletId, BoundedVec,
 };
 use frame_system::pallet_prelude::*;
 use scale_info::prelude::vec::Vec;
-use sp_runtime::traits::{AccountIdConversion, Hash as HashT};
+use sp_runtime::traits::{AccountIdConversion, Hash as HashT, Saturating, Zero};
 
 pub use pallet::*;
+pub use pallet_provider_staking::HardwareRequirements;
+use pallet_provider_staking::ProviderInspect;
 
-type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+pub mod migrations;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> = <<T as Config>::Currency as fungible::Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// Access to tasks and the bounties of completed tasks, used by reward distribution
+pub trait TaskEscrow<AccountId, Hash, AssetKind, Balance> {
+    /// Creator of a task, if it exists
+    fn creator(task_id: &Hash) -> Option<AccountId>;
+
+    /// Whether `who` joined the task as a provider
+    fn is_provider(task_id: &Hash, who: &AccountId) -> bool;
+
+    /// Whether the task is running
+    fn is_running(task_id: &Hash) -> bool;
+
+    /// Asset and amount of the bounty held in escrow for a completed task that has not been
+    /// paid out yet
+    fn escrowed(task_id: &Hash) -> Option<(AssetKind, Balance)>;
+
+    /// Move a completed task's whole escrowed bounty to `dest`, returning the amount moved
+    fn release(task_id: &Hash, dest: &AccountId) -> Result<Balance, DispatchError>;
+
+    /// Move part of a running task's bounty to `dest`, returning the bounty's asset
+    fn draw(task_id: &Hash, dest: &AccountId, amount: Balance) -> Result<AssetKind, DispatchError>;
+}
+
+/// Handlers notified of task lifecycle changes, implemented by reward distribution
+pub trait TaskHooks<AccountId, Hash> {
+    /// A provider left a running or validating task
+    fn on_provider_left(task_id: &Hash, who: &AccountId) -> DispatchResult;
+
+    /// A task completed and its bounty moved into escrow
+    fn on_task_completed(task_id: &Hash) -> DispatchResult;
+}
+
+impl<AccountId, Hash> TaskHooks<AccountId, Hash> for () {
+    fn on_provider_left(_task_id: &Hash, _who: &AccountId) -> DispatchResult {
+        Ok(())
+    }
+
+    fn on_task_completed(_task_id: &Hash) -> DispatchResult {
+        Ok(())
+    }
+}
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
 
+    /// The current storage version.
+    pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
+        /// Overarching hold reason
+        type RuntimeHoldReason: From<HoldReason>;
+
         /// Currency type for token operations
-        type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+        type Currency: fungible::Mutate<Self::AccountId>
+            + fungible::MutateHold<Self::AccountId, Reason = Self::RuntimeHoldReason>;
 
-        /// Minimum bounty for a task
+        /// Identifier of the assets bounties can be paid in
+        type AssetKind: Parameter + MaxEncodedLen;
+
+        /// Native currency and other assets, used for bounties not paid in the native asset
+        /// and for paying out escrow
+        type Assets: fungibles::Mutate<Self::AccountId, AssetId = Self::AssetKind, Balance = BalanceOf<Self>>;
+
+        /// The native asset, whose bounties are held rather than transferred
+        #[pallet::constant]
+        type NativeAsset: Get<Self::AssetKind>;
+
+        /// Minimum bounty for a task paid in the native asset
         #[pallet::constant]
         type MinimumBounty: Get<BalanceOf<Self>>;
 
+        /// Origin that whitelists assets for bounties
+        type AssetOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
         /// Maximum providers per task
         #[pallet::constant]
         type MaxProvidersPerTask: Get<u32>;
@@ -40,6 +116,39 @@ pub mod pallet {
         /// The pallet's ID, used for deriving its sovereign account
         #[pallet::constant]
         type PalletId: Get<PalletId>;
+
+        /// Provider lookup used to check hardware requirements and select candidates
+        type Providers: ProviderInspect<Self::AccountId>;
+
+        /// Blocks a provider has to accept an assignment offer
+        #[pallet::constant]
+        type OfferWindow: Get<BlockNumberFor<Self>>;
+
+        /// Maximum providers offered a single task over its lifetime
+        #[pallet::constant]
+        type MaxOffersPerTask: Get<u32>;
+
+        /// Maximum offers expiring in a single block
+        #[pallet::constant]
+        type MaxOfferExpiriesPerBlock: Get<u32>;
+
+        /// Maximum tasks waiting for candidate selection
+        #[pallet::constant]
+        type MaxPendingAssignments: Get<u32>;
+
+        /// Maximum tasks given new offers per block
+        #[pallet::constant]
+        type MaxAssignmentsPerBlock: Get<u32>;
+
+        /// Handlers notified when providers leave tasks and tasks complete
+        type Hooks: TaskHooks<Self::AccountId, Self::Hash>;
+    }
+
+    /// Reasons this pallet places holds on funds
+    #[pallet::composite_enum]
+    pub enum HoldReason {
+        /// Bounty of a task that has not completed yet
+        TaskBounty,
     }
 
     #[derive(Encode, Decode, DecodeWithMemTracking, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
@@ -68,6 +177,7 @@ pub mod pallet {
         pub creator: T::AccountId,
         pub name: BoundedVec<u8, ConstU32<255>>,
         pub model_type: ModelType,
+        pub asset: T::AssetKind,
         pub bounty: BalanceOf<T>,
         pub min_providers: u32,
         pub max_providers: u32,
@@ -78,11 +188,20 @@ pub mod pallet {
         pub hardware_requirements: HardwareRequirements,
     }
 
-    #[derive(Encode, Decode, DecodeWithMemTracking, Clone, RuntimeDebug, TypeInfo, Default, PartialEq, MaxEncodedLen)]
-    pub struct HardwareRequirements {
-        pub min_vram_gb: u32,
-        pub min_compute_capability: u32, // Stored as u32, divide by 10 for float
-        pub min_bandwidth_mbps: u32,
+    /// Matchmaking state of a task recruiting in assignment mode
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct Assignment<T: Config> {
+        /// Every provider offered the task so far, in offer order
+        pub offered: BoundedVec<T::AccountId, T::MaxOffersPerTask>,
+        /// Offers not yet accepted, declined or expired
+        pub outstanding: u32,
+    }
+
+    impl<T: Config> Default for Assignment<T> {
+        fn default() -> Self {
+            Self { offered: BoundedVec::default(), outstanding: 0 }
+        }
     }
 
     #[pallet::storage]
@@ -105,13 +224,57 @@ pub mod pallet {
         ValueQuery,
     >;
 
+    /// Non-native assets accepted for bounties and their minimum bounty
+    #[pallet::storage]
+    #[pallet::getter(fn minimum_bounties)]
+    pub type MinimumBounties<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetKind, BalanceOf<T>>;
+
+    /// Bounties of completed tasks held in the escrow account until paid out
+    #[pallet::storage]
+    #[pallet::getter(fn escrow)]
+    pub type Escrow<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, BalanceOf<T>>;
+
+    /// Tasks recruiting in assignment mode
+    #[pallet::storage]
+    #[pallet::getter(fn assignments)]
+    pub type Assignments<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, Assignment<T>>;
+
+    /// Open assignment offers and the block they expire at
+    #[pallet::storage]
+    #[pallet::getter(fn offers)]
+    pub type Offers<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::Hash, // Task ID
+        Blake2_128Concat,
+        T::AccountId, // Provider
+        BlockNumberFor<T>,
+    >;
+
+    /// Offers expiring at each block
+    #[pallet::storage]
+    pub type OfferExpiries<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        BoundedVec<(T::Hash, T::AccountId), T::MaxOfferExpiriesPerBlock>,
+        ValueQuery,
+    >;
+
+    /// Tasks waiting for candidates to be selected
+    #[pallet::storage]
+    #[pallet::getter(fn assignment_queue)]
+    pub type AssignmentQueue<T: Config> =
+        StorageValue<_, BoundedVec<T::Hash, T::MaxPendingAssignments>, ValueQuery>;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        /// Task created [task_id, creator, bounty]
+        /// Task created [task_id, creator, asset, bounty]
         TaskCreated {
             task_id: T::Hash,
             creator: T::AccountId,
+            asset: T::AssetKind,
             bounty: BalanceOf<T>
         },
 
@@ -131,6 +294,13 @@ pub mod pallet {
             task_id: T::Hash
         },
 
+        /// Escrowed bounty released for payout [task_id, dest, amount]
+        EscrowReleased {
+            task_id: T::Hash,
+            dest: T::AccountId,
+            amount: BalanceOf<T>
+        },
+
         /// Task cancelled [task_id, refunded_amount]
         TaskCancelled {
             task_id: T::Hash,
@@ -142,6 +312,49 @@ pub mod pallet {
             task_id: T::Hash,
             provider: T::AccountId
         },
+
+        /// Provider left a running task [task_id, provider]
+        ProviderLeft {
+            task_id: T::Hash,
+            provider: T::AccountId
+        },
+
+        /// Part of a running task's bounty drawn [task_id, dest, amount]
+        BountyDrawn {
+            task_id: T::Hash,
+            dest: T::AccountId,
+            amount: BalanceOf<T>
+        },
+
+        /// Task queued for provider assignment [task_id]
+        AssignmentRequested {
+            task_id: T::Hash
+        },
+
+        /// Task offered to a provider [task_id, provider, expires_at]
+        ProviderOffered {
+            task_id: T::Hash,
+            provider: T::AccountId,
+            expires_at: BlockNumberFor<T>
+        },
+
+        /// Provider declined an offer [task_id, provider]
+        OfferDeclined {
+            task_id: T::Hash,
+            provider: T::AccountId
+        },
+
+        /// Offer expired without an answer [task_id, provider]
+        OfferExpired {
+            task_id: T::Hash,
+            provider: T::AccountId
+        },
+
+        /// Asset accepted for bounties, or removed when `minimum` is `None` [asset, minimum]
+        MinimumBountySet {
+            asset: T::AssetKind,
+            minimum: Option<BalanceOf<T>>
+        },
     }
 
     #[pallet::error]
@@ -171,6 +384,29 @@ pub mod pallet {
         NameTooLong,
         /// IPFS hash too long
         IpfsHashTooLong,
+        /// Caller is not a registered provider
+        NotAProvider,
+        /// Provider hardware does not meet the task's requirements
+        HardwareRequirementsNotMet,
+        /// Task recruits by assignment and can only be joined through an offer
+        AssignmentOnly,
+        /// No open offer for this provider and task
+        OfferNotFound,
+        /// Too many tasks are waiting for assignment
+        AssignmentQueueFull,
+        /// Task has no bounty in escrow
+        NothingEscrowed,
+        /// Asset is not accepted for bounties
+        AssetNotAccepted,
+        /// Provider has not joined this task
+        NotJoined,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            Self::expire_offers(now).saturating_add(Self::process_assignments(now))
+        }
     }
 
     #[pallet::call]
@@ -182,6 +418,7 @@ pub mod pallet {
             origin: OriginFor<T>,
             name: Vec<u8>,
             model_type: ModelType,
+            asset: T::AssetKind,
             bounty: BalanceOf<T>,
             min_providers: u32,
             max_providers: u32,
@@ -191,14 +428,18 @@ pub mod pallet {
             let creator = ensure_signed(origin)?;
 
             // Validate inputs
-            ensure!(bounty >= T::MinimumBounty::get(), Error::<T>::BountyTooLow);
+            ensure!(bounty >= Self::minimum_bounty(&asset)?, Error::<T>::BountyTooLow);
             // Length validation is handled by BoundedVec
             ensure!(max_providers <= T::MaxProvidersPerTask::get(), Error::<T>::TooManyProviders);
             ensure!(min_providers > 0 && min_providers <= max_providers,
                 Error::<T>::InvalidHardwareRequirements);
 
-            // Reserve the bounty amount
-            T::Currency::reserve(&creator, bounty)?;
+            // Hold native bounties; other assets cannot be held and move to the escrow account
+            if asset == T::NativeAsset::get() {
+                T::Currency::hold(&HoldReason::TaskBounty.into(), &creator, bounty)?;
+            } else {
+                T::Assets::transfer(asset.clone(), &creator, &Self::account_id(), bounty, Preservation::Preserve)?;
+            }
 
             // Generate task ID
             let task_count = TaskCount::<T>::get();
@@ -209,6 +450,7 @@ pub mod pallet {
                 creator: creator.clone(),
                 name: name.try_into().map_err(|_| Error::<T>::NameTooLong)?,
                 model_type,
+                asset: asset.clone(),
                 bounty,
                 min_providers,
                 max_providers,
@@ -227,6 +469,7 @@ pub mod pallet {
             Self::deposit_event(Event::TaskCreated {
                 task_id,
                 creator,
+                asset,
                 bounty
             });
 
@@ -275,8 +518,18 @@ pub mod pallet {
                 Error::<T>::TaskAlreadyStarted
             );
 
-            // Unreserve the bounty
-            T::Currency::unreserve(&task.creator, task.bounty);
+            // Release the bounty
+            if task.asset == T::NativeAsset::get() {
+                T::Currency::release(&HoldReason::TaskBounty.into(), &task.creator, task.bounty, Precision::Exact)?;
+            } else {
+                T::Assets::transfer(
+                    task.asset.clone(),
+                    &Self::account_id(),
+                    &task.creator,
+                    task.bounty,
+                    Preservation::Expendable,
+                )?;
+            }
 
             // Update task status
             Tasks::<T>::mutate(&task_id, |maybe_task| {
@@ -284,6 +537,7 @@ pub mod pallet {
                     task.status = TaskStatus::Cancelled;
                 }
             });
+            Self::end_assignment(task_id);
 
             Self::deposit_event(Event::TaskCancelled {
                 task_id,
@@ -311,15 +565,25 @@ pub mod pallet {
                 task.status = TaskStatus::Completed;
                 task.completed_at = Some(frame_system::Pallet::<T>::block_number());
 
-                // Transfer bounty from reserve to escrow account
-                let escrow_account = Self::account_id();
-                let bounty = task.bounty;
-                T::Currency::unreserve(&task.creator, bounty);
-                T::Currency::transfer(&task.creator, &escrow_account, bounty, ExistenceRequirement::KeepAlive)?;
+                // Transfer a held bounty to the escrow account; other assets are there already
+                if task.asset == T::NativeAsset::get() {
+                    T::Currency::transfer_on_hold(
+                        &HoldReason::TaskBounty.into(),
+                        &task.creator,
+                        &Self::account_id(),
+                        task.bounty,
+                        Precision::Exact,
+                        Restriction::Free,
+                        Fortitude::Polite,
+                    )?;
+                }
+                Escrow::<T>::insert(&task_id, task.bounty);
 
                 Ok::<(), DispatchError>(())
             })?;
 
+            T::Hooks::on_task_completed(&task_id)?;
+
             Self::deposit_event(Event::TaskCompleted { task_id });
 
             Ok(())
@@ -334,11 +598,146 @@ pub mod pallet {
         ) -> DispatchResult {
             let provider = ensure_signed(origin)?;
 
+            ensure!(!Assignments::<T>::contains_key(&task_id), Error::<T>::AssignmentOnly);
+
+            Self::do_join(task_id, provider)
+        }
+
+        /// Start recruiting a task by assignment, or queue it again once its candidates ran out
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(6_000, 0))]
+        pub fn request_assignment(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let task = Tasks::<T>::get(&task_id).ok_or(Error::<T>::TaskNotFound)?;
+            ensure!(task.creator == who, Error::<T>::NotTaskCreator);
+
+            match task.status {
+                TaskStatus::Pending => {
+                    Self::enqueue_assignment(task_id)?;
+                    Tasks::<T>::mutate(&task_id, |maybe_task| {
+                        if let Some(task) = maybe_task {
+                            task.status = TaskStatus::Recruiting;
+                        }
+                    });
+                    Assignments::<T>::insert(&task_id, Assignment::<T>::default());
+                    Self::deposit_event(Event::TaskStarted { task_id });
+                },
+                TaskStatus::Recruiting => {
+                    ensure!(Assignments::<T>::contains_key(&task_id), Error::<T>::InvalidTaskStatus);
+                    Self::enqueue_assignment(task_id)?;
+                },
+                _ => return Err(Error::<T>::InvalidTaskStatus.into()),
+            }
+
+            Self::deposit_event(Event::AssignmentRequested { task_id });
+
+            Ok(())
+        }
+
+        /// Accept an assignment offer and join the task
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(8_000, 0))]
+        pub fn accept_offer(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Self::close_offer(task_id, &provider)?;
+
+            Self::do_join(task_id, provider)
+        }
+
+        /// Decline an assignment offer so the task moves on to the next candidate
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(6_000, 0))]
+        pub fn decline_offer(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Self::close_offer(task_id, &provider)?;
+            let _ = Self::enqueue_assignment(task_id);
+
+            Self::deposit_event(Event::OfferDeclined { task_id, provider });
+
+            Ok(())
+        }
+
+        /// Accept a non-native asset for bounties with a minimum bounty, or stop accepting it
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn set_minimum_bounty(
+            origin: OriginFor<T>,
+            asset: T::AssetKind,
+            minimum: Option<BalanceOf<T>>,
+        ) -> DispatchResult {
+            T::AssetOrigin::ensure_origin(origin)?;
+
+            ensure!(asset != T::NativeAsset::get(), Error::<T>::AssetNotAccepted);
+            MinimumBounties::<T>::set(&asset, minimum);
+
+            Self::deposit_event(Event::MinimumBountySet { asset, minimum });
+
+            Ok(())
+        }
+
+        /// Stop working on a running task
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(5_000, 0))]
+        pub fn leave_task(
+            origin: OriginFor<T>,
+            task_id: T::Hash,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let task = Tasks::<T>::get(&task_id).ok_or(Error::<T>::TaskNotFound)?;
+            ensure!(
+                task.status == TaskStatus::Running || task.status == TaskStatus::Validating,
+                Error::<T>::InvalidTaskStatus
+            );
+            ensure!(TaskProviders::<T>::take(&task_id, &provider), Error::<T>::NotJoined);
+
+            T::Hooks::on_provider_left(&task_id, &provider)?;
+
+            Self::deposit_event(Event::ProviderLeft { task_id, provider });
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Get the account ID of the pallet's escrow account
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account_truncating()
+        }
+
+        /// Minimum bounty in an asset, failing if the asset is not accepted
+        pub fn minimum_bounty(asset: &T::AssetKind) -> Result<BalanceOf<T>, DispatchError> {
+            if *asset == T::NativeAsset::get() {
+                return Ok(T::MinimumBounty::get());
+            }
+            MinimumBounties::<T>::get(asset).ok_or_else(|| Error::<T>::AssetNotAccepted.into())
+        }
+
+        /// Add a provider to a recruiting task, starting it once enough providers joined
+        fn do_join(task_id: T::Hash, provider: T::AccountId) -> DispatchResult {
             let task = Tasks::<T>::get(&task_id).ok_or(Error::<T>::TaskNotFound)?;
 
             ensure!(task.status == TaskStatus::Recruiting, Error::<T>::InvalidTaskStatus);
             ensure!(!TaskProviders::<T>::get(&task_id, &provider), Error::<T>::ProviderAlreadyJoined);
 
+            let hardware = T::Providers::hardware(&provider).ok_or(Error::<T>::NotAProvider)?;
+            ensure!(
+                task.hardware_requirements.is_met_by(&hardware),
+                Error::<T>::HardwareRequirementsNotMet
+            );
+
             // Count current providers
             let provider_count = TaskProviders::<T>::iter_prefix(&task_id)
                 .filter(|(_, joined)| *joined)
@@ -358,18 +757,251 @@ pub mod pallet {
                         }
                     }
                 });
+                Self::end_assignment(task_id);
             }
 
             Self::deposit_event(Event::ProviderJoined { task_id, provider });
 
             Ok(())
         }
+
+        /// Remove an open offer and its expiry
+        fn close_offer(task_id: T::Hash, provider: &T::AccountId) -> DispatchResult {
+            let expires_at = Offers::<T>::take(&task_id, provider).ok_or(Error::<T>::OfferNotFound)?;
+
+            OfferExpiries::<T>::mutate(expires_at, |due| due.retain(|(t, p)| !(*t == task_id && p == provider)));
+            Assignments::<T>::mutate(&task_id, |maybe_assignment| {
+                if let Some(assignment) = maybe_assignment {
+                    assignment.outstanding = assignment.outstanding.saturating_sub(1);
+                }
+            });
+
+            Ok(())
+        }
+
+        /// Queue a task for candidate selection unless it is already queued
+        fn enqueue_assignment(task_id: T::Hash) -> DispatchResult {
+            AssignmentQueue::<T>::try_mutate(|queue| {
+                if !queue.contains(&task_id) {
+                    queue.try_push(task_id).map_err(|_| Error::<T>::AssignmentQueueFull)?;
+                }
+                Ok(())
+            })
+        }
+
+        /// Withdraw every open offer of a task that stopped recruiting by assignment
+        fn end_assignment(task_id: T::Hash) {
+            let Some(assignment) = Assignments::<T>::take(&task_id) else { return };
+
+            for provider in assignment.offered.iter() {
+                let _ = Self::close_offer(task_id, provider);
+            }
+            AssignmentQueue::<T>::mutate(|queue| queue.retain(|t| *t != task_id));
+        }
+
+        /// Drop offers that were not answered in time and queue their tasks again
+        fn expire_offers(now: BlockNumberFor<T>) -> Weight {
+            let due = OfferExpiries::<T>::take(now);
+            let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+            for (task_id, provider) in due {
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(3, 3));
+
+                if Offers::<T>::take(&task_id, &provider).is_none() {
+                    continue;
+                }
+                Assignments::<T>::mutate(&task_id, |maybe_assignment| {
+                    if let Some(assignment) = maybe_assignment {
+                        assignment.outstanding = assignment.outstanding.saturating_sub(1);
+                    }
+                });
+                let _ = Self::enqueue_assignment(task_id);
+
+                Self::deposit_event(Event::OfferExpired { task_id, provider });
+            }
+
+            weight
+        }
+
+        /// Offer queued tasks to the best available candidates not offered yet
+        fn process_assignments(now: BlockNumberFor<T>) -> Weight {
+            let mut queue = AssignmentQueue::<T>::get();
+            let mut weight = T::DbWeight::get().reads(1);
+
+            if queue.is_empty() {
+                return weight;
+            }
+
+            let take = (T::MaxAssignmentsPerBlock::get() as usize).min(queue.len());
+            let batch: Vec<T::Hash> = queue.drain(..take).collect();
+            let mut requeue = Vec::new();
+
+            // Counting joined providers, querying candidates and filtering out those already joined
+            let max_candidates = T::MaxProvidersPerTask::get().saturating_add(T::MaxOffersPerTask::get());
+            let scan = T::Providers::candidates_weight(max_candidates).saturating_add(
+                T::DbWeight::get().reads((T::MaxProvidersPerTask::get() as u64).saturating_add(max_candidates as u64)),
+            );
+
+            for task_id in batch {
+                let (offers, unfinished) = Self::make_offers(task_id, now);
+                weight = weight
+                    .saturating_add(T::DbWeight::get().reads_writes(4, 1))
+                    .saturating_add(scan)
+                    .saturating_add(T::DbWeight::get().reads_writes(offers, offers.saturating_mul(2)));
+                if unfinished {
+                    requeue.push(task_id);
+                }
+            }
+
+            for task_id in requeue {
+                let _ = queue.try_push(task_id);
+            }
+            AssignmentQueue::<T>::put(queue);
+
+            weight.saturating_add(T::DbWeight::get().writes(1))
+        }
+
+        /// Offer a task to as many candidates as it still needs providers.
+        ///
+        /// Returns the number of offers made and whether the task has to stay queued
+        /// because the expiry slot of this block's offers filled up.
+        fn make_offers(task_id: T::Hash, now: BlockNumberFor<T>) -> (u64, bool) {
+            let Some(task) = Tasks::<T>::get(&task_id) else {
+                Assignments::<T>::remove(&task_id);
+                return (0, false);
+            };
+            if task.status != TaskStatus::Recruiting {
+                Self::end_assignment(task_id);
+                return (0, false);
+            }
+            let Some(mut assignment) = Assignments::<T>::get(&task_id) else { return (0, false) };
+
+            let joined = TaskProviders::<T>::iter_prefix(&task_id)
+                .filter(|(_, joined)| *joined)
+                .count() as u32;
+            let needed = task.min_providers.saturating_sub(joined.saturating_add(assignment.outstanding));
+            if needed == 0 {
+                return (0, false);
+            }
+
+            // Ask for enough candidates to skip everyone already offered the task
+            let candidates = T::Providers::candidates(
+                &task.hardware_requirements,
+                needed.saturating_add(assignment.offered.len() as u32),
+            );
+            let expires_at = now.saturating_add(T::OfferWindow::get());
+            let mut offers = 0u64;
+            let mut unfinished = false;
+
+            for provider in candidates
+                .into_iter()
+                .filter(|p| !assignment.offered.contains(p) && !TaskProviders::<T>::get(&task_id, p))
+                .take(needed as usize)
+            {
+                if assignment.offered.is_full() {
+                    break;
+                }
+                if OfferExpiries::<T>::try_append(expires_at, (task_id, provider.clone())).is_err() {
+                    unfinished = true;
+                    break;
+                }
+                let _ = assignment.offered.try_push(provider.clone());
+                assignment.outstanding = assignment.outstanding.saturating_add(1);
+                Offers::<T>::insert(&task_id, &provider, expires_at);
+                offers += 1;
+
+                Self::deposit_event(Event::ProviderOffered { task_id, provider, expires_at });
+            }
+
+            Assignments::<T>::insert(&task_id, assignment);
+
+            (offers, unfinished)
+        }
     }
 
-    impl<T: Config> Pallet<T> {
-        /// Get the account ID of the pallet's escrow account
-        pub fn account_id() -> T::AccountId {
-            T::PalletId::get().into_account_truncating()
+    impl<T: Config> TaskEscrow<T::AccountId, T::Hash, T::AssetKind, BalanceOf<T>> for Pallet<T> {
+        fn creator(task_id: &T::Hash) -> Option<T::AccountId> {
+            Tasks::<T>::get(task_id).map(|task| task.creator)
+        }
+
+        fn is_provider(task_id: &T::Hash, who: &T::AccountId) -> bool {
+            TaskProviders::<T>::get(task_id, who)
+        }
+
+        fn is_running(task_id: &T::Hash) -> bool {
+            Tasks::<T>::get(task_id).map_or(false, |task| task.status == TaskStatus::Running)
+        }
+
+        fn escrowed(task_id: &T::Hash) -> Option<(T::AssetKind, BalanceOf<T>)> {
+            let amount = Escrow::<T>::get(task_id)?;
+            Tasks::<T>::get(task_id).map(|task| (task.asset, amount))
+        }
+
+        fn release(task_id: &T::Hash, dest: &T::AccountId) -> Result<BalanceOf<T>, DispatchError> {
+            let task = Tasks::<T>::get(task_id).ok_or(Error::<T>::TaskNotFound)?;
+            let amount = Escrow::<T>::take(task_id).ok_or(Error::<T>::NothingEscrowed)?;
+
+            T::Assets::transfer(task.asset, &Self::account_id(), dest, amount, Preservation::Expendable)?;
+
+            Self::deposit_event(Event::EscrowReleased {
+                task_id: *task_id,
+                dest: dest.clone(),
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        fn draw(task_id: &T::Hash, dest: &T::AccountId, amount: BalanceOf<T>) -> Result<T::AssetKind, DispatchError> {
+            let asset = Tasks::<T>::try_mutate(task_id, |maybe_task| {
+                let task = maybe_task.as_mut().ok_or(Error::<T>::TaskNotFound)?;
+
+                ensure!(
+                    task.status == TaskStatus::Running || task.status == TaskStatus::Validating,
+                    Error::<T>::InvalidTaskStatus
+                );
+                ensure!(amount <= task.bounty, Error::<T>::InsufficientBalance);
+
+                if task.asset == T::NativeAsset::get() {
+                    T::Currency::transfer_on_hold(
+                        &HoldReason::TaskBounty.into(),
+                        &task.creator,
+                        dest,
+                        amount,
+                        Precision::Exact,
+                        Restriction::Free,
+                        Fortitude::Polite,
+                    )?;
+                } else {
+                    T::Assets::transfer(task.asset.clone(), &Self::account_id(), dest, amount, Preservation::Expendable)?;
+                }
+                task.bounty = task.bounty.saturating_sub(amount);
+
+                Ok::<_, DispatchError>(task.asset.clone())
+            })?;
+
+            Self::deposit_event(Event::BountyDrawn {
+                task_id: *task_id,
+                dest: dest.clone(),
+                amount,
+            });
+
+            Ok(asset)
+        }
+    }
+
+    impl<T: Config> pallet_provider_staking::TaskInspect<T::AccountId, T::Hash, BalanceOf<T>> for Pallet<T> {
+        fn creator_and_bounty(task_id: &T::Hash) -> Option<(T::AccountId, BalanceOf<T>)> {
+            // Compensation is paid in the native asset, so only native bounties bound it
+            Tasks::<T>::get(task_id).map(|task| {
+                let bounty = if task.asset == T::NativeAsset::get() { task.bounty } else { Zero::zero() };
+                (task.creator, bounty)
+            })
+        }
+
+        fn is_active_provider(task_id: &T::Hash, who: &T::AccountId) -> bool {
+            Tasks::<T>::get(task_id).map_or(false, |task| task.status == TaskStatus::Running) &&
+                TaskProviders::<T>::get(task_id, who)
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,332 @@
+//! Storage migrations for the task registry pallet.
+
+use super::*;
+use frame_support::{storage_alias, traits::UncheckedOnRuntimeUpgrade};
+
+pub mod v1 {
+    use super::*;
+
+    /// `HardwareRequirements` before tasks could require a minimum GPU tier
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, PartialEq, MaxEncodedLen)]
+    pub struct OldHardwareRequirements {
+        pub min_vram_gb: u32,
+        pub min_compute_capability: u32,
+        pub min_bandwidth_mbps: u32,
+    }
+
+    /// `Task` as of storage version 0
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct OldTask<T: Config> {
+        pub creator: T::AccountId,
+        pub name: BoundedVec<u8, ConstU32<255>>,
+        pub model_type: ModelType,
+        pub bounty: BalanceOf<T>,
+        pub min_providers: u32,
+        pub max_providers: u32,
+        pub status: TaskStatus,
+        pub created_at: BlockNumberFor<T>,
+        pub completed_at: Option<BlockNumberFor<T>>,
+        pub ipfs_hash: BoundedVec<u8, ConstU32<64>>,
+        pub hardware_requirements: OldHardwareRequirements,
+    }
+
+    /// Adds `min_gpu_tier` to every task's hardware requirements, defaulting to the lowest tier.
+    pub struct InnerMigrateToV1<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated = 0u64;
+
+            v3::Tasks::<T>::translate::<OldTask<T>, _>(|_, old| {
+                translated += 1;
+                Some(v3::OldTask {
+                    creator: old.creator,
+                    name: old.name,
+                    model_type: old.model_type,
+                    bounty: old.bounty,
+                    min_providers: old.min_providers,
+                    max_providers: old.max_providers,
+                    status: old.status,
+                    created_at: old.created_at,
+                    completed_at: old.completed_at,
+                    ipfs_hash: old.ipfs_hash,
+                    hardware_requirements: HardwareRequirements {
+                        min_vram_gb: old.hardware_requirements.min_vram_gb,
+                        min_compute_capability: old.hardware_requirements.min_compute_capability,
+                        min_bandwidth_mbps: old.hardware_requirements.min_bandwidth_mbps,
+                        min_gpu_tier: Default::default(),
+                    },
+                })
+            });
+
+            log::info!(target: "task-registry", "migrated {} tasks to v1", translated);
+
+            T::DbWeight::get().reads_writes(translated, translated)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((v3::Tasks::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let tasks = u32::decode(&mut &state[..]).map_err(|_| "v1: invalid pre-upgrade state")?;
+            let mut count = 0u32;
+            for task in v3::Tasks::<T>::iter_values() {
+                ensure!(
+                    task.hardware_requirements.min_gpu_tier == Default::default(),
+                    "v1: minimum GPU tier not the lowest"
+                );
+                count += 1;
+            }
+            ensure!(count == tasks, "v1: tasks lost");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV1<T> = frame_support::migrations::VersionedMigration<
+        0,
+        1,
+        InnerMigrateToV1<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v2 {
+    use super::*;
+    use frame_support::traits::ReservableCurrency;
+    use sp_runtime::traits::Saturating;
+
+    /// Moves the bounties of unfinished tasks from anonymous reserves to holds.
+    ///
+    /// `OldCurrency` is the `ReservableCurrency` the pallet was configured with before the
+    /// switch to fungible holds.
+    pub struct InnerMigrateToV2<T, OldCurrency>(core::marker::PhantomData<(T, OldCurrency)>);
+
+    impl<T, OldCurrency> UncheckedOnRuntimeUpgrade for InnerMigrateToV2<T, OldCurrency>
+    where
+        T: Config,
+        OldCurrency: ReservableCurrency<T::AccountId, Balance = BalanceOf<T>>,
+    {
+        fn on_runtime_upgrade() -> Weight {
+            let mut reads = 0u64;
+            let mut moved = 0u64;
+
+            for (_, task) in v3::Tasks::<T>::iter() {
+                reads += 1;
+
+                // Bounties stay reserved until a task completes or is cancelled
+                if matches!(task.status, TaskStatus::Completed | TaskStatus::Cancelled) {
+                    continue;
+                }
+
+                let missing = OldCurrency::unreserve(&task.creator, task.bounty);
+                let available = task.bounty.saturating_sub(missing);
+
+                if let Err(e) = T::Currency::hold(&HoldReason::TaskBounty.into(), &task.creator, available) {
+                    log::error!(target: "task-registry", "failed to hold bounty of {:?}: {:?}", task.creator, e);
+                }
+                moved += 1;
+            }
+
+            log::info!(target: "task-registry", "moved {} task bounties to holds", moved);
+
+            T::DbWeight::get().reads_writes(reads.saturating_add(moved.saturating_mul(2)), moved.saturating_mul(3))
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            use sp_std::collections::btree_map::BTreeMap;
+
+            // What the migration can move: each open bounty, capped by what its creator has left reserved
+            let mut reserved: BTreeMap<T::AccountId, BalanceOf<T>> = BTreeMap::new();
+            let mut expected = BalanceOf::<T>::zero();
+            for task in v3::Tasks::<T>::iter_values() {
+                if matches!(task.status, TaskStatus::Completed | TaskStatus::Cancelled) {
+                    continue;
+                }
+                let left = reserved
+                    .entry(task.creator.clone())
+                    .or_insert_with(|| OldCurrency::reserved_balance(&task.creator));
+                let moved = task.bounty.min(*left);
+                *left = left.saturating_sub(moved);
+                expected = expected.saturating_add(moved);
+            }
+            Ok(expected.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            use fungible::InspectHold;
+            use sp_std::collections::btree_set::BTreeSet;
+
+            let expected = BalanceOf::<T>::decode(&mut &state[..]).map_err(|_| "v2: invalid pre-upgrade state")?;
+
+            let mut held = BalanceOf::<T>::zero();
+            let creators = v3::Tasks::<T>::iter_values()
+                .filter(|task| !matches!(task.status, TaskStatus::Completed | TaskStatus::Cancelled))
+                .map(|task| task.creator)
+                .collect::<BTreeSet<_>>();
+            for creator in creators.iter() {
+                held = held.saturating_add(T::Currency::balance_on_hold(&HoldReason::TaskBounty.into(), creator));
+            }
+            ensure!(held == expected, "v2: reserved bounties not moved to holds");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV2<T, OldCurrency> = frame_support::migrations::VersionedMigration<
+        1,
+        2,
+        InnerMigrateToV2<T, OldCurrency>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v3 {
+    use super::*;
+
+    /// `Task` as of storage versions 1 to 3
+    #[derive(Encode, Decode, Clone, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[scale_info(skip_type_params(T))]
+    pub struct OldTask<T: Config> {
+        pub creator: T::AccountId,
+        pub name: BoundedVec<u8, ConstU32<255>>,
+        pub model_type: ModelType,
+        pub bounty: BalanceOf<T>,
+        pub min_providers: u32,
+        pub max_providers: u32,
+        pub status: TaskStatus,
+        pub created_at: BlockNumberFor<T>,
+        pub completed_at: Option<BlockNumberFor<T>>,
+        pub ipfs_hash: BoundedVec<u8, ConstU32<64>>,
+        pub hardware_requirements: HardwareRequirements,
+    }
+
+    /// Tasks as stored before bounties named their asset
+    #[storage_alias]
+    pub type Tasks<T: Config> = StorageMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        <T as frame_system::Config>::Hash,
+        OldTask<T>,
+    >;
+
+    /// Records the escrowed bounty of every completed task.
+    ///
+    /// Nothing has been paid out of the escrow account before this version, so every
+    /// completed task still has its whole bounty there.
+    pub struct InnerMigrateToV3<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV3<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut reads = 0u64;
+            let mut escrowed = 0u64;
+
+            for (task_id, task) in Tasks::<T>::iter() {
+                reads += 1;
+                if task.status == TaskStatus::Completed {
+                    Escrow::<T>::insert(task_id, task.bounty);
+                    escrowed += 1;
+                }
+            }
+
+            log::info!(target: "task-registry", "recorded escrow of {} completed tasks", escrowed);
+
+            T::DbWeight::get().reads_writes(reads, escrowed)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            ensure!(Escrow::<T>::iter_keys().next().is_none(), "v3: escrow already recorded");
+            let completed = Tasks::<T>::iter_values().filter(|task| task.status == TaskStatus::Completed).count() as u32;
+            Ok(completed.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let completed = u32::decode(&mut &state[..]).map_err(|_| "v3: invalid pre-upgrade state")?;
+            let mut escrowed = 0u32;
+            for (task_id, amount) in Escrow::<T>::iter() {
+                let task = Tasks::<T>::get(task_id).ok_or("v3: escrow of an unknown task")?;
+                ensure!(task.status == TaskStatus::Completed, "v3: escrow of an unfinished task");
+                ensure!(amount == task.bounty, "v3: escrow differs from the bounty");
+                escrowed += 1;
+            }
+            ensure!(escrowed == completed, "v3: completed tasks without escrow");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV3<T> = frame_support::migrations::VersionedMigration<
+        2,
+        3,
+        InnerMigrateToV3<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v4 {
+    use super::*;
+
+    /// Records that every existing task's bounty is paid in the native asset.
+    pub struct InnerMigrateToV4<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateToV4<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated = 0u64;
+
+            Tasks::<T>::translate::<v3::OldTask<T>, _>(|_, old| {
+                translated += 1;
+                Some(Task {
+                    creator: old.creator,
+                    name: old.name,
+                    model_type: old.model_type,
+                    asset: T::NativeAsset::get(),
+                    bounty: old.bounty,
+                    min_providers: old.min_providers,
+                    max_providers: old.max_providers,
+                    status: old.status,
+                    created_at: old.created_at,
+                    completed_at: old.completed_at,
+                    ipfs_hash: old.ipfs_hash,
+                    hardware_requirements: old.hardware_requirements,
+                })
+            });
+
+            log::info!(target: "task-registry", "migrated {} tasks to v4", translated);
+
+            T::DbWeight::get().reads_writes(translated, translated)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((v3::Tasks::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let tasks = u32::decode(&mut &state[..]).map_err(|_| "v4: invalid pre-upgrade state")?;
+            let mut count = 0u32;
+            for task in Tasks::<T>::iter_values() {
+                ensure!(task.asset == T::NativeAsset::get(), "v4: bounty not in the native asset");
+                count += 1;
+            }
+            ensure!(count == tasks, "v4: tasks lost");
+            Ok(())
+        }
+    }
+
+    pub type MigrateToV4<T> = frame_support::migrations::VersionedMigration<
+        3,
+        4,
+        InnerMigrateToV4<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
@@ -0,0 +1,218 @@
+//! Test runtime for the task registry pallet.
+//!
+//! Providers and task hooks come from in-memory mocks so each test can set up exactly the
+//! providers it needs and inspect the hook calls made.
+
+use crate as pallet_task_registry;
+use crate::TaskHooks;
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{
+        fungible::{self, NativeFromLeft, NativeOrWithId, UnionOf},
+        fungibles, AsEnsureOriginWithArg, ConstU32, ConstU64, Hooks,
+    },
+    weights::Weight,
+    PalletId,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use pallet_provider_staking::{GpuTier, HardwareInfo, HardwareRequirements, ProviderInspect};
+use sp_core::H256;
+use sp_runtime::{BuildStorage, DispatchResult};
+use std::{cell::RefCell, collections::BTreeMap};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = u64;
+pub type Balance = u64;
+pub type AssetKind = NativeOrWithId<u32>;
+
+pub const CREATOR: AccountId = 1;
+pub const ALICE: AccountId = 2;
+pub const BOB: AccountId = 3;
+pub const CHARLIE: AccountId = 4;
+
+pub const INITIAL_BALANCE: Balance = 1_000_000;
+pub const MINIMUM_BOUNTY: Balance = 100;
+/// Asset bounties can be funded in once whitelisted
+pub const ASSET: u32 = 1;
+pub const ASSET_MIN_BALANCE: Balance = 10;
+pub const OFFER_WINDOW: u64 = 5;
+
+frame_support::construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        Balances: pallet_balances,
+        Assets: pallet_assets,
+        TaskRegistry: pallet_task_registry,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+#[derive_impl(pallet_assets::config_preludes::TestDefaultConfig)]
+impl pallet_assets::Config for Test {
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type Freezer = ();
+    type Holder = ();
+}
+
+pub type NativeAndAssets = UnionOf<Balances, Assets, NativeFromLeft, AssetKind, AccountId>;
+
+parameter_types! {
+    pub const NativeAsset: AssetKind = NativeOrWithId::Native;
+    pub const TaskRegistryPalletId: PalletId = PalletId(*b"py/tasks");
+}
+
+impl pallet_task_registry::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type Currency = Balances;
+    type AssetKind = AssetKind;
+    type Assets = NativeAndAssets;
+    type NativeAsset = NativeAsset;
+    type MinimumBounty = ConstU64<MINIMUM_BOUNTY>;
+    type AssetOrigin = EnsureRoot<AccountId>;
+    type MaxProvidersPerTask = ConstU32<4>;
+    type PalletId = TaskRegistryPalletId;
+    type Providers = MockProviders;
+    type OfferWindow = ConstU64<OFFER_WINDOW>;
+    type MaxOffersPerTask = ConstU32<8>;
+    type MaxOfferExpiriesPerBlock = ConstU32<4>;
+    type MaxPendingAssignments = ConstU32<4>;
+    type MaxAssignmentsPerBlock = ConstU32<2>;
+    type Hooks = MockHooks;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HookCall {
+    ProviderLeft(H256, AccountId),
+    TaskCompleted(H256),
+}
+
+thread_local! {
+    static PROVIDERS: RefCell<BTreeMap<AccountId, HardwareInfo>> = RefCell::new(BTreeMap::new());
+    static HOOK_CALLS: RefCell<Vec<HookCall>> = RefCell::new(Vec::new());
+}
+
+/// Registers a provider with hardware of the given VRAM
+pub fn add_provider(who: AccountId, vram_gb: u32) {
+    let hardware = HardwareInfo {
+        gpu_model: b"RTX 4090".to_vec().try_into().unwrap(),
+        gpu_tier: GpuTier::Consumer,
+        vram_gb,
+        compute_capability: 89,
+        bandwidth_mbps: 1000,
+        cpu_cores: 16,
+        ram_gb: 64,
+    };
+    PROVIDERS.with(|providers| providers.borrow_mut().insert(who, hardware));
+}
+
+/// Hook calls made so far, in order
+pub fn hook_calls() -> Vec<HookCall> {
+    HOOK_CALLS.with(|calls| calls.borrow().clone())
+}
+
+/// Providers backed by `PROVIDERS`; candidates are ranked by account
+pub struct MockProviders;
+
+impl ProviderInspect<AccountId> for MockProviders {
+    fn hardware(who: &AccountId) -> Option<HardwareInfo> {
+        PROVIDERS.with(|providers| providers.borrow().get(who).cloned())
+    }
+
+    fn candidates(requirements: &HardwareRequirements, count: u32) -> Vec<AccountId> {
+        PROVIDERS.with(|providers| {
+            providers
+                .borrow()
+                .iter()
+                .filter(|(_, hardware)| requirements.is_met_by(hardware))
+                .map(|(who, _)| *who)
+                .take(count as usize)
+                .collect()
+        })
+    }
+
+    fn candidates_weight(_count: u32) -> Weight {
+        Weight::zero()
+    }
+}
+
+/// Records every hook call
+pub struct MockHooks;
+
+impl TaskHooks<AccountId, H256> for MockHooks {
+    fn on_provider_left(task_id: &H256, who: &AccountId) -> DispatchResult {
+        HOOK_CALLS.with(|calls| calls.borrow_mut().push(HookCall::ProviderLeft(*task_id, *who)));
+        Ok(())
+    }
+
+    fn on_task_completed(task_id: &H256) -> DispatchResult {
+        HOOK_CALLS.with(|calls| calls.borrow_mut().push(HookCall::TaskCompleted(*task_id)));
+        Ok(())
+    }
+}
+
+pub fn escrow_account() -> AccountId {
+    TaskRegistry::account_id()
+}
+
+pub fn balance(who: AccountId) -> Balance {
+    <Balances as fungible::Inspect<AccountId>>::balance(&who)
+}
+
+pub fn asset_balance(who: AccountId) -> Balance {
+    <Assets as fungibles::Inspect<AccountId>>::balance(ASSET, &who)
+}
+
+pub fn held(who: AccountId) -> Balance {
+    <Balances as fungible::InspectHold<AccountId>>::total_balance_on_hold(&who)
+}
+
+pub fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        System::set_block_number(System::block_number() + 1);
+        TaskRegistry::on_initialize(System::block_number());
+    }
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![
+            (CREATOR, INITIAL_BALANCE),
+            (ALICE, INITIAL_BALANCE),
+            (BOB, INITIAL_BALANCE),
+            (CHARLIE, INITIAL_BALANCE),
+            // The escrow account keeps its existential deposit while paying out
+            (TaskRegistry::account_id(), 1),
+        ],
+        ..Default::default()
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    pallet_assets::GenesisConfig::<Test> {
+        assets: vec![(ASSET, CREATOR, true, ASSET_MIN_BALANCE)],
+        accounts: vec![(ASSET, CREATOR, INITIAL_BALANCE)],
+        ..Default::default()
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
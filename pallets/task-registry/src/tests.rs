@@ -0,0 +1,367 @@
+use crate::{migrations, mock::*, Error, Event, HardwareRequirements, ModelType, TaskEscrow, TaskStatus, Tasks};
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::unhashed,
+    traits::{fungible::NativeOrWithId, OnRuntimeUpgrade, ReservableCurrency, StorageVersion},
+    BoundedVec,
+};
+use sp_core::H256;
+use sp_runtime::traits::Hash;
+
+const NATIVE: AssetKind = NativeOrWithId::Native;
+
+fn task_id(n: u8) -> H256 {
+    H256::repeat_byte(n)
+}
+
+/// Create a task needing two providers and return its ID
+fn create(asset: AssetKind, bounty: Balance, hardware_requirements: HardwareRequirements) -> H256 {
+    let task = <Test as frame_system::Config>::Hashing::hash_of(&(CREATOR, TaskRegistry::task_count()));
+    assert_ok!(TaskRegistry::create_task(
+        RuntimeOrigin::signed(CREATOR),
+        b"task".to_vec(),
+        ModelType::ResNet,
+        asset,
+        bounty,
+        2,
+        2,
+        b"Qm".to_vec(),
+        hardware_requirements,
+    ));
+    task
+}
+
+/// Create a native task and let ALICE and BOB join it until it runs
+fn running_task(bounty: Balance) -> H256 {
+    let task = create(NATIVE, bounty, HardwareRequirements::default());
+    assert_ok!(TaskRegistry::start_recruiting(RuntimeOrigin::signed(CREATOR), task));
+    add_provider(ALICE, 24);
+    add_provider(BOB, 24);
+    assert_ok!(TaskRegistry::join_task(RuntimeOrigin::signed(ALICE), task));
+    assert_ok!(TaskRegistry::join_task(RuntimeOrigin::signed(BOB), task));
+    assert_eq!(status(task), TaskStatus::Running);
+    task
+}
+
+fn status(task: H256) -> TaskStatus {
+    TaskRegistry::tasks(task).expect("task exists").status
+}
+
+fn set_status(task: H256, status: TaskStatus) {
+    Tasks::<Test>::mutate(task, |maybe_task| maybe_task.as_mut().expect("task exists").status = status);
+}
+
+fn run_migration<M: OnRuntimeUpgrade>() {
+    #[cfg(feature = "try-runtime")]
+    let state = M::pre_upgrade().expect("pre-upgrade checks pass");
+    M::on_runtime_upgrade();
+    #[cfg(feature = "try-runtime")]
+    M::post_upgrade(state).expect("post-upgrade checks pass");
+}
+
+#[test]
+fn native_bounty_is_held_until_the_task_completes() {
+    new_test_ext().execute_with(|| {
+        let task = running_task(1000);
+        assert_eq!(held(CREATOR), 1000);
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE - 1000);
+
+        assert_noop!(
+            TaskRegistry::complete_task(RuntimeOrigin::signed(CREATOR), task),
+            Error::<Test>::InvalidTaskStatus
+        );
+        set_status(task, TaskStatus::Validating);
+        assert_ok!(TaskRegistry::complete_task(RuntimeOrigin::signed(CREATOR), task));
+
+        assert_eq!(held(CREATOR), 0);
+        assert_eq!(balance(escrow_account()), 1001);
+        assert_eq!(TaskRegistry::escrow(task), Some(1000));
+        assert_eq!(<TaskRegistry as TaskEscrow<_, _, _, _>>::escrowed(&task), Some((NATIVE, 1000)));
+        assert_eq!(hook_calls(), vec![HookCall::TaskCompleted(task)]);
+
+        assert_eq!(<TaskRegistry as TaskEscrow<_, _, _, _>>::release(&task, &CHARLIE), Ok(1000));
+        assert_eq!(balance(CHARLIE), INITIAL_BALANCE + 1000);
+        assert_eq!(balance(escrow_account()), 1);
+        assert_noop!(
+            <TaskRegistry as TaskEscrow<_, _, _, _>>::release(&task, &CHARLIE),
+            Error::<Test>::NothingEscrowed
+        );
+    });
+}
+
+#[test]
+fn cancelling_releases_a_native_bounty() {
+    new_test_ext().execute_with(|| {
+        let task = create(NATIVE, 1000, HardwareRequirements::default());
+        assert_noop!(
+            TaskRegistry::create_task(
+                RuntimeOrigin::signed(CREATOR),
+                b"task".to_vec(),
+                ModelType::ResNet,
+                NATIVE,
+                MINIMUM_BOUNTY - 1,
+                1,
+                1,
+                b"Qm".to_vec(),
+                HardwareRequirements::default(),
+            ),
+            Error::<Test>::BountyTooLow
+        );
+        assert_noop!(TaskRegistry::cancel_task(RuntimeOrigin::signed(ALICE), task), Error::<Test>::NotTaskCreator);
+
+        assert_ok!(TaskRegistry::cancel_task(RuntimeOrigin::signed(CREATOR), task));
+        assert_eq!(held(CREATOR), 0);
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE);
+        assert_eq!(status(task), TaskStatus::Cancelled);
+        System::assert_last_event(Event::TaskCancelled { task_id: task, refunded_amount: 1000 }.into());
+
+        assert_noop!(
+            TaskRegistry::cancel_task(RuntimeOrigin::signed(CREATOR), task),
+            Error::<Test>::TaskAlreadyStarted
+        );
+    });
+}
+
+#[test]
+fn asset_bounties_move_through_the_escrow_account() {
+    new_test_ext().execute_with(|| {
+        let asset = NativeOrWithId::WithId(ASSET);
+        assert_noop!(
+            TaskRegistry::create_task(
+                RuntimeOrigin::signed(CREATOR),
+                b"task".to_vec(),
+                ModelType::ResNet,
+                asset.clone(),
+                500,
+                2,
+                2,
+                b"Qm".to_vec(),
+                HardwareRequirements::default(),
+            ),
+            Error::<Test>::AssetNotAccepted
+        );
+        assert_ok!(TaskRegistry::set_minimum_bounty(RuntimeOrigin::root(), asset.clone(), Some(50)));
+
+        let cancelled = create(asset.clone(), 500, HardwareRequirements::default());
+        assert_eq!(asset_balance(CREATOR), INITIAL_BALANCE - 500);
+        assert_eq!(asset_balance(escrow_account()), 500);
+        assert_eq!(held(CREATOR), 0);
+        assert_ok!(TaskRegistry::cancel_task(RuntimeOrigin::signed(CREATOR), cancelled));
+        assert_eq!(asset_balance(CREATOR), INITIAL_BALANCE);
+        assert_eq!(asset_balance(escrow_account()), 0);
+
+        let task = create(asset.clone(), 500, HardwareRequirements::default());
+        assert_ok!(TaskRegistry::start_recruiting(RuntimeOrigin::signed(CREATOR), task));
+        add_provider(ALICE, 24);
+        add_provider(BOB, 24);
+        assert_ok!(TaskRegistry::join_task(RuntimeOrigin::signed(ALICE), task));
+        assert_ok!(TaskRegistry::join_task(RuntimeOrigin::signed(BOB), task));
+
+        assert_eq!(<TaskRegistry as TaskEscrow<_, _, _, _>>::draw(&task, &ALICE, 100), Ok(asset.clone()));
+        assert_eq!(asset_balance(ALICE), 100);
+
+        set_status(task, TaskStatus::Validating);
+        assert_ok!(TaskRegistry::complete_task(RuntimeOrigin::signed(CREATOR), task));
+        assert_eq!(<TaskRegistry as TaskEscrow<_, _, _, _>>::escrowed(&task), Some((asset, 400)));
+        assert_eq!(<TaskRegistry as TaskEscrow<_, _, _, _>>::release(&task, &BOB), Ok(400));
+        assert_eq!(asset_balance(BOB), 400);
+        assert_eq!(asset_balance(escrow_account()), 0);
+    });
+}
+
+#[test]
+fn bounty_is_drawn_from_running_tasks_only() {
+    new_test_ext().execute_with(|| {
+        let pending = create(NATIVE, 1000, HardwareRequirements::default());
+        assert_noop!(
+            <TaskRegistry as TaskEscrow<_, _, _, _>>::draw(&pending, &CHARLIE, 100),
+            Error::<Test>::InvalidTaskStatus
+        );
+
+        let task = running_task(1000);
+        assert_eq!(<TaskRegistry as TaskEscrow<_, _, _, _>>::draw(&task, &CHARLIE, 300), Ok(NATIVE));
+        assert_eq!(held(CREATOR), 1700);
+        assert_eq!(balance(CHARLIE), INITIAL_BALANCE + 300);
+        assert_eq!(TaskRegistry::tasks(task).map(|t| t.bounty), Some(700));
+        assert_noop!(
+            <TaskRegistry as TaskEscrow<_, _, _, _>>::draw(&task, &CHARLIE, 701),
+            Error::<Test>::InsufficientBalance
+        );
+
+        // Only what is left of the bounty is escrowed
+        set_status(task, TaskStatus::Validating);
+        assert_ok!(TaskRegistry::complete_task(RuntimeOrigin::signed(CREATOR), task));
+        assert_eq!(TaskRegistry::escrow(task), Some(700));
+        assert_eq!(held(CREATOR), 1000);
+    });
+}
+
+#[test]
+fn joining_checks_the_provider_hardware() {
+    new_test_ext().execute_with(|| {
+        let requirements = HardwareRequirements { min_vram_gb: 16, ..Default::default() };
+        let task = create(NATIVE, 1000, requirements);
+        assert_noop!(TaskRegistry::join_task(RuntimeOrigin::signed(ALICE), task), Error::<Test>::InvalidTaskStatus);
+        assert_ok!(TaskRegistry::start_recruiting(RuntimeOrigin::signed(CREATOR), task));
+
+        assert_noop!(TaskRegistry::join_task(RuntimeOrigin::signed(ALICE), task), Error::<Test>::NotAProvider);
+        add_provider(ALICE, 8);
+        assert_noop!(
+            TaskRegistry::join_task(RuntimeOrigin::signed(ALICE), task),
+            Error::<Test>::HardwareRequirementsNotMet
+        );
+        add_provider(ALICE, 24);
+        assert_ok!(TaskRegistry::join_task(RuntimeOrigin::signed(ALICE), task));
+        assert_noop!(
+            TaskRegistry::join_task(RuntimeOrigin::signed(ALICE), task),
+            Error::<Test>::ProviderAlreadyJoined
+        );
+        assert_eq!(status(task), TaskStatus::Recruiting);
+    });
+}
+
+#[test]
+fn leaving_a_task_notifies_the_hooks() {
+    new_test_ext().execute_with(|| {
+        let task = running_task(1000);
+
+        assert_ok!(TaskRegistry::leave_task(RuntimeOrigin::signed(ALICE), task));
+        assert!(!TaskRegistry::task_providers(task, ALICE));
+        assert_eq!(hook_calls(), vec![HookCall::ProviderLeft(task, ALICE)]);
+        System::assert_last_event(Event::ProviderLeft { task_id: task, provider: ALICE }.into());
+
+        assert_noop!(TaskRegistry::leave_task(RuntimeOrigin::signed(ALICE), task), Error::<Test>::NotJoined);
+        assert_noop!(TaskRegistry::leave_task(RuntimeOrigin::signed(CHARLIE), task), Error::<Test>::NotJoined);
+    });
+}
+
+#[test]
+fn unanswered_offers_move_on_to_the_next_candidate() {
+    new_test_ext().execute_with(|| {
+        add_provider(ALICE, 24);
+        add_provider(BOB, 24);
+        let task = <Test as frame_system::Config>::Hashing::hash_of(&(CREATOR, 0u32));
+        assert_ok!(TaskRegistry::create_task(
+            RuntimeOrigin::signed(CREATOR),
+            b"task".to_vec(),
+            ModelType::ResNet,
+            NATIVE,
+            1000,
+            1,
+            1,
+            b"Qm".to_vec(),
+            HardwareRequirements::default(),
+        ));
+        assert_ok!(TaskRegistry::request_assignment(RuntimeOrigin::signed(CREATOR), task));
+        assert_eq!(TaskRegistry::assignment_queue().to_vec(), vec![task]);
+
+        run_to_block(2);
+        assert_eq!(TaskRegistry::offers(task, ALICE), Some(2 + OFFER_WINDOW));
+        assert_noop!(TaskRegistry::join_task(RuntimeOrigin::signed(BOB), task), Error::<Test>::AssignmentOnly);
+        assert_noop!(TaskRegistry::accept_offer(RuntimeOrigin::signed(BOB), task), Error::<Test>::OfferNotFound);
+
+        run_to_block(2 + OFFER_WINDOW);
+        assert!(TaskRegistry::offers(task, ALICE).is_none());
+        assert_eq!(TaskRegistry::offers(task, BOB), Some(2 + 2 * OFFER_WINDOW));
+
+        assert_ok!(TaskRegistry::accept_offer(RuntimeOrigin::signed(BOB), task));
+        assert_eq!(status(task), TaskStatus::Running);
+        assert!(TaskRegistry::assignments(task).is_none());
+        assert!(TaskRegistry::assignment_queue().is_empty());
+    });
+}
+
+fn old_task(status: TaskStatus, bounty: Balance) -> migrations::v3::OldTask<Test> {
+    migrations::v3::OldTask {
+        creator: CREATOR,
+        name: BoundedVec::truncate_from(b"task".to_vec()),
+        model_type: ModelType::Bert,
+        bounty,
+        min_providers: 1,
+        max_providers: 2,
+        status,
+        created_at: 1,
+        completed_at: None,
+        ipfs_hash: BoundedVec::truncate_from(b"Qm".to_vec()),
+        hardware_requirements: HardwareRequirements { min_vram_gb: 16, ..Default::default() },
+    }
+}
+
+#[test]
+fn migration_to_v1_requires_the_lowest_gpu_tier() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<TaskRegistry>();
+        let old = old_task(TaskStatus::Pending, 500);
+        unhashed::put(
+            &migrations::v3::Tasks::<Test>::hashed_key_for(task_id(1)),
+            &migrations::v1::OldTask::<Test> {
+                creator: old.creator,
+                name: old.name,
+                model_type: old.model_type,
+                bounty: old.bounty,
+                min_providers: old.min_providers,
+                max_providers: old.max_providers,
+                status: old.status,
+                created_at: old.created_at,
+                completed_at: old.completed_at,
+                ipfs_hash: old.ipfs_hash,
+                hardware_requirements: migrations::v1::OldHardwareRequirements {
+                    min_vram_gb: 16,
+                    min_compute_capability: 0,
+                    min_bandwidth_mbps: 0,
+                },
+            },
+        );
+
+        run_migration::<migrations::v1::MigrateToV1<Test>>();
+
+        let task = migrations::v3::Tasks::<Test>::get(task_id(1)).expect("task kept");
+        assert_eq!(task.hardware_requirements, HardwareRequirements { min_vram_gb: 16, ..Default::default() });
+        assert_eq!(StorageVersion::get::<TaskRegistry>(), StorageVersion::new(1));
+    });
+}
+
+#[test]
+fn migration_to_v2_holds_open_bounties() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(1).put::<TaskRegistry>();
+        migrations::v3::Tasks::<Test>::insert(task_id(1), old_task(TaskStatus::Running, 500));
+        migrations::v3::Tasks::<Test>::insert(task_id(2), old_task(TaskStatus::Completed, 300));
+        assert_ok!(Balances::reserve(&CREATOR, 500));
+
+        run_migration::<migrations::v2::MigrateToV2<Test, Balances>>();
+
+        assert_eq!(held(CREATOR), 500);
+        assert_eq!(balance(CREATOR), INITIAL_BALANCE - 500);
+        assert_eq!(StorageVersion::get::<TaskRegistry>(), StorageVersion::new(2));
+    });
+}
+
+#[test]
+fn migration_to_v3_escrows_completed_bounties() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(2).put::<TaskRegistry>();
+        migrations::v3::Tasks::<Test>::insert(task_id(1), old_task(TaskStatus::Running, 500));
+        migrations::v3::Tasks::<Test>::insert(task_id(2), old_task(TaskStatus::Completed, 300));
+
+        run_migration::<migrations::v3::MigrateToV3<Test>>();
+
+        assert!(TaskRegistry::escrow(task_id(1)).is_none());
+        assert_eq!(TaskRegistry::escrow(task_id(2)), Some(300));
+        assert_eq!(StorageVersion::get::<TaskRegistry>(), StorageVersion::new(3));
+    });
+}
+
+#[test]
+fn migration_to_v4_funds_existing_tasks_in_the_native_asset() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(3).put::<TaskRegistry>();
+        migrations::v3::Tasks::<Test>::insert(task_id(1), old_task(TaskStatus::Running, 500));
+
+        run_migration::<migrations::v4::MigrateToV4<Test>>();
+
+        let task = TaskRegistry::tasks(task_id(1)).expect("task kept");
+        assert_eq!((task.asset, task.bounty, task.status), (NATIVE, 500, TaskStatus::Running));
+        assert_eq!(StorageVersion::get::<TaskRegistry>(), StorageVersion::new(4));
+    });
+}
@@ -13,8 +13,8 @@ use polkadot_sdk::{
     sp_core::{self, crypto::KeyTypeId, OpaqueMetadata},
     sp_runtime::{
         self, create_runtime_str, generic, impl_opaque_keys,
-        traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
-        transaction_validity::{TransactionSource, TransactionValidity},
+        traits::{AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, Convert, IdentifyAccount, NumberFor, Verify},
+        transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
         ApplyExtrinsicResult, MultiSignature,
     },
     sp_std::{self, prelude::*},
@@ -27,7 +27,10 @@ use polkadot_sdk::{
     sp_genesis_builder::{self},
     frame_support::{
         self, construct_runtime, parameter_types, genesis_builder_helper,
-        traits::{ConstBool, ConstU32, ConstU64, ConstU8},
+        traits::{
+            fungible::{NativeFromLeft, NativeOrWithId, UnionOf},
+            AsEnsureOriginWithArg, ConstBool, ConstU32, ConstU64, ConstU8,
+        },
         weights::{
             constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_REF_TIME_PER_SECOND},
             IdentityFee, Weight,
@@ -37,6 +40,7 @@ use polkadot_sdk::{
     frame_system::{self},
     frame_system_rpc_runtime_api::{self},
     frame_executive::{self},
+    pallet_assets::{self},
     pallet_aura::{self},
     pallet_balances::{self},
     pallet_contracts::{self},
@@ -49,7 +53,7 @@ use polkadot_sdk::{
 
 // Import our custom pallets
 use pallet_task_registry;
-use pallet_provider_staking;
+use pallet_provider_staking::{self, GpuTier, TierParams};
 use pallet_reward_distribution;
 
 /// Alias to 512-bit hash when used in the context of a transaction signature on the chain
@@ -79,10 +83,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("glin"),
     impl_name: create_runtime_str!("glin"),
     authoring_version: 1,
-    spec_version: 1,
+    spec_version: 18,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
     system_version: 1,
 };
 
@@ -157,7 +161,7 @@ impl frame_system::Config for Runtime {
     type OnSetCode = ();
     type MaxConsumers = frame_support::traits::ConstU32<16>;
     type RuntimeTask = RuntimeTask;
-    type SingleBlockMigrations = ();
+    type SingleBlockMigrations = Migrations;
     type MultiBlockMigrator = ();
     type PreInherents = ();
     type PostInherents = ();
@@ -213,6 +217,44 @@ impl pallet_balances::Config for Runtime {
     type DoneSlashHandler = ();
 }
 
+parameter_types! {
+    pub const AssetDeposit: Balance = 10 * GLIN;
+    pub const AssetAccountDeposit: Balance = GLIN / 100;
+    pub const MetadataDepositBase: Balance = GLIN;
+    pub const MetadataDepositPerByte: Balance = GLIN / 100;
+    pub const ApprovalDeposit: Balance = GLIN / 100;
+}
+
+impl pallet_assets::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = Balance;
+    type AssetId = u32;
+    type AssetIdParameter = codec::Compact<u32>;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type AssetDeposit = AssetDeposit;
+    type AssetAccountDeposit = AssetAccountDeposit;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type ApprovalDeposit = ApprovalDeposit;
+    type StringLimit = ConstU32<50>;
+    type Holder = ();
+    type Freezer = ();
+    type Extra = ();
+    type CallbackHandle = ();
+    type WeightInfo = ();
+    type RemoveItemsLimit = ConstU32<1000>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+/// Asset kind of bounties and rewards: the native currency or an asset of `pallet_assets`
+pub type AssetKind = NativeOrWithId<u32>;
+
+/// Native currency and `pallet_assets` assets as one `fungibles` implementation
+pub type NativeAndAssets = UnionOf<Balances, Assets, NativeFromLeft, AssetKind, AccountId>;
+
 impl pallet_transaction_payment::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<Balances, ()>;
@@ -279,32 +321,98 @@ impl pallet_contracts::Config for Runtime {
 // Configure our custom pallets
 parameter_types! {
     pub const TaskRegistryPalletId: PalletId = PalletId(*b"py/tasks");
+    pub const NativeAsset: AssetKind = NativeOrWithId::Native;
     pub const MinimumBounty: Balance = 10 * GLIN;
     pub const MaxProvidersPerTask: u32 = 100;
+    pub const OfferWindow: BlockNumber = 10 * MINUTES;
+    pub const MaxOffersPerTask: u32 = 400;
+    pub const MaxOfferExpiriesPerBlock: u32 = 256;
+    pub const MaxPendingAssignments: u32 = 1000;
+    pub const MaxAssignmentsPerBlock: u32 = 8;
 }
 
 impl pallet_task_registry::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
     type Currency = Balances;
+    type AssetKind = AssetKind;
+    type Assets = NativeAndAssets;
+    type NativeAsset = NativeAsset;
     type MinimumBounty = MinimumBounty;
+    type AssetOrigin = frame_system::EnsureRoot<AccountId>;
     type MaxProvidersPerTask = MaxProvidersPerTask;
     type PalletId = TaskRegistryPalletId;
+    type Providers = ProviderStaking;
+    type OfferWindow = OfferWindow;
+    type MaxOffersPerTask = MaxOffersPerTask;
+    type MaxOfferExpiriesPerBlock = MaxOfferExpiriesPerBlock;
+    type MaxPendingAssignments = MaxPendingAssignments;
+    type MaxAssignmentsPerBlock = MaxAssignmentsPerBlock;
+    type Hooks = RewardDistribution;
 }
 
 parameter_types! {
-    pub const MinimumStake: Balance = 1000 * GLIN;
+    pub InitialTierTable: Vec<(GpuTier, TierParams<Balance>)> = vec![
+        (GpuTier::Consumer, TierParams { min_vram_gb: 8, min_compute_capability: 75, min_stake: 1000 * GLIN }),
+        (GpuTier::Prosumer, TierParams { min_vram_gb: 16, min_compute_capability: 86, min_stake: 5000 * GLIN }),
+        (GpuTier::Professional, TierParams { min_vram_gb: 40, min_compute_capability: 80, min_stake: 20000 * GLIN }),
+    ];
     pub const MaxProviders: u32 = 10000;
     pub const SlashPercentage: sp_runtime::Percent = sp_runtime::Percent::from_percent(10);
     pub const UnstakingPeriod: BlockNumber = 7 * DAYS;
+    pub const SlashDeferDuration: BlockNumber = 2 * DAYS;
+    pub const MaxSlashesPerBlock: u32 = 16;
+    pub const MaxSlashRecords: u32 = 1000;
+    pub const InsurancePalletId: PalletId = PalletId(*b"py/insur");
+    pub const ChallengeResponsePeriod: BlockNumber = 30 * MINUTES;
+    pub const ChallengeVerificationPeriod: BlockNumber = 2 * HOURS;
+    pub const RandomChallengeInterval: BlockNumber = 1 * HOURS;
+    pub const ChallengeBond: Balance = 10 * GLIN;
+    pub const MaxChallengesPerBlock: u32 = 16;
+    pub const MaxEndpoints: u32 = 8;
+    pub const ProviderUnsignedPriority: TransactionPriority = TransactionPriority::MAX / 2;
+    pub const HeartbeatInterval: BlockNumber = 10 * MINUTES;
+    pub const MaxGradientRounds: u32 = 100_000;
+    pub const MaxIndexScan: u32 = 500;
+    pub const BaseJailDuration: BlockNumber = DAYS;
+    pub const MaxJailDuration: BlockNumber = 28 * DAYS;
+    pub const MaxJailings: u32 = 5;
+    pub const ProbationReputation: u32 = 300;
 }
 
 impl pallet_provider_staking::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
     type Currency = Balances;
-    type MinimumStake = MinimumStake;
     type MaxProviders = MaxProviders;
     type SlashPercentage = SlashPercentage;
     type UnstakingPeriod = UnstakingPeriod;
+    type SlashDeferDuration = SlashDeferDuration;
+    type MaxSlashesPerBlock = MaxSlashesPerBlock;
+    type MaxSlashRecords = MaxSlashRecords;
+    type GovernanceOrigin = frame_system::EnsureRoot<AccountId>;
+    type InitialTierTable = InitialTierTable;
+    type OnSlash = pallet_provider_staking::DepositToInsurancePool<Runtime>;
+    type SlashHooks = RewardDistribution;
+    type InsurancePalletId = InsurancePalletId;
+    type Tasks = TaskRegistry;
+    type Randomness = RandomnessCollectiveFlip;
+    type VerifierOrigin = frame_system::EnsureRoot<AccountId>;
+    type ChallengeResponsePeriod = ChallengeResponsePeriod;
+    type ChallengeVerificationPeriod = ChallengeVerificationPeriod;
+    type RandomChallengeInterval = RandomChallengeInterval;
+    type ChallengeBond = ChallengeBond;
+    type MaxChallengesPerBlock = MaxChallengesPerBlock;
+    type MaxEndpoints = MaxEndpoints;
+    type ProviderKey = pallet_provider_staking::crypto::Public;
+    type UnsignedPriority = ProviderUnsignedPriority;
+    type HeartbeatInterval = HeartbeatInterval;
+    type MaxGradientRounds = MaxGradientRounds;
+    type MaxIndexScan = MaxIndexScan;
+    type BaseJailDuration = BaseJailDuration;
+    type MaxJailDuration = MaxJailDuration;
+    type MaxJailings = MaxJailings;
+    type ProbationReputation = ProbationReputation;
 }
 
 parameter_types! {
@@ -312,17 +420,70 @@ parameter_types! {
     pub const MaxProvidersPerBatch: u32 = 1000;
     pub const MinimumReward: Balance = GLIN / 100; // 0.01 GLIN
     pub const SettlementPeriod: BlockNumber = 100; // ~10 minutes
-    pub const PlatformFeePercentage: sp_runtime::Permill = sp_runtime::Permill::from_percent(2);
+    pub const ProofClaimPeriod: BlockNumber = 7 * DAYS;
+    pub const DefaultPlatformFee: sp_runtime::Permill = sp_runtime::Permill::from_percent(2);
+    pub const MaxPlatformFee: sp_runtime::Permill = sp_runtime::Permill::from_percent(10);
+    pub TreasuryAccount: AccountId = PalletId(*b"py/trsry").into_account_truncating();
+    pub ValidatorFeePot: AccountId = PalletId(*b"py/valfe").into_account_truncating();
+    pub StakerFeePot: AccountId = PalletId(*b"py/stkfe").into_account_truncating();
+    pub const MaxQueuedBatches: u32 = 1_000;
+    pub const CoordinatorBondRatio: sp_runtime::Permill = sp_runtime::Permill::from_percent(5);
+    pub const MinCoordinatorBond: Balance = 10 * GLIN;
+    pub const ImmediateRelease: sp_runtime::Permill = sp_runtime::Permill::from_percent(25);
+    pub const VestingPeriod: BlockNumber = 30 * DAYS;
+    pub const VestingThreshold: Balance = 100 * GLIN;
+    pub const MaxVestingSchedules: u32 = 32;
+    pub const BatchChallengeBond: Balance = 50 * GLIN;
+    pub const MaxStreamsPerProvider: u32 = 32;
+}
+
+/// Reward multiplier of each GPU tier (100 = 1.0x)
+pub struct TierRewardMultiplier;
+
+impl Convert<GpuTier, u32> for TierRewardMultiplier {
+    fn convert(tier: GpuTier) -> u32 {
+        match tier {
+            GpuTier::Consumer => 100,
+            GpuTier::Prosumer => 125,
+            GpuTier::Professional => 150,
+        }
+    }
 }
 
 impl pallet_reward_distribution::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
     type Currency = Balances;
+    type AssetKind = AssetKind;
+    type Assets = NativeAndAssets;
+    type NativeAsset = NativeAsset;
     type MaxProvidersPerBatch = MaxProvidersPerBatch;
     type MinimumReward = MinimumReward;
     type PalletId = RewardPalletId;
     type SettlementPeriod = SettlementPeriod;
-    type PlatformFeePercentage = PlatformFeePercentage;
+    type ProofClaimPeriod = ProofClaimPeriod;
+    type DefaultPlatformFee = DefaultPlatformFee;
+    type MaxPlatformFee = MaxPlatformFee;
+    type FeeOrigin = frame_system::EnsureRoot<AccountId>;
+    type TreasuryAccount = TreasuryAccount;
+    type ValidatorFeePot = ValidatorFeePot;
+    type StakerFeePot = StakerFeePot;
+    type Providers = ProviderStaking;
+    type Tasks = TaskRegistry;
+    type TierMultiplier = TierRewardMultiplier;
+    type MaxQueuedBatches = MaxQueuedBatches;
+    type CoordinatorBondRatio = CoordinatorBondRatio;
+    type MinCoordinatorBond = MinCoordinatorBond;
+    type BatchChallengeBond = BatchChallengeBond;
+    type ArbiterOrigin = frame_system::EnsureRoot<AccountId>;
+    type ImmediateRelease = ImmediateRelease;
+    type VestingPeriod = VestingPeriod;
+    type VestingThreshold = VestingThreshold;
+    type MaxVestingSchedules = MaxVestingSchedules;
+    type Contributions = ProviderStaking;
+    type MaxStreamsPerTask = MaxProvidersPerTask;
+    type MaxStreamsPerProvider = MaxStreamsPerProvider;
+    type Stats = ProviderStaking;
 }
 
 // Construct the runtime
@@ -337,6 +498,7 @@ construct_runtime!(
         Sudo: pallet_sudo,
         RandomnessCollectiveFlip: pallet_insecure_randomness_collective_flip,
         Contracts: pallet_contracts,
+        Assets: pallet_assets,
 
         // Our custom pallets
         TaskRegistry: pallet_task_registry,
@@ -369,6 +531,27 @@ pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, RuntimeCall,
 /// The address type
 pub type Address = sp_runtime::MultiAddress<AccountId, ()>;
 
+/// Storage migrations applied on runtime upgrade
+pub type Migrations = (
+    pallet_provider_staking::migrations::v1::MigrateToV1<Runtime>,
+    pallet_provider_staking::migrations::v2::MigrateToV2<Runtime>,
+    pallet_provider_staking::migrations::v3::MigrateToV3<Runtime>,
+    pallet_provider_staking::migrations::v4::MigrateToV4<Runtime>,
+    pallet_task_registry::migrations::v1::MigrateToV1<Runtime>,
+    pallet_provider_staking::migrations::v5::MigrateToV5<Runtime, Balances>,
+    pallet_task_registry::migrations::v2::MigrateToV2<Runtime, Balances>,
+    pallet_provider_staking::migrations::v6::MigrateToV6<Runtime>,
+    pallet_task_registry::migrations::v3::MigrateToV3<Runtime>,
+    pallet_reward_distribution::migrations::v1::MigrateToV1<Runtime>,
+    pallet_task_registry::migrations::v4::MigrateToV4<Runtime>,
+    pallet_reward_distribution::migrations::v2::MigrateToV2<Runtime>,
+    pallet_provider_staking::migrations::v7::MigrateToV7<Runtime>,
+    pallet_provider_staking::migrations::v8::MigrateToV8<Runtime>,
+    pallet_reward_distribution::migrations::v3::MigrateToV3<Runtime>,
+    pallet_provider_staking::migrations::v9::MigrateToV9<Runtime>,
+    pallet_reward_distribution::migrations::v4::MigrateToV4<Runtime>,
+);
+
 /// Executive: handles dispatch to the various modules
 pub type Executive = frame_executive::Executive<
     Runtime,
@@ -587,6 +770,30 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_provider_staking_runtime_api::ProviderStakingApi<Block, AccountId> for Runtime {
+        fn reachable_providers(
+            status: Option<pallet_provider_staking::ProviderStatus>,
+            requirements: pallet_provider_staking::HardwareRequirements,
+        ) -> Vec<pallet_provider_staking::ReachableProvider<AccountId>> {
+            ProviderStaking::reachable_providers(status, requirements)
+        }
+
+        fn top_providers(
+            requirements: pallet_provider_staking::HardwareRequirements,
+            count: u32,
+        ) -> Vec<AccountId> {
+            ProviderStaking::top_providers(&requirements, count)
+        }
+    }
+
+    impl pallet_provider_staking_runtime_api::ProviderEarningsApi<Block, AccountId, Hash, Balance, BlockNumber> for Runtime {
+        fn earnings_history(
+            provider: AccountId,
+        ) -> Vec<(Hash, pallet_provider_staking::TaskEarnings<Balance, BlockNumber>)> {
+            ProviderStaking::earnings_history(&provider)
+        }
+    }
+
     impl sp_genesis_builder::GenesisBuilder<Block> for Runtime {
         fn build_state(config: Vec<u8>) -> sp_genesis_builder::Result {
             genesis_builder_helper::build_state::<RuntimeGenesisConfig>(config)